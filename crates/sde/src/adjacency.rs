@@ -0,0 +1,251 @@
+use crate::objects::{SolarSystem, Universe};
+use std::collections::{BTreeSet, HashMap};
+
+/// Kind of area used to build an AdjacencyGraph
+#[derive(Hash, PartialEq, Eq, Clone, Copy)]
+pub enum AreaKind {
+    /// Areas are Regions
+    Region,
+    /// Areas are Constellations
+    Constellation,
+}
+
+/// Stargate that crosses the border between two areas, seen from one of them
+#[derive(Hash, PartialEq, Eq, Clone, PartialOrd, Ord)]
+pub struct BorderGate {
+    /// Identifier of the connection, the lowest and the highest Solar System identifiers
+    /// joined by a dash like the `systemConnectionId` written by the importer
+    pub id: String,
+    /// Solar System inside the area
+    pub system: u32,
+    /// Solar System on the other side of the border
+    pub destination: u32,
+}
+
+impl BorderGate {
+    /// Creates a new BorderGate from the Solar System inside the area to the one outside
+    pub fn new(system: u32, destination: u32) -> Self {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        BorderGate {
+            id: format!("{}-{}", system.min(destination), system.max(destination)),
+            system,
+            destination,
+        }
+    }
+}
+
+/// Regions or Constellations that share stargates, with the gates that connect them.
+///
+/// Every gate is stored on both sides of the border, so the gates of an area always
+/// have their `system` inside of it.
+pub struct AdjacencyGraph {
+    /// Kind of the areas of the graph
+    pub kind: AreaKind,
+    // area -> neighbour area -> gates between them
+    areas: HashMap<u32, HashMap<u32, Vec<BorderGate>>>,
+}
+
+impl AdjacencyGraph {
+    /// Builds the graph from the stargate connections of the Solar Systems of the
+    /// Universe. Every area of the Universe is in the graph, even without neighbours.
+    pub fn new(universe: &Universe, kind: AreaKind) -> Self {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let area_of = |system: &SolarSystem| match kind {
+            AreaKind::Region => system.region,
+            AreaKind::Constellation => system.constellation,
+        };
+        let ids: Vec<u32> = match kind {
+            AreaKind::Region => universe.regions.keys().copied().collect(),
+            AreaKind::Constellation => universe.constellations.keys().copied().collect(),
+        };
+        let mut areas: HashMap<u32, HashMap<u32, Vec<BorderGate>>> =
+            ids.into_iter().map(|id| (id, HashMap::new())).collect();
+
+        for system in universe.solar_systems.values() {
+            let area = area_of(system);
+            for connection in &system.connections {
+                let Some(destination) = universe.solar_systems.get(connection) else {
+                    continue;
+                };
+                let neighbour = area_of(destination);
+                if neighbour != area {
+                    areas
+                        .entry(area)
+                        .or_default()
+                        .entry(neighbour)
+                        .or_default()
+                        .push(BorderGate::new(system.id, destination.id));
+                }
+            }
+        }
+        for neighbours in areas.values_mut() {
+            for gates in neighbours.values_mut() {
+                gates.sort_unstable();
+                gates.dedup();
+            }
+        }
+        AdjacencyGraph { kind, areas }
+    }
+
+    /// Returns the areas that share at least one stargate with the area, sorted by
+    /// their identifier
+    pub fn neighbours(&self, area: u32) -> Vec<u32> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let mut result: Vec<u32> = self
+            .areas
+            .get(&area)
+            .map(|neighbours| neighbours.keys().copied().collect())
+            .unwrap_or_default();
+        result.sort_unstable();
+        result
+    }
+
+    /// Returns true if there is a stargate between the two areas
+    pub fn are_adjacent(&self, area: u32, other: u32) -> bool {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        self.areas
+            .get(&area)
+            .is_some_and(|neighbours| neighbours.contains_key(&other))
+    }
+
+    /// Returns the stargates from the area to the other one, seen from the first area
+    pub fn gates_between(&self, area: u32, other: u32) -> &[BorderGate] {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        self.areas
+            .get(&area)
+            .and_then(|neighbours| neighbours.get(&other))
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Returns every stargate that leaves the area, sorted by neighbour and gate
+    pub fn gates(&self, area: u32) -> Vec<&BorderGate> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        self.neighbours(area)
+            .into_iter()
+            .flat_map(|neighbour| self.gates_between(area, neighbour))
+            .collect()
+    }
+
+    /// Returns the Solar Systems of the area that have a stargate to another area,
+    /// sorted by their identifier
+    pub fn border_systems(&self, area: u32) -> Vec<u32> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        self.gates(area)
+            .into_iter()
+            .map(|gate| gate.system)
+            .collect::<BTreeSet<u32>>()
+            .into_iter()
+            .collect()
+    }
+
+    /// Returns the identifiers of every area in the graph, sorted
+    pub fn areas(&self) -> Vec<u32> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let mut result: Vec<u32> = self.areas.keys().copied().collect();
+        result.sort_unstable();
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{system, universe};
+
+    // The Forge (Kimotoro, Otsela) and Lonetrek (Mahtista), with a Region without gates
+    fn areas() -> Universe {
+        let mut maurasi = system(30000140, 0.9);
+        maurasi.constellation = 20000019;
+        let mut airaken = system(30002813, 0.5);
+        airaken.constellation = 20000410;
+        airaken.region = 10000016;
+        let mut isolated = system(30000001, 0.5);
+        isolated.constellation = 20000001;
+        isolated.region = 10000001;
+        universe(
+            vec![
+                system(30000142, 0.9),
+                system(30000144, 0.9),
+                maurasi,
+                airaken,
+                isolated,
+            ],
+            &[
+                (30000142, 30000144),
+                (30000142, 30000140),
+                (30000144, 30000140),
+                (30000140, 30002813),
+            ],
+        )
+    }
+
+    #[test]
+    fn finds_the_neighbour_regions() {
+        let graph = AdjacencyGraph::new(&areas(), AreaKind::Region);
+        assert_eq!(graph.areas(), vec![10000001, 10000002, 10000016]);
+        assert_eq!(graph.neighbours(10000002), vec![10000016]);
+        assert_eq!(graph.neighbours(10000016), vec![10000002]);
+        assert!(graph.neighbours(10000001).is_empty());
+        assert!(graph.are_adjacent(10000002, 10000016));
+        assert!(!graph.are_adjacent(10000002, 10000001));
+        assert!(graph.neighbours(10000099).is_empty());
+    }
+
+    #[test]
+    fn gates_are_seen_from_both_sides() {
+        let graph = AdjacencyGraph::new(&areas(), AreaKind::Region);
+        let gates = graph.gates_between(10000002, 10000016);
+        assert_eq!(gates.len(), 1);
+        assert_eq!(gates[0].id, "30000140-30002813");
+        assert_eq!(
+            (gates[0].system, gates[0].destination),
+            (30000140, 30002813)
+        );
+        let gates = graph.gates_between(10000016, 10000002);
+        assert_eq!(gates[0].id, "30000140-30002813");
+        assert_eq!(
+            (gates[0].system, gates[0].destination),
+            (30002813, 30000140)
+        );
+        assert!(graph.gates_between(10000002, 10000001).is_empty());
+    }
+
+    #[test]
+    fn finds_the_border_systems_of_constellations() {
+        let graph = AdjacencyGraph::new(&areas(), AreaKind::Constellation);
+        assert_eq!(graph.neighbours(20000020), vec![20000019]);
+        assert_eq!(graph.neighbours(20000019), vec![20000020, 20000410]);
+        assert_eq!(graph.border_systems(20000020), vec![30000142, 30000144]);
+        assert_eq!(graph.border_systems(20000019), vec![30000140]);
+        let ids: Vec<&str> = graph
+            .gates(20000019)
+            .into_iter()
+            .map(|gate| gate.id.as_str())
+            .collect();
+        assert_eq!(
+            ids,
+            vec![
+                "30000140-30000142",
+                "30000140-30000144",
+                "30000140-30002813"
+            ]
+        );
+    }
+}
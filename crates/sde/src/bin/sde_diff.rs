@@ -0,0 +1,61 @@
+//! Compares two SDE sqlite databases, usually the one in use and a new one built by
+//! `sde-import`, and reports what changed.
+//!
+//! Usage: `sde-diff <old.db> <new.db> [--json] [--tolerance units]`
+//!
+//! The report lists added, removed and renamed Solar Systems, renamed Regions, changed
+//! stargate connections and Solar Systems moved in the abstract maps. With `--json` it is
+//! printed as JSON instead of text.
+use sde::diff::{DEFAULT_TOLERANCE, SdeDiff};
+use std::path::Path;
+use std::process::ExitCode;
+
+const USAGE: &str = "Usage: sde-diff <old.db> <new.db> [--json] [--tolerance units]";
+
+fn main() -> ExitCode {
+    let mut paths = Vec::new();
+    let mut json = false;
+    let mut tolerance = DEFAULT_TOLERANCE;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--json" => json = true,
+            "--tolerance" => match args.next().and_then(|value| value.parse().ok()) {
+                Some(value) => tolerance = value,
+                None => {
+                    eprintln!("{USAGE}");
+                    return ExitCode::FAILURE;
+                }
+            },
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                return ExitCode::SUCCESS;
+            }
+            _ => paths.push(arg),
+        }
+    }
+    if paths.len() != 2 {
+        eprintln!("{USAGE}");
+        return ExitCode::FAILURE;
+    }
+
+    let diff = match SdeDiff::compare(Path::new(&paths[0]), Path::new(&paths[1]), tolerance) {
+        Ok(diff) => diff,
+        Err(t_error) => {
+            eprintln!("Error comparing the databases: {t_error}");
+            return ExitCode::FAILURE;
+        }
+    };
+    if json {
+        match diff.to_json() {
+            Ok(text) => println!("{text}"),
+            Err(t_error) => {
+                eprintln!("Error writing the JSON report: {t_error}");
+                return ExitCode::FAILURE;
+            }
+        }
+    } else {
+        print!("{diff}");
+    }
+    ExitCode::SUCCESS
+}
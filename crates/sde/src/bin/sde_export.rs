@@ -0,0 +1,99 @@
+//! Writes the Solar Systems and stargates of an SDE sqlite database in a graph or data
+//! format, for other tools.
+//!
+//! Usage: `sde-export <sde.db> <dot|graphml|geojson|json> [--abstract] [--region id|name]...
+//! [--output file]`
+//!
+//! Every Region is exported unless `--region` is given, it can be repeated. With
+//! `--abstract` the coordinates are the ones of the abstract region maps instead of the
+//! projected ones, and the Solar Systems without them are left out. The export is printed
+//! unless `--output` is given.
+use sde::SdeManager;
+use sde::export::{ExportCoordinates, ExportFormat};
+use std::fs::write;
+use std::path::Path;
+use std::process::ExitCode;
+
+const USAGE: &str = "Usage: sde-export <sde.db> <dot|graphml|geojson|json> [--abstract] \
+                     [--region id|name]... [--output file]";
+
+fn main() -> ExitCode {
+    let mut arguments = Vec::new();
+    let mut coordinates = ExportCoordinates::Projected;
+    let mut region_names = Vec::new();
+    let mut output = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--abstract" => coordinates = ExportCoordinates::Abstract,
+            "--region" | "--output" => match args.next() {
+                Some(value) if arg == "--region" => region_names.push(value),
+                Some(value) => output = Some(value),
+                None => {
+                    eprintln!("{USAGE}");
+                    return ExitCode::FAILURE;
+                }
+            },
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                return ExitCode::SUCCESS;
+            }
+            _ => arguments.push(arg),
+        }
+    }
+    if arguments.len() != 2 {
+        eprintln!("{USAGE}");
+        return ExitCode::FAILURE;
+    }
+    let Some(format) = ExportFormat::from_name(&arguments[1]) else {
+        eprintln!("Unknown format {}\n{USAGE}", arguments[1]);
+        return ExitCode::FAILURE;
+    };
+
+    let mut manager = match SdeManager::open(Path::new(&arguments[0]), 1) {
+        Ok(manager) => manager,
+        Err(t_error) => {
+            eprintln!("Error opening the SDE database: {t_error}");
+            return ExitCode::FAILURE;
+        }
+    };
+    if let Err(t_error) = manager.get_universe() {
+        eprintln!("Error reading the universe: {t_error}");
+        return ExitCode::FAILURE;
+    }
+    let mut regions = Vec::new();
+    for name in &region_names {
+        let region = manager.universe.regions.values().find(|region| {
+            region.id.to_string() == *name || region.name.eq_ignore_ascii_case(name)
+        });
+        match region {
+            Some(region) => regions.push(region.id),
+            None => {
+                eprintln!("Region {name} not found");
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+
+    let text = match manager
+        .get_export(regions, coordinates)
+        .map_err(|t_error| t_error.to_string())
+        .and_then(|export| export.write(format).map_err(|t_error| t_error.to_string()))
+    {
+        Ok(text) => text,
+        Err(t_error) => {
+            eprintln!("Error exporting the universe: {t_error}");
+            return ExitCode::FAILURE;
+        }
+    };
+    match output {
+        Some(path) => {
+            if let Err(t_error) = write(&path, text) {
+                eprintln!("Error writing {path}: {t_error}");
+                return ExitCode::FAILURE;
+            }
+        }
+        None => print!("{text}"),
+    }
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,107 @@
+//! Builds the SDE sqlite database used by Telescope from CCP's official static data export.
+//!
+//! Usage: `sde-import <export.zip|export folder> [destination.db] [--format jsonl|yaml]
+//! [--statics statics.csv] [--layouts]`
+//!
+//! With `--layouts` the abstract map of every region is generated after the import.
+use sde::SdeManager;
+use sde::importer::{SdeFormat, SdeImporter};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+const USAGE: &str = "Usage: sde-import <export.zip|export folder> [destination.db] \
+                     [--format jsonl|yaml] [--statics statics.csv] [--layouts]";
+
+fn main() -> ExitCode {
+    let mut paths = Vec::new();
+    let mut format = None;
+    let mut statics = None;
+    let mut layouts = false;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--format" => {
+                format = match args.next().as_deref() {
+                    Some("jsonl") => Some(SdeFormat::JsonLines),
+                    Some("yaml") => Some(SdeFormat::Yaml),
+                    _ => {
+                        eprintln!("{USAGE}");
+                        return ExitCode::FAILURE;
+                    }
+                };
+            }
+            "--statics" => match args.next() {
+                Some(path) => statics = Some(PathBuf::from(path)),
+                None => {
+                    eprintln!("{USAGE}");
+                    return ExitCode::FAILURE;
+                }
+            },
+            "--layouts" => layouts = true,
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                return ExitCode::SUCCESS;
+            }
+            _ => paths.push(arg),
+        }
+    }
+    if paths.is_empty() || paths.len() > 2 {
+        eprintln!("{USAGE}");
+        return ExitCode::FAILURE;
+    }
+    let destination = paths
+        .get(1)
+        .cloned()
+        .unwrap_or(String::from("assets/sde.db"));
+
+    let mut importer = SdeImporter::new(Path::new(&paths[0]));
+    importer.format = format;
+    importer.statics = statics;
+    match importer.import(Path::new(&destination)) {
+        Ok(summary) => {
+            println!("SDE imported into {destination}");
+            println!("  regions:        {}", summary.regions);
+            println!("  constellations: {}", summary.constellations);
+            println!("  solar systems:  {}", summary.solar_systems);
+            println!("  connections:    {}", summary.connections);
+            println!("  planets:        {}", summary.planets);
+            println!("  moons:          {}", summary.moons);
+            println!("  stations:       {}", summary.stations);
+            println!("  services:       {}", summary.services);
+            println!("  j-space:        {}", summary.wormhole_systems);
+            println!("  statics:        {}", summary.statics);
+            println!("  factions:       {}", summary.factions);
+            println!("  categories:     {}", summary.categories);
+            println!("  groups:         {}", summary.groups);
+            println!("  types:          {}", summary.types);
+        }
+        Err(t_error) => {
+            eprintln!("Error importing the SDE: {t_error}");
+            return ExitCode::FAILURE;
+        }
+    }
+
+    if layouts {
+        let sde = SdeManager::new(Path::new(&destination), 1);
+        let regions = match sde.get_region(Vec::new(), None) {
+            Ok(regions) => regions,
+            Err(t_error) => {
+                eprintln!("Error reading the regions: {t_error}");
+                return ExitCode::FAILURE;
+            }
+        };
+        let mut count = 0;
+        for region in regions.values() {
+            if region.constellations.is_empty() {
+                continue;
+            }
+            if let Err(t_error) = sde.generate_abstract_layout(region.id, true) {
+                eprintln!("Error generating the layout of {}: {t_error}", region.name);
+                return ExitCode::FAILURE;
+            }
+            count += 1;
+        }
+        println!("  layouts:        {count}");
+    }
+    ExitCode::SUCCESS
+}
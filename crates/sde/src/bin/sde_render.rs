@@ -0,0 +1,147 @@
+//! Draws the universe map or the abstract map of a region of an SDE sqlite database as an
+//! SVG image, without opening a window.
+//!
+//! Usage: `sde-render <sde.db> <output.svg> [--region id|name] [--intel system]...
+//! [--character name@system]... [--route origin:destination]... [--width pixels]`
+//!
+//! Without `--region` the universe map is drawn. Solar Systems can be given by name or
+//! identifier. Intel hits are circled in red, characters are marked in green with their
+//! name and routes are drawn in yellow following the shortest stargate route.
+use sde::SdeManager;
+use sde::objects::{SdeLine, Universe};
+use sde::route::RouteMode;
+use sde::svg::SvgRenderer;
+use std::collections::HashSet;
+use std::fs::write;
+use std::path::Path;
+use std::process::ExitCode;
+
+const USAGE: &str = "Usage: sde-render <sde.db> <output.svg> [--region id|name] \
+                     [--intel system]... [--character name@system]... \
+                     [--route origin:destination]... [--width pixels]";
+
+fn main() -> ExitCode {
+    let mut paths = Vec::new();
+    let mut options: Vec<(String, String)> = Vec::new();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--region" | "--intel" | "--character" | "--route" | "--width" => match args.next() {
+                Some(value) => options.push((arg, value)),
+                None => {
+                    eprintln!("{USAGE}");
+                    return ExitCode::FAILURE;
+                }
+            },
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                return ExitCode::SUCCESS;
+            }
+            _ => paths.push(arg),
+        }
+    }
+    if paths.len() != 2 {
+        eprintln!("{USAGE}");
+        return ExitCode::FAILURE;
+    }
+    match render(Path::new(&paths[0]), &options) {
+        Ok(svg) => {
+            if let Err(t_error) = write(&paths[1], svg) {
+                eprintln!("Error writing {}: {t_error}", paths[1]);
+                return ExitCode::FAILURE;
+            }
+            ExitCode::SUCCESS
+        }
+        Err(t_error) => {
+            eprintln!("{t_error}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn render(path: &Path, options: &[(String, String)]) -> Result<String, String> {
+    let mut manager = SdeManager::open(path, 1).map_err(|t_error| t_error.to_string())?;
+    manager
+        .get_universe()
+        .map_err(|t_error| t_error.to_string())?;
+    let universe = manager.share_universe();
+
+    let mut renderer = SvgRenderer::new();
+    let mut region = None;
+    for (option, value) in options {
+        match option.as_str() {
+            "--region" => {
+                let found = universe.regions.values().find(|region| {
+                    region.id.to_string() == *value || region.name.eq_ignore_ascii_case(value)
+                });
+                region = Some(found.ok_or(format!("Region {value} not found"))?);
+            }
+            "--intel" => renderer
+                .highlights
+                .intel
+                .push(find_system(&universe, value)?),
+            "--character" => {
+                let (name, system) = value
+                    .rsplit_once('@')
+                    .ok_or(format!("Character {value} is not name@system"))?;
+                renderer
+                    .highlights
+                    .characters
+                    .push((name.to_string(), find_system(&universe, system)?));
+            }
+            "--route" => {
+                let (origin, destination) = value
+                    .split_once(':')
+                    .ok_or(format!("Route {value} is not origin:destination"))?;
+                let origin = find_system(&universe, origin)?;
+                let destination = find_system(&universe, destination)?;
+                let route = universe
+                    .get_route(
+                        origin,
+                        destination,
+                        RouteMode::Shortest,
+                        &HashSet::new(),
+                        &[],
+                    )
+                    .ok_or(format!("No stargate route for {value}"))?;
+                renderer.highlights.routes.push(route);
+            }
+            "--width" => {
+                renderer.width = value
+                    .parse()
+                    .map_err(|_| format!("Width {value} is not a number"))?;
+            }
+            _ => {}
+        }
+    }
+
+    let data = match region {
+        Some(region) => {
+            renderer.title = Some(region.name.clone());
+            manager.get_region_map(region.id)
+        }
+        None => {
+            // the universe is too dense for the system names, the regions are named instead
+            renderer.system_labels = false;
+            renderer.node_radius = 1.5;
+            for area in universe.get_region_areas() {
+                let center = SdeLine::new(area.min, area.max).midpoint();
+                renderer
+                    .labels
+                    .push((area.name, center.to_rawpoint().components));
+            }
+            manager.get_universe_map()
+        }
+    }
+    .map_err(|t_error| t_error.to_string())?;
+    Ok(renderer.render(&data))
+}
+
+fn find_system(universe: &Universe, text: &str) -> Result<u32, String> {
+    universe
+        .solar_systems
+        .values()
+        .find(|system| system.id.to_string() == text || system.name.eq_ignore_ascii_case(text))
+        .map(|system| system.id)
+        .ok_or(format!("Solar System {text} not found"))
+}
@@ -0,0 +1,95 @@
+use crate::cache::MapData;
+use egui_map::map::objects::{MapLine, RawPoint};
+
+/// Prefix of the identifiers of the lines drawn for jump bridges
+pub const BRIDGE_LINE_PREFIX: &str = "bridge:";
+/// Number of pieces a jump bridge line is split in, every other piece is drawn
+const BRIDGE_DASHES: usize = 9;
+
+impl MapData {
+    /// Adds the jump bridges as dashed lines between the points of the map, so they do
+    /// not look like stargates. Bridges with an end that is not in the map are skipped.
+    pub fn add_bridges(&mut self, bridges: &[(u32, u32)]) {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        for &(system_a, system_b) in bridges {
+            let (Some(point_a), Some(point_b)) = (
+                self.points.get(&(system_a as usize)),
+                self.points.get(&(system_b as usize)),
+            ) else {
+                continue;
+            };
+            let start = point_a.raw_point.components;
+            let end = point_b.raw_point.components;
+            let along = |piece: usize| {
+                let share = piece as f32 / BRIDGE_DASHES as f32;
+                RawPoint::new(
+                    start[0] + (end[0] - start[0]) * share,
+                    start[1] + (end[1] - start[1]) * share,
+                )
+            };
+            let mut line_ids = Vec::new();
+            for piece in (0..BRIDGE_DASHES).step_by(2) {
+                let id = format!(
+                    "{BRIDGE_LINE_PREFIX}{}-{}:{piece}",
+                    system_a.min(system_b),
+                    system_a.max(system_b)
+                );
+                let mut line = MapLine::new(along(piece), along(piece + 1));
+                line.id = Some(id.clone());
+                self.lines.insert(id.clone(), line);
+                line_ids.push(id);
+            }
+            for system in [system_a, system_b] {
+                if let Some(point) = self.points.get_mut(&(system as usize)) {
+                    point.connections.extend(line_ids.iter().cloned());
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use egui_map::map::objects::MapPoint;
+
+    fn map() -> MapData {
+        let mut data = MapData::new();
+        data.points
+            .insert(1, MapPoint::new(1, RawPoint::new(0.0, 0.0)));
+        data.points
+            .insert(2, MapPoint::new(2, RawPoint::new(90.0, 18.0)));
+        data
+    }
+
+    #[test]
+    fn bridges_are_dashed_lines() {
+        let mut data = map();
+        data.add_bridges(&[(2, 1)]);
+        assert_eq!(data.lines.len(), BRIDGE_DASHES.div_ceil(2));
+        let first = &data.lines["bridge:1-2:0"];
+        assert_eq!(first.raw_line.points[0].components, [90.0, 18.0]);
+        assert_eq!(first.raw_line.points[1].components, [80.0, 16.0]);
+        let last = &data.lines["bridge:1-2:8"];
+        assert_eq!(last.raw_line.points[1].components, [0.0, 0.0]);
+        for system in [1, 2] {
+            let connections = &data.points[&system].connections;
+            assert_eq!(connections.len(), data.lines.len());
+            assert!(
+                connections
+                    .iter()
+                    .all(|id| id.starts_with(BRIDGE_LINE_PREFIX))
+            );
+        }
+    }
+
+    #[test]
+    fn bridges_out_of_the_map_are_skipped() {
+        let mut data = map();
+        data.add_bridges(&[(1, 3), (4, 5)]);
+        assert!(data.lines.is_empty());
+        assert!(data.points[&1].connections.is_empty());
+    }
+}
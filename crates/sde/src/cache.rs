@@ -0,0 +1,781 @@
+use crate::objects::{
+    Constellation, Faction, Moon, Planet, Region, SdeLine, SdePoint, SolarSystem, Station,
+    StationService, Universe,
+};
+use crate::wormhole::{WormholeClass, WormholeEffect, WormholeStatic};
+use egui_map::map::objects::{MapLine, MapPoint, RawPoint};
+use std::collections::HashMap;
+use std::fs::{File, rename};
+use std::hash::Hash;
+use std::io::{Read, Write};
+use std::path::Path;
+
+/// Version of the cache file format, files with another version are ignored
+pub const CACHE_VERSION: u32 = 3;
+/// Bytes at the start of every cache file
+const MAGIC: &[u8; 4] = b"TSDC";
+
+/// Map points and lines of a pane, as they are given to the map widget
+#[derive(Clone)]
+pub struct MapData {
+    /// Solar System points with the identifiers of their lines
+    pub points: HashMap<usize, MapPoint>,
+    /// Lines between Solar Systems
+    pub lines: HashMap<String, MapLine>,
+}
+
+impl MapData {
+    /// Creates a new empty MapData
+    pub fn new() -> Self {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        MapData {
+            points: HashMap::new(),
+            lines: HashMap::new(),
+        }
+    }
+}
+
+impl Default for MapData {
+    fn default() -> Self {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        Self::new()
+    }
+}
+
+/// On-disk cache of the data read from the SDE database, so it does not need to be read
+/// again on every start.
+///
+/// The cache stores the SHA-256 hash of the `sde.db` it was built from and it is ignored
+/// when the hash of the current database is a different one. The maps are stored with
+/// the factor used to build them, a map built with another factor is not returned.
+pub struct SdeCache {
+    /// SHA-256 hash of the SDE database the data was read from
+    pub sde_hash: [u8; 32],
+    /// Map of the whole universe, with the factor used to build it
+    pub universe_map: Option<(i64, MapData)>,
+    /// Abstract maps of the regions, with the factor used to build them
+    pub region_maps: HashMap<u32, (i64, MapData)>,
+}
+
+impl SdeCache {
+    /// Creates a new empty SdeCache for the SDE database with the given hash
+    pub fn new(sde_hash: [u8; 32]) -> Self {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        SdeCache {
+            sde_hash,
+            universe_map: None,
+            region_maps: HashMap::new(),
+        }
+    }
+
+    /// Reads the cache file and returns it with the cached Universe. It returns `None`
+    /// when the file does not exist, it is damaged, it has another format version or it
+    /// was built from another SDE database.
+    pub fn load(path: &Path, sde_hash: &[u8; 32]) -> Option<(SdeCache, Universe)> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let mut data = Vec::new();
+        File::open(path).ok()?.read_to_end(&mut data).ok()?;
+        let mut reader = Reader::new(&data);
+        if reader.take(MAGIC.len())? != MAGIC || u32::read(&mut reader)? != CACHE_VERSION {
+            return None;
+        }
+        let hash: [u8; 32] = reader.take(32)?.try_into().ok()?;
+        if &hash != sde_hash {
+            return None;
+        }
+        let universe = Universe::read(&mut reader)?;
+        let cache = SdeCache {
+            sde_hash: hash,
+            universe_map: Option::read(&mut reader)?,
+            region_maps: HashMap::read(&mut reader)?,
+        };
+        if !reader.is_empty() {
+            return None;
+        }
+        Some((cache, universe))
+    }
+
+    /// Writes the cache file with the Universe. The file is written with another name
+    /// first, so the previous cache is kept if writing fails.
+    pub fn save(&self, path: &Path, universe: &Universe) -> Result<(), std::io::Error> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let mut data = Vec::new();
+        data.extend_from_slice(MAGIC);
+        CACHE_VERSION.write(&mut data);
+        data.extend_from_slice(&self.sde_hash);
+        universe.write(&mut data);
+        self.universe_map.write(&mut data);
+        self.region_maps.write(&mut data);
+
+        let temp_path = path.with_extension("tmp");
+        File::create(&temp_path)?.write_all(&data)?;
+        rename(&temp_path, path)
+    }
+
+    /// Returns the universe map if it was built with the factor
+    pub fn get_universe_map(&self, factor: i64) -> Option<&MapData> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        self.universe_map
+            .as_ref()
+            .filter(|(map_factor, _)| *map_factor == factor)
+            .map(|(_, map)| map)
+    }
+
+    /// Returns the abstract map of the region if it was built with the factor
+    pub fn get_region_map(&self, region_id: u32, factor: i64) -> Option<&MapData> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        self.region_maps
+            .get(&region_id)
+            .filter(|(map_factor, _)| *map_factor == factor)
+            .map(|(_, map)| map)
+    }
+}
+
+/// Reads the values of a cache file in order
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        Reader { data }
+    }
+
+    fn take(&mut self, length: usize) -> Option<&'a [u8]> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        if self.data.len() < length {
+            return None;
+        }
+        let (value, rest) = self.data.split_at(length);
+        self.data = rest;
+        Some(value)
+    }
+
+    fn is_empty(&self) -> bool {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        self.data.is_empty()
+    }
+}
+
+/// Values that can be written to and read from the cache file. Numbers are stored in
+/// little endian, and collections with their length first.
+trait Cacheable: Sized {
+    fn write(&self, buffer: &mut Vec<u8>);
+    fn read(reader: &mut Reader<'_>) -> Option<Self>;
+}
+
+macro_rules! cacheable_number {
+    ($($number:ty),*) => {
+        $(impl Cacheable for $number {
+            fn write(&self, buffer: &mut Vec<u8>) {
+                buffer.extend_from_slice(&self.to_le_bytes());
+            }
+
+            fn read(reader: &mut Reader<'_>) -> Option<Self> {
+                Some(Self::from_le_bytes(
+                    reader.take(size_of::<Self>())?.try_into().ok()?,
+                ))
+            }
+        })*
+    };
+}
+
+cacheable_number!(u8, u32, u64, i64, f32);
+
+impl Cacheable for usize {
+    fn write(&self, buffer: &mut Vec<u8>) {
+        (*self as u64).write(buffer);
+    }
+
+    fn read(reader: &mut Reader<'_>) -> Option<Self> {
+        usize::try_from(u64::read(reader)?).ok()
+    }
+}
+
+impl Cacheable for String {
+    fn write(&self, buffer: &mut Vec<u8>) {
+        (self.len() as u32).write(buffer);
+        buffer.extend_from_slice(self.as_bytes());
+    }
+
+    fn read(reader: &mut Reader<'_>) -> Option<Self> {
+        let length = u32::read(reader)? as usize;
+        String::from_utf8(reader.take(length)?.to_vec()).ok()
+    }
+}
+
+impl<T: Cacheable> Cacheable for Vec<T> {
+    fn write(&self, buffer: &mut Vec<u8>) {
+        (self.len() as u32).write(buffer);
+        for value in self {
+            value.write(buffer);
+        }
+    }
+
+    fn read(reader: &mut Reader<'_>) -> Option<Self> {
+        let length = u32::read(reader)? as usize;
+        // the length comes from the file, so it is not trusted to reserve memory
+        let mut values = Vec::with_capacity(length.min(reader.data.len()));
+        for _ in 0..length {
+            values.push(T::read(reader)?);
+        }
+        Some(values)
+    }
+}
+
+impl<T: Cacheable> Cacheable for Option<T> {
+    fn write(&self, buffer: &mut Vec<u8>) {
+        match self {
+            Some(value) => {
+                1u8.write(buffer);
+                value.write(buffer);
+            }
+            None => 0u8.write(buffer),
+        }
+    }
+
+    fn read(reader: &mut Reader<'_>) -> Option<Self> {
+        match u8::read(reader)? {
+            0 => Some(None),
+            1 => Some(Some(T::read(reader)?)),
+            _ => None,
+        }
+    }
+}
+
+impl<A: Cacheable, B: Cacheable> Cacheable for (A, B) {
+    fn write(&self, buffer: &mut Vec<u8>) {
+        self.0.write(buffer);
+        self.1.write(buffer);
+    }
+
+    fn read(reader: &mut Reader<'_>) -> Option<Self> {
+        Some((A::read(reader)?, B::read(reader)?))
+    }
+}
+
+impl<K: Cacheable + Eq + Hash, V: Cacheable> Cacheable for HashMap<K, V> {
+    fn write(&self, buffer: &mut Vec<u8>) {
+        (self.len() as u32).write(buffer);
+        for (key, value) in self {
+            key.write(buffer);
+            value.write(buffer);
+        }
+    }
+
+    fn read(reader: &mut Reader<'_>) -> Option<Self> {
+        let length = u32::read(reader)? as usize;
+        let mut values = HashMap::with_capacity(length.min(reader.data.len()));
+        for _ in 0..length {
+            values.insert(K::read(reader)?, V::read(reader)?);
+        }
+        Some(values)
+    }
+}
+
+impl Cacheable for SdePoint {
+    fn write(&self, buffer: &mut Vec<u8>) {
+        self.x.write(buffer);
+        self.y.write(buffer);
+        self.z.write(buffer);
+    }
+
+    fn read(reader: &mut Reader<'_>) -> Option<Self> {
+        Some(SdePoint::new(
+            i64::read(reader)?,
+            i64::read(reader)?,
+            i64::read(reader)?,
+        ))
+    }
+}
+
+impl Cacheable for SdeLine {
+    fn write(&self, buffer: &mut Vec<u8>) {
+        self.points[0].write(buffer);
+        self.points[1].write(buffer);
+    }
+
+    fn read(reader: &mut Reader<'_>) -> Option<Self> {
+        Some(SdeLine::new(
+            SdePoint::read(reader)?,
+            SdePoint::read(reader)?,
+        ))
+    }
+}
+
+impl Cacheable for WormholeClass {
+    fn write(&self, buffer: &mut Vec<u8>) {
+        self.id().write(buffer);
+    }
+
+    fn read(reader: &mut Reader<'_>) -> Option<Self> {
+        Some(WormholeClass::from_id(u8::read(reader)?))
+    }
+}
+
+impl Cacheable for WormholeEffect {
+    fn write(&self, buffer: &mut Vec<u8>) {
+        let index = WormholeEffect::ALL
+            .iter()
+            .position(|effect| effect == self)
+            .unwrap_or_default();
+        (index as u8).write(buffer);
+    }
+
+    fn read(reader: &mut Reader<'_>) -> Option<Self> {
+        WormholeEffect::ALL.get(u8::read(reader)? as usize).copied()
+    }
+}
+
+impl Cacheable for WormholeStatic {
+    fn write(&self, buffer: &mut Vec<u8>) {
+        self.name.write(buffer);
+        self.destination.write(buffer);
+    }
+
+    fn read(reader: &mut Reader<'_>) -> Option<Self> {
+        let mut object = WormholeStatic::new();
+        object.name = String::read(reader)?;
+        object.destination = WormholeClass::read(reader)?;
+        Some(object)
+    }
+}
+
+impl Cacheable for Region {
+    fn write(&self, buffer: &mut Vec<u8>) {
+        self.id.write(buffer);
+        self.name.write(buffer);
+        self.constellations.write(buffer);
+        self.projected_coords.write(buffer);
+        self.faction.write(buffer);
+    }
+
+    fn read(reader: &mut Reader<'_>) -> Option<Self> {
+        let mut object = Region::new();
+        object.id = u32::read(reader)?;
+        object.name = String::read(reader)?;
+        object.constellations = Vec::read(reader)?;
+        object.projected_coords = SdePoint::read(reader)?;
+        object.faction = Option::read(reader)?;
+        Some(object)
+    }
+}
+
+impl Cacheable for Constellation {
+    fn write(&self, buffer: &mut Vec<u8>) {
+        self.id.write(buffer);
+        self.name.write(buffer);
+        self.region.write(buffer);
+        self.solar_systems.write(buffer);
+        self.projected_coords.write(buffer);
+    }
+
+    fn read(reader: &mut Reader<'_>) -> Option<Self> {
+        let mut object = Constellation::new();
+        object.id = u32::read(reader)?;
+        object.name = String::read(reader)?;
+        object.region = u32::read(reader)?;
+        object.solar_systems = Vec::read(reader)?;
+        object.projected_coords = SdePoint::read(reader)?;
+        Some(object)
+    }
+}
+
+impl Cacheable for SolarSystem {
+    fn write(&self, buffer: &mut Vec<u8>) {
+        self.id.write(buffer);
+        self.name.write(buffer);
+        self.region.write(buffer);
+        self.constellation.write(buffer);
+        self.planets.write(buffer);
+        self.connections.write(buffer);
+        self.stations.write(buffer);
+        self.real_coords.write(buffer);
+        self.projected_coords.write(buffer);
+        self.factor.write(buffer);
+        self.security.write(buffer);
+        self.wormhole_class.write(buffer);
+        self.effect.write(buffer);
+        self.statics.write(buffer);
+        self.faction.write(buffer);
+    }
+
+    fn read(reader: &mut Reader<'_>) -> Option<Self> {
+        let mut object = SolarSystem::new(1);
+        object.id = u32::read(reader)?;
+        object.name = String::read(reader)?;
+        object.region = u32::read(reader)?;
+        object.constellation = u32::read(reader)?;
+        object.planets = Vec::read(reader)?;
+        object.connections = Vec::read(reader)?;
+        object.stations = Vec::read(reader)?;
+        object.real_coords = SdePoint::read(reader)?;
+        object.projected_coords = SdePoint::read(reader)?;
+        object.factor = i64::read(reader)?;
+        object.security = f32::read(reader)?;
+        object.wormhole_class = WormholeClass::read(reader)?;
+        object.effect = Option::read(reader)?;
+        object.statics = Vec::read(reader)?;
+        object.faction = Option::read(reader)?;
+        Some(object)
+    }
+}
+
+impl Cacheable for Planet {
+    fn write(&self, buffer: &mut Vec<u8>) {
+        self.id.write(buffer);
+        self.solar_system.write(buffer);
+        self.index.write(buffer);
+        self.moons.write(buffer);
+        self.stations.write(buffer);
+    }
+
+    fn read(reader: &mut Reader<'_>) -> Option<Self> {
+        let mut object = Planet::new();
+        object.id = u32::read(reader)?;
+        object.solar_system = u32::read(reader)?;
+        object.index = u8::read(reader)?;
+        object.moons = Vec::read(reader)?;
+        object.stations = Vec::read(reader)?;
+        Some(object)
+    }
+}
+
+impl Cacheable for Moon {
+    fn write(&self, buffer: &mut Vec<u8>) {
+        self.id.write(buffer);
+        self.planet.write(buffer);
+        self.index.write(buffer);
+        self.solar_system.write(buffer);
+        self.stations.write(buffer);
+    }
+
+    fn read(reader: &mut Reader<'_>) -> Option<Self> {
+        let mut object = Moon::new();
+        object.id = u32::read(reader)?;
+        object.planet = u32::read(reader)?;
+        object.index = u8::read(reader)?;
+        object.solar_system = u32::read(reader)?;
+        object.stations = Vec::read(reader)?;
+        Some(object)
+    }
+}
+
+impl Cacheable for Station {
+    fn write(&self, buffer: &mut Vec<u8>) {
+        self.id.write(buffer);
+        self.name.write(buffer);
+        self.solar_system.write(buffer);
+        self.orbit.write(buffer);
+        self.corporation.write(buffer);
+        self.operation.write(buffer);
+        self.type_id.write(buffer);
+        self.services.write(buffer);
+    }
+
+    fn read(reader: &mut Reader<'_>) -> Option<Self> {
+        let mut object = Station::new();
+        object.id = u32::read(reader)?;
+        object.name = String::read(reader)?;
+        object.solar_system = u32::read(reader)?;
+        object.orbit = u32::read(reader)?;
+        object.corporation = u32::read(reader)?;
+        object.operation = u32::read(reader)?;
+        object.type_id = u32::read(reader)?;
+        object.services = Vec::read(reader)?;
+        Some(object)
+    }
+}
+
+impl Cacheable for StationService {
+    fn write(&self, buffer: &mut Vec<u8>) {
+        self.id.write(buffer);
+        self.name.write(buffer);
+    }
+
+    fn read(reader: &mut Reader<'_>) -> Option<Self> {
+        let mut object = StationService::new();
+        object.id = u32::read(reader)?;
+        object.name = String::read(reader)?;
+        Some(object)
+    }
+}
+
+impl Cacheable for Faction {
+    fn write(&self, buffer: &mut Vec<u8>) {
+        self.id.write(buffer);
+        self.name.write(buffer);
+    }
+
+    fn read(reader: &mut Reader<'_>) -> Option<Self> {
+        let mut object = Faction::new();
+        object.id = u32::read(reader)?;
+        object.name = String::read(reader)?;
+        Some(object)
+    }
+}
+
+impl Cacheable for Universe {
+    fn write(&self, buffer: &mut Vec<u8>) {
+        self.factor.write(buffer);
+        self.regions.write(buffer);
+        self.constellations.write(buffer);
+        self.solar_systems.write(buffer);
+        self.planets.write(buffer);
+        self.moons.write(buffer);
+        self.stations.write(buffer);
+        self.services.write(buffer);
+        self.factions.write(buffer);
+        self.connections.write(buffer);
+    }
+
+    fn read(reader: &mut Reader<'_>) -> Option<Self> {
+        let mut object = Universe::new(i64::read(reader)?);
+        object.regions = HashMap::read(reader)?;
+        object.constellations = HashMap::read(reader)?;
+        object.solar_systems = HashMap::read(reader)?;
+        object.planets = HashMap::read(reader)?;
+        object.moons = HashMap::read(reader)?;
+        object.stations = HashMap::read(reader)?;
+        object.services = HashMap::read(reader)?;
+        object.factions = HashMap::read(reader)?;
+        object.connections = HashMap::read(reader)?;
+        Some(object)
+    }
+}
+
+impl Cacheable for RawPoint {
+    fn write(&self, buffer: &mut Vec<u8>) {
+        self.components[0].write(buffer);
+        self.components[1].write(buffer);
+    }
+
+    fn read(reader: &mut Reader<'_>) -> Option<Self> {
+        Some(RawPoint::new(f32::read(reader)?, f32::read(reader)?))
+    }
+}
+
+impl Cacheable for MapPoint {
+    fn write(&self, buffer: &mut Vec<u8>) {
+        self.get_id().write(buffer);
+        self.get_name().write(buffer);
+        self.raw_point.write(buffer);
+        self.connections.write(buffer);
+    }
+
+    fn read(reader: &mut Reader<'_>) -> Option<Self> {
+        let mut object = MapPoint::new(usize::read(reader)?, RawPoint::default());
+        object.set_name(String::read(reader)?);
+        object.raw_point = RawPoint::read(reader)?;
+        object.connections = Vec::read(reader)?;
+        Some(object)
+    }
+}
+
+impl Cacheable for MapLine {
+    fn write(&self, buffer: &mut Vec<u8>) {
+        self.id.write(buffer);
+        self.raw_line.points[0].write(buffer);
+        self.raw_line.points[1].write(buffer);
+    }
+
+    fn read(reader: &mut Reader<'_>) -> Option<Self> {
+        let id = Option::read(reader)?;
+        let mut object = MapLine::new(RawPoint::read(reader)?, RawPoint::read(reader)?);
+        object.id = id;
+        Some(object)
+    }
+}
+
+impl Cacheable for MapData {
+    fn write(&self, buffer: &mut Vec<u8>) {
+        self.points.write(buffer);
+        self.lines.write(buffer);
+    }
+
+    fn read(reader: &mut Reader<'_>) -> Option<Self> {
+        Some(MapData {
+            points: HashMap::read(reader)?,
+            lines: HashMap::read(reader)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{placed, temp_dir, universe};
+    use std::fs::{read, write};
+
+    fn round_trip<T: Cacheable>(value: &T) -> Option<T> {
+        let mut buffer = Vec::new();
+        value.write(&mut buffer);
+        let mut reader = Reader::new(&buffer);
+        let result = T::read(&mut reader);
+        assert!(reader.is_empty());
+        result
+    }
+
+    fn sample_universe() -> Universe {
+        let mut wormhole = placed(31000001, -0.99, [3.0, 0.0, 4.0]);
+        wormhole.wormhole_class = WormholeClass::Class(5);
+        wormhole.effect = Some(WormholeEffect::Pulsar);
+        let mut statik = WormholeStatic::new();
+        statik.name = String::from("H296");
+        statik.destination = WormholeClass::Class(5);
+        wormhole.statics.push(statik);
+        let mut universe = universe(
+            vec![
+                placed(30000142, 0.95, [0.0, 0.0, 0.0]),
+                placed(30000144, 0.9, [1.0, 2.0, -3.0]),
+                wormhole,
+            ],
+            &[(30000142, 30000144)],
+        );
+        let jita = universe.solar_systems[&30000142].clone();
+        let perimeter = universe.solar_systems[&30000144].clone();
+        universe.connections.insert(
+            String::from("30000142-30000144"),
+            SdeLine::new(jita.real_coords, perimeter.real_coords),
+        );
+        let mut planet = Planet::new();
+        planet.id = 40009077;
+        planet.solar_system = 30000142;
+        planet.index = 4;
+        universe.planets.insert(planet.id, planet);
+        let mut station = Station::new();
+        station.id = 60003760;
+        station.name = String::from("Jita IV - Moon 4 - Caldari Navy Assembly Plant");
+        station.services = vec![5, 16];
+        universe.stations.insert(station.id, station);
+        let mut faction = Faction::new();
+        faction.id = 500001;
+        faction.name = String::from("Caldari State");
+        universe.factions.insert(faction.id, faction);
+        universe
+    }
+
+    fn sample_map() -> MapData {
+        let mut data = MapData::new();
+        let mut point = MapPoint::new(30000142, RawPoint::new(1.5, -2.0));
+        point.set_name(String::from("Jita"));
+        point.connections.push(String::from("30000142-30000144"));
+        data.points.insert(30000142, point);
+        let mut line = MapLine::new(RawPoint::new(1.5, -2.0), RawPoint::new(3.0, 4.0));
+        line.id = Some(String::from("30000142-30000144"));
+        data.lines.insert(String::from("30000142-30000144"), line);
+        data
+    }
+
+    #[test]
+    fn values_round_trip() {
+        assert_eq!(round_trip(&-42i64), Some(-42));
+        assert_eq!(round_trip(&0.25f32), Some(0.25));
+        assert_eq!(
+            round_trip(&String::from("Jita ✓")),
+            Some(String::from("Jita ✓"))
+        );
+        assert_eq!(round_trip(&vec![1u32, 2, 3]), Some(vec![1, 2, 3]));
+        assert_eq!(round_trip(&Some(7u8)), Some(Some(7)));
+        assert_eq!(round_trip(&None::<u8>), Some(None));
+        let map = HashMap::from([(1u32, (2u64, String::from("two")))]);
+        assert_eq!(round_trip(&map), Some(map));
+        for id in [0, 5, 25, 99] {
+            assert!(round_trip(&WormholeClass::from_id(id)) == Some(WormholeClass::from_id(id)));
+        }
+        for effect in WormholeEffect::ALL {
+            assert!(round_trip(&effect) == Some(effect));
+        }
+    }
+
+    #[test]
+    fn truncated_values_are_not_read() {
+        let mut buffer = Vec::new();
+        vec![String::from("Jita"), String::from("Perimeter")].write(&mut buffer);
+        buffer.pop();
+        assert!(Vec::<String>::read(&mut Reader::new(&buffer)).is_none());
+    }
+
+    #[test]
+    fn universe_and_maps_round_trip() {
+        let folder = temp_dir("cache-round-trip");
+        let path = folder.join("sde.cache");
+        let universe = sample_universe();
+        let mut cache = SdeCache::new([7; 32]);
+        cache.universe_map = Some((1, sample_map()));
+        cache.region_maps.insert(10000002, (2, sample_map()));
+        cache.save(&path, &universe).unwrap();
+
+        let (loaded, read_universe) = SdeCache::load(&path, &[7; 32]).unwrap();
+        assert_eq!(loaded.sde_hash, [7; 32]);
+        assert!(read_universe.solar_systems == universe.solar_systems);
+        assert!(read_universe.regions == universe.regions);
+        assert!(read_universe.constellations == universe.constellations);
+        assert!(read_universe.planets == universe.planets);
+        assert!(read_universe.stations == universe.stations);
+        assert!(read_universe.factions == universe.factions);
+        assert!(read_universe.connections == universe.connections);
+        assert_eq!(read_universe.factor, universe.factor);
+
+        let map = loaded.get_region_map(10000002, 2).unwrap();
+        let point = &map.points[&30000142];
+        assert_eq!(point.get_name(), "Jita");
+        assert_eq!(point.raw_point.components, [1.5, -2.0]);
+        assert_eq!(point.connections, vec![String::from("30000142-30000144")]);
+        let line = &map.lines["30000142-30000144"];
+        assert_eq!(line.id.as_deref(), Some("30000142-30000144"));
+        assert_eq!(line.raw_line.points[1].components, [3.0, 4.0]);
+        // maps built with another factor are not returned
+        assert!(loaded.get_region_map(10000002, 1).is_none());
+        assert!(loaded.get_region_map(10000001, 2).is_none());
+        assert!(loaded.get_universe_map(1).is_some());
+        assert!(loaded.get_universe_map(2).is_none());
+    }
+
+    #[test]
+    fn ignores_other_databases_versions_and_damaged_files() {
+        let folder = temp_dir("cache-damaged");
+        let path = folder.join("sde.cache");
+        assert!(SdeCache::load(&path, &[7; 32]).is_none());
+
+        SdeCache::new([7; 32])
+            .save(&path, &sample_universe())
+            .unwrap();
+        assert!(SdeCache::load(&path, &[7; 32]).is_some());
+        assert!(SdeCache::load(&path, &[8; 32]).is_none());
+
+        let data = read(&path).unwrap();
+        let damaged = folder.join("damaged.cache");
+        write(&damaged, &data[..data.len() - 1]).unwrap();
+        assert!(SdeCache::load(&damaged, &[7; 32]).is_none());
+        write(&damaged, [data.as_slice(), &[0]].concat()).unwrap();
+        assert!(SdeCache::load(&damaged, &[7; 32]).is_none());
+
+        let mut other_version = data.clone();
+        other_version[MAGIC.len()..MAGIC.len() + 4]
+            .copy_from_slice(&(CACHE_VERSION + 1).to_le_bytes());
+        write(&damaged, other_version).unwrap();
+        assert!(SdeCache::load(&damaged, &[7; 32]).is_none());
+    }
+}
@@ -0,0 +1,386 @@
+use crate::search::{MatchKind, NameKey, normalize};
+use std::collections::HashMap;
+
+/// Category identifier of the ships
+pub const SHIP_CATEGORY: u32 = 6;
+
+/// Category of item types, like Ship, Module or Charge
+#[derive(Hash, PartialEq, Eq, Clone)]
+pub struct ItemCategory {
+    /// Category identifier
+    pub id: u32,
+    /// Category name
+    pub name: String,
+    /// True when the category is used in the game
+    pub published: bool,
+}
+
+impl ItemCategory {
+    /// Creates a new ItemCategory Struct. All the values are initialized. Needs to be filled
+    pub fn new() -> Self {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        ItemCategory {
+            id: 0,
+            name: String::new(),
+            published: false,
+        }
+    }
+}
+
+impl Default for ItemCategory {
+    fn default() -> Self {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        Self::new()
+    }
+}
+
+/// Group of item types inside a category. For ships it is the hull class, like
+/// Interdictor or Force Recon Ship
+#[derive(Hash, PartialEq, Eq, Clone)]
+pub struct ItemGroup {
+    /// Group identifier
+    pub id: u32,
+    /// Group name
+    pub name: String,
+    /// Category identifier
+    pub category: u32,
+    /// True when the group is used in the game
+    pub published: bool,
+}
+
+impl ItemGroup {
+    /// Creates a new ItemGroup Struct. All the values are initialized. Needs to be filled
+    pub fn new() -> Self {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        ItemGroup {
+            id: 0,
+            name: String::new(),
+            category: 0,
+            published: false,
+        }
+    }
+}
+
+impl Default for ItemGroup {
+    fn default() -> Self {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        Self::new()
+    }
+}
+
+/// Item type, like a ship hull, a module or a charge
+#[derive(Hash, PartialEq, Eq, Clone)]
+pub struct ItemType {
+    /// Type identifier
+    pub id: u32,
+    /// Type name
+    pub name: String,
+    /// Group identifier
+    pub group: u32,
+    /// True when the type is used in the game
+    pub published: bool,
+}
+
+impl ItemType {
+    /// Creates a new ItemType Struct. All the values are initialized. Needs to be filled
+    pub fn new() -> Self {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        ItemType {
+            id: 0,
+            name: String::new(),
+            group: 0,
+            published: false,
+        }
+    }
+}
+
+impl Default for ItemType {
+    fn default() -> Self {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        Self::new()
+    }
+}
+
+/// Item type found by a fuzzy search in the TypeCatalog
+#[derive(Hash, PartialEq, Eq, Clone)]
+pub struct TypeMatch {
+    /// Type identifier
+    pub id: u32,
+    /// Type name
+    pub name: String,
+    /// Group identifier
+    pub group: u32,
+    /// How the name matched the searched text
+    pub matched: MatchKind,
+}
+
+/// In-memory catalog of the item types, with their groups and categories.
+///
+/// The types can be found by identifier, by exact name ignoring the case, and by fuzzy
+/// name with the same matching rules of the `SearchIndex`. The fuzzy search only returns
+/// published types.
+pub struct TypeCatalog {
+    /// Categories by identifier
+    pub categories: HashMap<u32, ItemCategory>,
+    /// Groups by identifier
+    pub groups: HashMap<u32, ItemGroup>,
+    /// Types by identifier
+    pub types: HashMap<u32, ItemType>,
+    // lowercase name -> type, the published type wins when two share a name
+    names: HashMap<String, u32>,
+    // published types with their prepared names, for the fuzzy search
+    keys: Vec<(u32, NameKey)>,
+}
+
+impl TypeCatalog {
+    /// Creates a new TypeCatalog with the categories, groups and types
+    pub fn new(
+        categories: HashMap<u32, ItemCategory>,
+        groups: HashMap<u32, ItemGroup>,
+        types: HashMap<u32, ItemType>,
+    ) -> Self {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let mut names: HashMap<String, u32> = HashMap::with_capacity(types.len());
+        let mut keys = Vec::new();
+        for item in types.values() {
+            let name = item.name.trim().to_lowercase();
+            match names.get(&name).and_then(|id| types.get(id)) {
+                Some(other) if other.published || !item.published => {}
+                _ => {
+                    names.insert(name, item.id);
+                }
+            }
+            if item.published {
+                keys.push((item.id, NameKey::new(&item.name)));
+            }
+        }
+        TypeCatalog {
+            categories,
+            groups,
+            types,
+            names,
+            keys,
+        }
+    }
+
+    /// Returns the type with the identifier
+    pub fn get_type(&self, type_id: u32) -> Option<&ItemType> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        self.types.get(&type_id)
+    }
+
+    /// Returns the type with the name, ignoring the case and the surrounding spaces
+    pub fn get_type_by_name(&self, name: &str) -> Option<&ItemType> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        self.names
+            .get(&name.trim().to_lowercase())
+            .and_then(|id| self.types.get(id))
+    }
+
+    /// Returns the group of the type
+    pub fn get_group(&self, type_id: u32) -> Option<&ItemGroup> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        self.types
+            .get(&type_id)
+            .and_then(|item| self.groups.get(&item.group))
+    }
+
+    /// Returns the category of the type
+    pub fn get_category(&self, type_id: u32) -> Option<&ItemCategory> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        self.get_group(type_id)
+            .and_then(|group| self.categories.get(&group.category))
+    }
+
+    /// Returns true when the type is a ship
+    pub fn is_ship(&self, type_id: u32) -> bool {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        self.get_group(type_id)
+            .is_some_and(|group| group.category == SHIP_CATEGORY)
+    }
+
+    /// Returns the published types whose name matches the text, best matches first. When
+    /// `category` is set only the types of that category are returned. `limit` caps the
+    /// number of results.
+    pub fn search(&self, text: &str, category: Option<u32>, limit: usize) -> Vec<TypeMatch> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let query = normalize(text);
+        if query.is_empty() {
+            return Vec::new();
+        }
+        let lowercase = text.trim().to_lowercase();
+        let mut results: Vec<(MatchKind, &ItemType)> = self
+            .keys
+            .iter()
+            .filter_map(|(id, key)| {
+                let item = self.types.get(id)?;
+                if let Some(category) = category {
+                    let group = self.groups.get(&item.group)?;
+                    if group.category != category {
+                        return None;
+                    }
+                }
+                key.matches(&query, &lowercase)
+                    .map(|matched| (matched, item))
+            })
+            .collect();
+        results.sort_by(|a, b| {
+            a.0.cmp(&b.0)
+                .then_with(|| a.1.name.len().cmp(&b.1.name.len()))
+                .then_with(|| a.1.name.cmp(&b.1.name))
+        });
+        results
+            .into_iter()
+            .take(limit)
+            .map(|(matched, item)| TypeMatch {
+                id: item.id,
+                name: item.name.clone(),
+                group: item.group,
+                matched,
+            })
+            .collect()
+    }
+
+    /// Resolves a ship name written by a player to the ship type and its hull class. The
+    /// exact name is tried first and then the best fuzzy match among the ships.
+    pub fn find_ship(&self, name: &str) -> Option<(&ItemType, &ItemGroup)> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let item = match self.get_type_by_name(name) {
+            Some(item) if self.is_ship(item.id) => item,
+            _ => {
+                let found = self.search(name, Some(SHIP_CATEGORY), 1).pop()?;
+                self.types.get(&found.id)?
+            }
+        };
+        Some((item, self.groups.get(&item.group)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn catalog() -> TypeCatalog {
+        let categories = [(4, "Material"), (6, "Ship")]
+            .into_iter()
+            .map(|(id, name)| {
+                let mut category = ItemCategory::new();
+                category.id = id;
+                category.name = name.to_string();
+                category.published = true;
+                (id, category)
+            })
+            .collect();
+        let groups = [
+            (18, "Mineral", 4),
+            (541, "Interdictor", 6),
+            (833, "Force Recon Ship", 6),
+        ]
+        .into_iter()
+        .map(|(id, name, category)| {
+            let mut group = ItemGroup::new();
+            group.id = id;
+            group.name = name.to_string();
+            group.category = category;
+            group.published = true;
+            (id, group)
+        })
+        .collect();
+        let types = [
+            (34, "Tritanium", 18, true),
+            (22456, "Sabre", 541, true),
+            (22460, "Eris", 541, true),
+            (11957, "Falcon", 833, true),
+            (11958, "Falcon Blueprint", 18, true),
+            (99999, "Sabre", 541, false),
+            (99998, "Rook", 833, false),
+        ]
+        .into_iter()
+        .map(|(id, name, group, published)| {
+            let mut item = ItemType::new();
+            item.id = id;
+            item.name = name.to_string();
+            item.group = group;
+            item.published = published;
+            (id, item)
+        })
+        .collect();
+        TypeCatalog::new(categories, groups, types)
+    }
+
+    #[test]
+    fn finds_types_by_identifier_and_name() {
+        let catalog = catalog();
+        assert_eq!(catalog.get_type(22460).unwrap().name, "Eris");
+        assert!(catalog.get_type(1).is_none());
+        // the published type wins when two share a name
+        assert_eq!(catalog.get_type_by_name("  sABRE ").unwrap().id, 22456);
+        assert_eq!(catalog.get_type_by_name("rook").unwrap().id, 99998);
+        assert_eq!(catalog.get_group(11957).unwrap().name, "Force Recon Ship");
+        assert_eq!(catalog.get_category(34).unwrap().name, "Material");
+        assert!(catalog.is_ship(22456));
+        assert!(!catalog.is_ship(34));
+        assert!(!catalog.is_ship(1));
+    }
+
+    #[test]
+    fn searches_only_the_published_types() {
+        let catalog = catalog();
+        let found = catalog.search("falc", None, 10);
+        let ids: Vec<u32> = found.iter().map(|item| item.id).collect();
+        assert_eq!(ids, vec![11957, 11958]);
+        assert!(found[0].matched == MatchKind::Prefix);
+        assert_eq!(catalog.search("falc", Some(SHIP_CATEGORY), 10).len(), 1);
+        assert_eq!(catalog.search("falc", None, 1).len(), 1);
+        assert!(catalog.search("rook", None, 10).is_empty());
+        assert!(catalog.search("  ", None, 10).is_empty());
+    }
+
+    #[test]
+    fn finds_ships_with_their_hull_class() {
+        let catalog = catalog();
+        let (ship, group) = catalog.find_ship("sabre").unwrap();
+        assert_eq!((ship.id, group.id), (22456, 541));
+        // a typo falls back to the fuzzy search among the ships
+        let (ship, group) = catalog.find_ship("Falcom").unwrap();
+        assert_eq!((ship.id, group.name.as_str()), (11957, "Force Recon Ship"));
+        assert!(catalog.find_ship("Tritanium").is_none());
+        assert!(catalog.find_ship("").is_none());
+    }
+
+    #[test]
+    fn empty_catalog_finds_nothing() {
+        let catalog = TypeCatalog::new(HashMap::new(), HashMap::new(), HashMap::new());
+        assert!(catalog.get_type_by_name("Sabre").is_none());
+        assert!(catalog.search("Sabre", None, 10).is_empty());
+        assert!(catalog.find_ship("Sabre").is_none());
+    }
+}
@@ -0,0 +1,355 @@
+use crate::objects::Universe;
+use crate::wormhole::KNOWN_SPACE_REGIONS;
+use std::collections::{HashMap, VecDeque};
+
+/// Part of the stargate graph analysed by a ChokepointAnalysis
+#[derive(Hash, PartialEq, Eq, Clone, Copy)]
+pub enum GraphScope {
+    /// Solar Systems of one Region, only the stargates inside of it are used
+    Region(u32),
+    /// Every Solar System of known space, without J-Space and the other wormhole regions
+    KnownSpace,
+}
+
+/// Chokepoints of the stargate graph: the Solar Systems and stargates that split it when
+/// they are removed, and how many shortest routes go through every Solar System.
+///
+/// The graph is read from `SolarSystem.connections`, the jump bridges are not used
+/// because they change with the sovereignty.
+pub struct ChokepointAnalysis {
+    /// Part of the graph that was analysed
+    pub scope: GraphScope,
+    /// Solar Systems that leave some other systems unreachable when they are removed,
+    /// sorted by identifier
+    pub articulation_points: Vec<u32>,
+    /// Stargates that leave some systems unreachable when they are removed, as pairs of
+    /// Solar Systems with the lowest identifier first, sorted
+    pub bridges: Vec<(u32, u32)>,
+    /// Betweenness centrality of every Solar System, the share of the shortest routes
+    /// between two other systems that go through it, from 0 to 1
+    pub betweenness: HashMap<u32, f64>,
+}
+
+impl ChokepointAnalysis {
+    /// Analyses the part of the stargate graph of the Universe
+    pub fn new(universe: &Universe, scope: GraphScope) -> Self {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let in_scope = |region: u32| match scope {
+            GraphScope::Region(id) => region == id,
+            GraphScope::KnownSpace => KNOWN_SPACE_REGIONS.contains(&region),
+        };
+        let mut ids: Vec<u32> = universe
+            .solar_systems
+            .values()
+            .filter(|system| in_scope(system.region))
+            .map(|system| system.id)
+            .collect();
+        ids.sort_unstable();
+        let index: HashMap<u32, usize> = ids.iter().enumerate().map(|(i, id)| (*id, i)).collect();
+        let graph: Vec<Vec<usize>> = ids
+            .iter()
+            .map(|id| {
+                let mut neighbours: Vec<usize> = universe.solar_systems[id]
+                    .connections
+                    .iter()
+                    .filter_map(|connection| index.get(connection).copied())
+                    .collect();
+                neighbours.sort_unstable();
+                neighbours.dedup();
+                neighbours
+            })
+            .collect();
+
+        let (points, bridges) = Self::cut_elements(&graph);
+        let centrality = Self::centrality(&graph);
+
+        let mut articulation_points: Vec<u32> = points.into_iter().map(|i| ids[i]).collect();
+        articulation_points.sort_unstable();
+        let mut bridges: Vec<(u32, u32)> = bridges
+            .into_iter()
+            .map(|(a, b)| (ids[a].min(ids[b]), ids[a].max(ids[b])))
+            .collect();
+        bridges.sort_unstable();
+        ChokepointAnalysis {
+            scope,
+            articulation_points,
+            bridges,
+            betweenness: ids.into_iter().zip(centrality).collect(),
+        }
+    }
+
+    /// Returns true if the Solar System splits the graph when it is removed
+    pub fn is_articulation_point(&self, system_id: u32) -> bool {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        self.articulation_points.binary_search(&system_id).is_ok()
+    }
+
+    /// Returns true if the stargate between the two Solar Systems splits the graph when
+    /// it is removed
+    pub fn is_bridge(&self, system_a: u32, system_b: u32) -> bool {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        self.bridges
+            .binary_search(&(system_a.min(system_b), system_a.max(system_b)))
+            .is_ok()
+    }
+
+    /// Returns the Solar Systems with the highest betweenness centrality, the highest
+    /// first. `limit` caps the number of results.
+    pub fn ranking(&self, limit: usize) -> Vec<(u32, f64)> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let mut result: Vec<(u32, f64)> = self
+            .betweenness
+            .iter()
+            .map(|(id, value)| (*id, *value))
+            .collect();
+        result.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        result.truncate(limit);
+        result
+    }
+
+    // articulation points and bridges with the iterative version of Tarjan's algorithm,
+    // the whole known space is too deep for the recursive one
+    fn cut_elements(graph: &[Vec<usize>]) -> (Vec<usize>, Vec<(usize, usize)>) {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let count = graph.len();
+        let mut discovery = vec![usize::MAX; count];
+        let mut low = vec![0; count];
+        let mut is_point = vec![false; count];
+        let mut bridges = Vec::new();
+        let mut time = 0;
+        for root in 0..count {
+            if discovery[root] != usize::MAX {
+                continue;
+            }
+            discovery[root] = time;
+            low[root] = time;
+            time += 1;
+            let mut root_children = 0;
+            // node, parent and the next neighbour to visit
+            let mut stack = vec![(root, usize::MAX, 0)];
+            while let Some(&mut (node, parent, ref mut next)) = stack.last_mut() {
+                if let Some(&neighbour) = graph[node].get(*next) {
+                    *next += 1;
+                    if neighbour == parent {
+                        continue;
+                    }
+                    if discovery[neighbour] == usize::MAX {
+                        discovery[neighbour] = time;
+                        low[neighbour] = time;
+                        time += 1;
+                        if node == root {
+                            root_children += 1;
+                        }
+                        stack.push((neighbour, node, 0));
+                    } else {
+                        low[node] = low[node].min(discovery[neighbour]);
+                    }
+                    continue;
+                }
+                stack.pop();
+                if parent != usize::MAX {
+                    low[parent] = low[parent].min(low[node]);
+                    if low[node] > discovery[parent] {
+                        bridges.push((parent, node));
+                    }
+                    if parent != root && low[node] >= discovery[parent] {
+                        is_point[parent] = true;
+                    }
+                }
+            }
+            if root_children > 1 {
+                is_point[root] = true;
+            }
+        }
+        let points = (0..count).filter(|node| is_point[*node]).collect();
+        (points, bridges)
+    }
+
+    // Brandes' algorithm for unweighted graphs, normalized by the number of pairs of
+    // other Solar Systems
+    fn centrality(graph: &[Vec<usize>]) -> Vec<f64> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let count = graph.len();
+        let mut result = vec![0.0; count];
+        let mut order = Vec::with_capacity(count);
+        let mut queue = VecDeque::with_capacity(count);
+        let mut paths = vec![0.0f64; count];
+        let mut distance = vec![usize::MAX; count];
+        let mut dependency = vec![0.0f64; count];
+        for source in 0..count {
+            order.clear();
+            paths.fill(0.0);
+            distance.fill(usize::MAX);
+            dependency.fill(0.0);
+            paths[source] = 1.0;
+            distance[source] = 0;
+            queue.push_back(source);
+            while let Some(node) = queue.pop_front() {
+                order.push(node);
+                for &neighbour in &graph[node] {
+                    if distance[neighbour] == usize::MAX {
+                        distance[neighbour] = distance[node] + 1;
+                        queue.push_back(neighbour);
+                    }
+                    if distance[neighbour] == distance[node] + 1 {
+                        paths[neighbour] += paths[node];
+                    }
+                }
+            }
+            // the predecessors of a node are its neighbours one jump closer to the source
+            for &node in order.iter().rev() {
+                for &neighbour in &graph[node] {
+                    if distance[neighbour] != usize::MAX
+                        && distance[neighbour] + 1 == distance[node]
+                    {
+                        dependency[neighbour] +=
+                            paths[neighbour] / paths[node] * (1.0 + dependency[node]);
+                    }
+                }
+                if node != source {
+                    result[node] += dependency[node];
+                }
+            }
+        }
+        // every pair is counted from both ends
+        if count > 2 {
+            let pairs = ((count - 1) * (count - 2)) as f64;
+            for value in &mut result {
+                *value /= pairs;
+            }
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{REGION, system, universe};
+
+    // triangle 1 - 2 - 3, gate 3 - 4 and triangle 4 - 5 - 6, 7 is isolated
+    fn bow_tie() -> Universe {
+        universe(
+            (1..=7).map(|id| system(id, 0.5)).collect(),
+            &[(1, 2), (2, 3), (3, 1), (3, 4), (4, 5), (5, 6), (6, 4)],
+        )
+    }
+
+    fn assert_close(value: f64, expected: f64) {
+        assert!((value - expected).abs() < 1e-9, "{value} != {expected}");
+    }
+
+    #[test]
+    fn finds_articulation_points_and_bridges() {
+        let analysis = ChokepointAnalysis::new(&bow_tie(), GraphScope::Region(REGION));
+        assert_eq!(analysis.articulation_points, vec![3, 4]);
+        assert_eq!(analysis.bridges, vec![(3, 4)]);
+        assert!(analysis.is_articulation_point(4));
+        assert!(!analysis.is_articulation_point(7));
+        assert!(analysis.is_bridge(4, 3));
+        assert!(!analysis.is_bridge(1, 2));
+    }
+
+    #[test]
+    fn root_is_a_point_only_with_two_subtrees() {
+        // star around 1, the search starts on the lowest identifier
+        let star = universe(
+            (1..=4).map(|id| system(id, 0.5)).collect(),
+            &[(1, 2), (1, 3), (1, 4)],
+        );
+        let analysis = ChokepointAnalysis::new(&star, GraphScope::KnownSpace);
+        assert_eq!(analysis.articulation_points, vec![1]);
+        assert_eq!(analysis.bridges, vec![(1, 2), (1, 3), (1, 4)]);
+
+        let ring = universe(
+            (1..=4).map(|id| system(id, 0.5)).collect(),
+            &[(1, 2), (2, 3), (3, 4), (4, 1)],
+        );
+        let analysis = ChokepointAnalysis::new(&ring, GraphScope::KnownSpace);
+        assert!(analysis.articulation_points.is_empty());
+        assert!(analysis.bridges.is_empty());
+    }
+
+    #[test]
+    fn long_chains_do_not_overflow_the_stack() {
+        // deeper than the known space, every node of a chain but the ends splits it
+        let count = 100_000;
+        let chain: Vec<Vec<usize>> = (0..count)
+            .map(|node| {
+                let mut neighbours = Vec::new();
+                if node > 0 {
+                    neighbours.push(node - 1);
+                }
+                if node + 1 < count {
+                    neighbours.push(node + 1);
+                }
+                neighbours
+            })
+            .collect();
+        let (points, bridges) = ChokepointAnalysis::cut_elements(&chain);
+        assert_eq!(points, (1..count - 1).collect::<Vec<usize>>());
+        assert_eq!(bridges.len(), count - 1);
+    }
+
+    #[test]
+    fn measures_the_betweenness() {
+        let analysis = ChokepointAnalysis::new(&bow_tie(), GraphScope::Region(REGION));
+        // 3 is on the routes from 1 and 2 to the other triangle, 12 of the 30 ordered
+        // pairs of the other systems, the isolated one included
+        assert_close(analysis.betweenness[&3], 12.0 / 30.0);
+        assert_close(analysis.betweenness[&4], 12.0 / 30.0);
+        assert_close(analysis.betweenness[&1], 0.0);
+        assert_close(analysis.betweenness[&7], 0.0);
+
+        // the opposite corners of a square have two routes, each one gets half
+        let ring = universe(
+            (1..=4).map(|id| system(id, 0.5)).collect(),
+            &[(1, 2), (2, 3), (3, 4), (4, 1)],
+        );
+        let analysis = ChokepointAnalysis::new(&ring, GraphScope::KnownSpace);
+        for id in 1..=4 {
+            assert_close(analysis.betweenness[&id], 1.0 / 6.0);
+        }
+    }
+
+    #[test]
+    fn ranks_the_busiest_systems_first() {
+        let analysis = ChokepointAnalysis::new(&bow_tie(), GraphScope::Region(REGION));
+        let ranking = analysis.ranking(3);
+        assert_eq!(ranking.len(), 3);
+        assert_eq!((ranking[0].0, ranking[1].0), (3, 4));
+        // ties are sorted by identifier
+        assert_eq!(ranking[2].0, 1);
+        assert!(analysis.ranking(0).is_empty());
+    }
+
+    #[test]
+    fn only_reads_the_systems_in_scope() {
+        let mut systems: Vec<_> = (1..=3).map(|id| system(id, 0.5)).collect();
+        // 2 is in another known space region and 3 in J-Space
+        systems[1].region = 10000043;
+        systems[2].region = 11000001;
+        let universe = universe(systems, &[(1, 2), (2, 3)]);
+
+        let analysis = ChokepointAnalysis::new(&universe, GraphScope::Region(REGION));
+        assert!(analysis.scope == GraphScope::Region(REGION));
+        assert_eq!(analysis.betweenness.len(), 1);
+        assert!(analysis.bridges.is_empty());
+
+        let analysis = ChokepointAnalysis::new(&universe, GraphScope::KnownSpace);
+        assert_eq!(analysis.bridges, vec![(1, 2)]);
+        assert!(!analysis.betweenness.contains_key(&3));
+    }
+}
@@ -0,0 +1,547 @@
+use crate::SdeManager;
+use crate::error::SdeError;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::path::Path;
+
+/// Distance in abstract map units under which a Solar System is not reported as moved
+pub const DEFAULT_TOLERANCE: f64 = 0.5;
+
+/// Tables and columns read to compare two databases, the rest of the schema can change
+/// between SDE versions without breaking the comparison
+const DIFF_SCHEMA: [(&str, &[&str]); 5] = [
+    ("mapRegions", &["regionId", "regionName"]),
+    ("mapConstellations", &["constellationId", "regionId"]),
+    (
+        "mapSolarSystems",
+        &["solarSystemId", "solarSystemName", "constellationId"],
+    ),
+    ("mapSystemConnections", &["systemA", "systemB"]),
+    (
+        "mapAbstractSystems",
+        &["solarSystemId", "regionId", "x", "y"],
+    ),
+];
+
+/// Solar System that is only in one of the databases
+#[derive(Serialize, Clone, PartialEq, Debug)]
+pub struct SystemEntry {
+    /// Solar System identifier
+    pub id: u32,
+    /// Solar System name
+    pub name: String,
+    /// Region identifier
+    pub region_id: u32,
+    /// Region name
+    pub region: String,
+}
+
+/// Solar System or Region that has another name in the new database
+#[derive(Serialize, Clone, PartialEq, Debug)]
+pub struct RenamedEntry {
+    /// Solar System or Region identifier
+    pub id: u32,
+    /// Name in the old database
+    pub old_name: String,
+    /// Name in the new database
+    pub new_name: String,
+}
+
+/// Stargate connection that is only in one of the databases
+#[derive(Serialize, Clone, PartialEq, Debug)]
+pub struct ConnectionEntry {
+    /// Solar System with the lowest identifier
+    pub system_a: u32,
+    /// Name of the first Solar System
+    pub name_a: String,
+    /// Solar System with the highest identifier
+    pub system_b: u32,
+    /// Name of the second Solar System
+    pub name_b: String,
+}
+
+/// Solar System that has another place in the abstract map of its region
+#[derive(Serialize, Clone, PartialEq, Debug)]
+pub struct MovedEntry {
+    /// Solar System identifier
+    pub id: u32,
+    /// Solar System name
+    pub name: String,
+    /// Region of the abstract map
+    pub region_id: u32,
+    /// Coordinates in the old database
+    pub old: [f64; 2],
+    /// Coordinates in the new database
+    pub new: [f64; 2],
+}
+
+/// Differences between two SDE databases, usually the one in use and a new one built
+/// from a newer static data export. Every list is sorted by identifier.
+#[derive(Serialize, Clone, PartialEq, Debug)]
+pub struct SdeDiff {
+    /// Solar Systems that are only in the new database
+    pub added_systems: Vec<SystemEntry>,
+    /// Solar Systems that are only in the old database
+    pub removed_systems: Vec<SystemEntry>,
+    /// Solar Systems in both databases with another name
+    pub renamed_systems: Vec<RenamedEntry>,
+    /// Regions in both databases with another name
+    pub renamed_regions: Vec<RenamedEntry>,
+    /// Stargate connections that are only in the new database
+    pub added_connections: Vec<ConnectionEntry>,
+    /// Stargate connections that are only in the old database
+    pub removed_connections: Vec<ConnectionEntry>,
+    /// Solar Systems placed somewhere else in the abstract map of their region. Systems
+    /// without abstract coordinates in one of the databases are not compared.
+    pub moved_systems: Vec<MovedEntry>,
+}
+
+impl SdeDiff {
+    /// Creates a new empty SdeDiff
+    pub fn new() -> Self {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        SdeDiff {
+            added_systems: Vec::new(),
+            removed_systems: Vec::new(),
+            renamed_systems: Vec::new(),
+            renamed_regions: Vec::new(),
+            added_connections: Vec::new(),
+            removed_connections: Vec::new(),
+            moved_systems: Vec::new(),
+        }
+    }
+
+    /// Compares two SDE databases. Abstract map coordinates that moved less than
+    /// `tolerance` are not reported.
+    pub fn compare(old: &Path, new: &Path, tolerance: f64) -> Result<Self, SdeError> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let old = SdeSnapshot::read(old)?;
+        let new = SdeSnapshot::read(new)?;
+        let mut diff = SdeDiff::new();
+
+        for (id, (name, region_id)) in &new.systems {
+            match old.systems.get(id) {
+                None => diff
+                    .added_systems
+                    .push(new.system_entry(*id, name, *region_id)),
+                Some((old_name, _)) if old_name != name => {
+                    diff.renamed_systems.push(RenamedEntry {
+                        id: *id,
+                        old_name: old_name.clone(),
+                        new_name: name.clone(),
+                    })
+                }
+                Some(_) => {}
+            }
+        }
+        for (id, (name, region_id)) in &old.systems {
+            if !new.systems.contains_key(id) {
+                diff.removed_systems
+                    .push(old.system_entry(*id, name, *region_id));
+            }
+        }
+        for (id, name) in &new.regions {
+            if let Some(old_name) = old.regions.get(id)
+                && old_name != name
+            {
+                diff.renamed_regions.push(RenamedEntry {
+                    id: *id,
+                    old_name: old_name.clone(),
+                    new_name: name.clone(),
+                });
+            }
+        }
+        for &(system_a, system_b) in new.connections.difference(&old.connections) {
+            diff.added_connections
+                .push(new.connection_entry(system_a, system_b));
+        }
+        for &(system_a, system_b) in old.connections.difference(&new.connections) {
+            diff.removed_connections
+                .push(old.connection_entry(system_a, system_b));
+        }
+        for (id, (region_id, position)) in &new.abstract_systems {
+            let Some((_, old_position)) = old.abstract_systems.get(id) else {
+                continue;
+            };
+            let distance = ((position[0] - old_position[0]).powi(2)
+                + (position[1] - old_position[1]).powi(2))
+            .sqrt();
+            if distance > tolerance {
+                diff.moved_systems.push(MovedEntry {
+                    id: *id,
+                    name: new.system_name(*id),
+                    region_id: *region_id,
+                    old: *old_position,
+                    new: *position,
+                });
+            }
+        }
+        Ok(diff)
+    }
+
+    /// Returns true when both databases have the same data
+    pub fn is_empty(&self) -> bool {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        self.added_systems.is_empty()
+            && self.removed_systems.is_empty()
+            && self.renamed_systems.is_empty()
+            && self.renamed_regions.is_empty()
+            && self.added_connections.is_empty()
+            && self.removed_connections.is_empty()
+            && self.moved_systems.is_empty()
+    }
+
+    /// Returns the differences as pretty printed JSON
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        serde_json::to_string_pretty(self)
+    }
+}
+
+impl Default for SdeDiff {
+    fn default() -> Self {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        Self::new()
+    }
+}
+
+impl Display for SdeDiff {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        if self.is_empty() {
+            return writeln!(f, "No differences found");
+        }
+        if !self.added_systems.is_empty() {
+            writeln!(f, "Solar Systems added: {}", self.added_systems.len())?;
+            for system in &self.added_systems {
+                writeln!(f, "  + {} {} ({})", system.id, system.name, system.region)?;
+            }
+        }
+        if !self.removed_systems.is_empty() {
+            writeln!(f, "Solar Systems removed: {}", self.removed_systems.len())?;
+            for system in &self.removed_systems {
+                writeln!(f, "  - {} {} ({})", system.id, system.name, system.region)?;
+            }
+        }
+        for (title, entries) in [
+            ("Solar Systems renamed", &self.renamed_systems),
+            ("Regions renamed", &self.renamed_regions),
+        ] {
+            if !entries.is_empty() {
+                writeln!(f, "{title}: {}", entries.len())?;
+                for entry in entries {
+                    writeln!(
+                        f,
+                        "  ~ {} {} → {}",
+                        entry.id, entry.old_name, entry.new_name
+                    )?;
+                }
+            }
+        }
+        for (title, sign, entries) in [
+            ("Stargate connections added", '+', &self.added_connections),
+            (
+                "Stargate connections removed",
+                '-',
+                &self.removed_connections,
+            ),
+        ] {
+            if !entries.is_empty() {
+                writeln!(f, "{title}: {}", entries.len())?;
+                for entry in entries {
+                    writeln!(f, "  {sign} {} » {}", entry.name_a, entry.name_b)?;
+                }
+            }
+        }
+        if !self.moved_systems.is_empty() {
+            writeln!(f, "Abstract map points moved: {}", self.moved_systems.len())?;
+            for entry in &self.moved_systems {
+                writeln!(
+                    f,
+                    "  ~ {} {} ({:.1}, {:.1}) → ({:.1}, {:.1})",
+                    entry.id, entry.name, entry.old[0], entry.old[1], entry.new[0], entry.new[1]
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// data of one database needed to compare it, sorted so the reports are stable
+struct SdeSnapshot {
+    // region -> name
+    regions: BTreeMap<u32, String>,
+    // solar system -> (name, region)
+    systems: BTreeMap<u32, (String, u32)>,
+    // stargates, the lowest identifier first
+    connections: BTreeSet<(u32, u32)>,
+    // solar system -> (region, abstract map coordinates)
+    abstract_systems: BTreeMap<u32, (u32, [f64; 2])>,
+}
+
+impl SdeSnapshot {
+    fn read(path: &Path) -> Result<Self, SdeError> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        // older or newer databases can be compared, only the tables read here are checked
+        if !path.is_file() {
+            return Err(SdeError::MissingDatabase(path.to_path_buf()));
+        }
+        let manager = SdeManager::new(path, 1);
+        for (table, columns) in DIFF_SCHEMA {
+            if !manager.has_table(table, &[])? {
+                return Err(SdeError::MissingTable(table.to_string()));
+            }
+            for column in columns {
+                if !manager.has_table(table, &[column])? {
+                    return Err(SdeError::MissingColumn(
+                        table.to_string(),
+                        column.to_string(),
+                    ));
+                }
+            }
+        }
+        let connection = manager.pool.get()?;
+
+        let mut statement =
+            connection.prepare_cached("SELECT regionId, regionName FROM mapRegions;")?;
+        let regions = statement
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<_, _>>()?;
+
+        let mut query = String::from("SELECT mss.solarSystemId, mss.solarSystemName, ");
+        query += "mc.regionId FROM mapSolarSystems AS mss INNER JOIN mapConstellations AS mc ";
+        query += "ON (mc.constellationId = mss.constellationId);";
+        let mut statement = connection.prepare_cached(query.as_str())?;
+        let systems = statement
+            .query_map([], |row| Ok((row.get(0)?, (row.get(1)?, row.get(2)?))))?
+            .collect::<Result<_, _>>()?;
+
+        let mut statement =
+            connection.prepare_cached("SELECT systemA, systemB FROM mapSystemConnections;")?;
+        let connections = statement
+            .query_map([], |row| {
+                let system_a: u32 = row.get(0)?;
+                let system_b: u32 = row.get(1)?;
+                Ok((system_a.min(system_b), system_a.max(system_b)))
+            })?
+            .collect::<Result<_, _>>()?;
+
+        let mut statement = connection
+            .prepare_cached("SELECT solarSystemId, regionId, x, y FROM mapAbstractSystems;")?;
+        let abstract_systems = statement
+            .query_map([], |row| {
+                Ok((row.get(0)?, (row.get(1)?, [row.get(2)?, row.get(3)?])))
+            })?
+            .collect::<Result<_, _>>()?;
+
+        Ok(SdeSnapshot {
+            regions,
+            systems,
+            connections,
+            abstract_systems,
+        })
+    }
+
+    fn system_name(&self, id: u32) -> String {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        self.systems
+            .get(&id)
+            .map(|(name, _)| name.clone())
+            .unwrap_or(id.to_string())
+    }
+
+    fn system_entry(&self, id: u32, name: &str, region_id: u32) -> SystemEntry {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        SystemEntry {
+            id,
+            name: name.to_string(),
+            region_id,
+            region: self
+                .regions
+                .get(&region_id)
+                .cloned()
+                .unwrap_or(region_id.to_string()),
+        }
+    }
+
+    fn connection_entry(&self, system_a: u32, system_b: u32) -> ConnectionEntry {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        ConnectionEntry {
+            system_a,
+            name_a: self.system_name(system_a),
+            system_b,
+            name_b: self.system_name(system_b),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{TempDir, schema_database};
+    use rusqlite::Connection;
+    use std::path::PathBuf;
+
+    // database with only the tables read by the comparison and a few rows
+    fn database(name: &str, rows: &str) -> (TempDir, PathBuf) {
+        schema_database(name, &DIFF_SCHEMA, rows)
+    }
+
+    const OLD: &str = "
+        INSERT INTO mapRegions VALUES (10000002, 'The Forge'), (10000043, 'Domain');
+        INSERT INTO mapConstellations VALUES (20000020, 10000002), (20000322, 10000043);
+        INSERT INTO mapSolarSystems VALUES
+            (30000142, 'Jita', 20000020),
+            (30000144, 'Perimeter', 20000020),
+            (30002187, 'Amarr', 20000322);
+        INSERT INTO mapSystemConnections VALUES (30000142, 30000144), (30000144, 30002187);
+        INSERT INTO mapAbstractSystems VALUES
+            (30000142, 10000002, 0.0, 0.0),
+            (30000144, 10000002, 10.0, 0.0);";
+
+    const NEW: &str = "
+        INSERT INTO mapRegions VALUES (10000002, 'The Forge'), (10000043, 'Domain Prime');
+        INSERT INTO mapConstellations VALUES (20000020, 10000002), (20000322, 10000043);
+        INSERT INTO mapSolarSystems VALUES
+            (30000142, 'Jita', 20000020),
+            (30000144, 'Perimeter II', 20000020),
+            (30000145, 'New Caldari', 20000020);
+        INSERT INTO mapSystemConnections VALUES (30000144, 30000142), (30000142, 30000145);
+        INSERT INTO mapAbstractSystems VALUES
+            (30000142, 10000002, 0.2, 0.2),
+            (30000144, 10000002, 10.0, 5.0),
+            (30000145, 10000002, 20.0, 0.0);";
+
+    #[test]
+    fn same_database_has_no_differences() {
+        let (_folder, path) = database("diff-same", OLD);
+        let diff = SdeDiff::compare(&path, &path, DEFAULT_TOLERANCE).unwrap();
+        assert!(diff.is_empty());
+        assert_eq!(diff, SdeDiff::default());
+        assert_eq!(diff.to_string(), "No differences found\n");
+    }
+
+    #[test]
+    fn finds_every_kind_of_difference() {
+        let (_old_folder, old) = database("diff-old", OLD);
+        let (_new_folder, new) = database("diff-new", NEW);
+        let diff = SdeDiff::compare(&old, &new, DEFAULT_TOLERANCE).unwrap();
+
+        assert_eq!(
+            diff.added_systems,
+            vec![SystemEntry {
+                id: 30000145,
+                name: String::from("New Caldari"),
+                region_id: 10000002,
+                region: String::from("The Forge"),
+            }]
+        );
+        assert_eq!(
+            diff.removed_systems,
+            vec![SystemEntry {
+                id: 30002187,
+                name: String::from("Amarr"),
+                region_id: 10000043,
+                region: String::from("Domain"),
+            }]
+        );
+        assert_eq!(
+            diff.renamed_systems,
+            vec![RenamedEntry {
+                id: 30000144,
+                old_name: String::from("Perimeter"),
+                new_name: String::from("Perimeter II"),
+            }]
+        );
+        assert_eq!(diff.renamed_regions.len(), 1);
+        assert_eq!(diff.renamed_regions[0].new_name, "Domain Prime");
+        // the stargates are compared without their direction
+        assert_eq!(
+            diff.added_connections,
+            vec![ConnectionEntry {
+                system_a: 30000142,
+                name_a: String::from("Jita"),
+                system_b: 30000145,
+                name_b: String::from("New Caldari"),
+            }]
+        );
+        assert_eq!(diff.removed_connections.len(), 1);
+        assert_eq!(diff.removed_connections[0].name_a, "Perimeter");
+        assert_eq!(diff.removed_connections[0].name_b, "Amarr");
+        // Jita moved less than the tolerance
+        assert_eq!(
+            diff.moved_systems,
+            vec![MovedEntry {
+                id: 30000144,
+                name: String::from("Perimeter II"),
+                region_id: 10000002,
+                old: [10.0, 0.0],
+                new: [10.0, 5.0],
+            }]
+        );
+
+        let report = diff.to_string();
+        assert!(report.contains("  + 30000145 New Caldari (The Forge)"));
+        assert!(report.contains("  - 30002187 Amarr (Domain)"));
+        assert!(report.contains("  ~ 10000043 Domain → Domain Prime"));
+        assert!(report.contains("  - Perimeter » Amarr"));
+        let json: serde_json::Value = serde_json::from_str(&diff.to_json().unwrap()).unwrap();
+        assert_eq!(json["added_systems"][0]["id"], 30000145);
+    }
+
+    #[test]
+    fn compares_databases_without_the_other_tables() {
+        // no stations, factions or types, and a newer schema version
+        let (_old_folder, old) = database("diff-partial-old", OLD);
+        let (_new_folder, new) = database("diff-partial-new", NEW);
+        Connection::open(&new)
+            .unwrap()
+            .execute_batch("PRAGMA user_version = 9999;")
+            .unwrap();
+        assert!(SdeDiff::compare(&old, &new, 100.0).is_ok());
+    }
+
+    #[test]
+    fn fails_without_the_compared_tables() {
+        let (_folder, path) = database("diff-missing", OLD);
+        let missing = path.with_file_name("missing.db");
+        assert!(matches!(
+            SdeDiff::compare(&path, &missing, DEFAULT_TOLERANCE),
+            Err(SdeError::MissingDatabase(_))
+        ));
+
+        let connection = Connection::open(&path).unwrap();
+        connection
+            .execute_batch("ALTER TABLE mapAbstractSystems DROP COLUMN y;")
+            .unwrap();
+        assert!(matches!(
+            SdeDiff::compare(&path, &path, DEFAULT_TOLERANCE),
+            Err(SdeError::MissingColumn(table, column)) if table == "mapAbstractSystems" && column == "y"
+        ));
+        connection
+            .execute_batch("DROP TABLE mapSystemConnections;")
+            .unwrap();
+        assert!(matches!(
+            SdeDiff::compare(&path, &path, DEFAULT_TOLERANCE),
+            Err(SdeError::MissingTable(table)) if table == "mapSystemConnections"
+        ));
+    }
+}
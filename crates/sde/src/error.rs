@@ -0,0 +1,92 @@
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::path::PathBuf;
+
+/// Errors returned while reading the SDE database
+#[derive(Debug)]
+pub enum SdeError {
+    /// The SDE database file does not exist
+    MissingDatabase(PathBuf),
+    /// A table needed by Telescope is not in the database
+    MissingTable(String),
+    /// A table is in the database, but one of its columns is missing
+    MissingColumn(String, String),
+    /// The database schema version is not supported, with the found and the expected version
+    WrongVersion(u32, u32),
+    /// The Solar System identifier is not in the database
+    SystemNotFound(u32),
+    /// The Region identifier is not in the database
+    RegionNotFound(u32),
+    /// The Constellation identifier is not in the database
+    ConstellationNotFound(u32),
+    /// Error returned by sqlite
+    Database(rusqlite::Error),
+    /// Error reading or writing a file
+    Io(std::io::Error),
+}
+
+impl SdeError {
+    /// Returns true when the error means the SDE database is missing or outdated and it
+    /// needs to be replaced
+    pub fn is_outdated(&self) -> bool {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        matches!(
+            self,
+            SdeError::MissingDatabase(_)
+                | SdeError::MissingTable(_)
+                | SdeError::MissingColumn(_, _)
+                | SdeError::WrongVersion(_, _)
+        )
+    }
+}
+
+impl Display for SdeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            SdeError::MissingDatabase(path) => {
+                write!(f, "SDE database not found at {}", path.display())
+            }
+            SdeError::MissingTable(table) => {
+                write!(f, "SDE database is outdated, table {table} is missing")
+            }
+            SdeError::MissingColumn(table, column) => write!(
+                f,
+                "SDE database is outdated, column {column} is missing in table {table}"
+            ),
+            SdeError::WrongVersion(found, expected) => write!(
+                f,
+                "SDE database version {found} is not supported, version {expected} is expected"
+            ),
+            SdeError::SystemNotFound(id) => write!(f, "Solar System with Id {id} not found"),
+            SdeError::RegionNotFound(id) => write!(f, "Region with Id {id} not found"),
+            SdeError::ConstellationNotFound(id) => {
+                write!(f, "Constellation with Id {id} not found")
+            }
+            SdeError::Database(t_error) => t_error.fmt(f),
+            SdeError::Io(t_error) => t_error.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for SdeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SdeError::Database(t_error) => Some(t_error),
+            SdeError::Io(t_error) => Some(t_error),
+            _ => None,
+        }
+    }
+}
+
+impl From<rusqlite::Error> for SdeError {
+    fn from(value: rusqlite::Error) -> Self {
+        SdeError::Database(value)
+    }
+}
+
+impl From<std::io::Error> for SdeError {
+    fn from(value: std::io::Error) -> Self {
+        SdeError::Io(value)
+    }
+}
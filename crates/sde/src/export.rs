@@ -0,0 +1,577 @@
+use crate::objects::{Projection, Universe};
+use serde::Serialize;
+use serde_json::{Value, json};
+use std::collections::{BTreeSet, HashMap};
+
+/// File format written by the exporters
+#[derive(Hash, PartialEq, Eq, Clone, Copy, Debug)]
+pub enum ExportFormat {
+    /// Graphviz DOT, with a cluster by Region
+    Dot,
+    /// GraphML, with the attributes of the Solar Systems as data keys
+    GraphMl,
+    /// GeoJSON feature collection, with a point by Solar System and a line by stargate.
+    /// The coordinates are map units, not longitude and latitude.
+    GeoJson,
+    /// Plain JSON with the Regions, Constellations, Solar Systems and connections
+    Json,
+}
+
+impl ExportFormat {
+    /// Returns the format with the name, like `dot` or `geojson`, ignoring the case
+    pub fn from_name(name: &str) -> Option<Self> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        match name.to_lowercase().as_str() {
+            "dot" | "gv" => Some(ExportFormat::Dot),
+            "graphml" => Some(ExportFormat::GraphMl),
+            "geojson" => Some(ExportFormat::GeoJson),
+            "json" => Some(ExportFormat::Json),
+            _ => None,
+        }
+    }
+
+    /// Usual file extension of the format
+    pub fn extension(&self) -> &'static str {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        match self {
+            ExportFormat::Dot => "dot",
+            ExportFormat::GraphMl => "graphml",
+            ExportFormat::GeoJson => "geojson",
+            ExportFormat::Json => "json",
+        }
+    }
+}
+
+/// Coordinates written for every Solar System
+#[derive(Hash, PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub enum ExportCoordinates {
+    /// Real position seen from the top, the one of the universe map
+    #[default]
+    Projected,
+    /// Position in the abstract map of its Region, systems without one are left out
+    Abstract,
+}
+
+/// Region written by the exporters
+#[derive(Serialize, Clone, PartialEq, Debug)]
+pub struct ExportRegion {
+    /// Region identifier
+    pub id: u32,
+    /// Region name
+    pub name: String,
+}
+
+/// Constellation written by the exporters
+#[derive(Serialize, Clone, PartialEq, Debug)]
+pub struct ExportConstellation {
+    /// Constellation identifier
+    pub id: u32,
+    /// Constellation name
+    pub name: String,
+    /// Region identifier
+    pub region_id: u32,
+}
+
+/// Solar System written by the exporters
+#[derive(Serialize, Clone, PartialEq, Debug)]
+pub struct ExportSystem {
+    /// Solar System identifier
+    pub id: u32,
+    /// Solar System name
+    pub name: String,
+    /// Constellation identifier
+    pub constellation_id: u32,
+    /// Constellation name
+    pub constellation: String,
+    /// Region identifier
+    pub region_id: u32,
+    /// Region name
+    pub region: String,
+    /// Raw security status
+    pub security: f32,
+    /// Map coordinates, projected or abstract
+    pub x: f64,
+    /// Map coordinates, projected or abstract
+    pub y: f64,
+}
+
+/// Stargate connection written by the exporters
+#[derive(Serialize, Clone, PartialEq, Debug)]
+pub struct ExportConnection {
+    /// Identifier of the connection, the lowest and the highest Solar System identifiers
+    /// joined by a dash like the `systemConnectionId` written by the importer
+    pub id: String,
+    /// Solar System with the lowest identifier
+    pub system_a: u32,
+    /// Solar System with the highest identifier
+    pub system_b: u32,
+    /// True when the stargate goes from one Region to another
+    pub interregional: bool,
+}
+
+/// Stargate graph of the Universe or some of its Regions, ready to be written in any of
+/// the export formats. Every list is sorted by identifier, so the files are stable.
+#[derive(Serialize, Clone, PartialEq, Debug)]
+pub struct UniverseExport {
+    /// Coordinates of the Solar Systems
+    #[serde(skip)]
+    pub coordinates: ExportCoordinates,
+    /// Regions with at least one exported Solar System
+    pub regions: Vec<ExportRegion>,
+    /// Constellations with at least one exported Solar System
+    pub constellations: Vec<ExportConstellation>,
+    /// Exported Solar Systems
+    pub systems: Vec<ExportSystem>,
+    /// Stargates between two exported Solar Systems
+    pub connections: Vec<ExportConnection>,
+}
+
+impl UniverseExport {
+    /// Creates a new empty UniverseExport
+    pub fn new() -> Self {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        UniverseExport {
+            coordinates: ExportCoordinates::Projected,
+            regions: Vec::new(),
+            constellations: Vec::new(),
+            systems: Vec::new(),
+            connections: Vec::new(),
+        }
+    }
+
+    /// Builds the export from the Solar Systems of the Regions, or of every Region when
+    /// the list is empty, using the projected coordinates
+    pub fn from_universe(universe: &Universe, regions: &[u32]) -> Self {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let mut export = UniverseExport::new();
+        for system in universe.solar_systems.values() {
+            if !regions.is_empty() && !regions.contains(&system.region) {
+                continue;
+            }
+            let [x, y] = system.projected_coords.project(Projection::Top);
+            export.systems.push(ExportSystem {
+                id: system.id,
+                name: system.name.clone(),
+                constellation_id: system.constellation,
+                constellation: universe
+                    .constellations
+                    .get(&system.constellation)
+                    .map(|constellation| constellation.name.clone())
+                    .unwrap_or_default(),
+                region_id: system.region,
+                region: universe
+                    .regions
+                    .get(&system.region)
+                    .map(|region| region.name.clone())
+                    .unwrap_or_default(),
+                security: system.security,
+                x,
+                y,
+            });
+        }
+        export.systems.sort_by_key(|system| system.id);
+
+        let regions: HashMap<u32, u32> = export
+            .systems
+            .iter()
+            .map(|system| (system.id, system.region_id))
+            .collect();
+        let mut pairs = BTreeSet::new();
+        for system in &export.systems {
+            for connection in &universe.solar_systems[&system.id].connections {
+                if regions.contains_key(connection) {
+                    pairs.insert((system.id.min(*connection), system.id.max(*connection)));
+                }
+            }
+        }
+        export.connections = pairs
+            .into_iter()
+            .map(|(system_a, system_b)| ExportConnection {
+                id: system_a.to_string() + "-" + system_b.to_string().as_str(),
+                system_a,
+                system_b,
+                interregional: regions[&system_a] != regions[&system_b],
+            })
+            .collect();
+        export.collect_areas();
+        export
+    }
+
+    /// Replaces the coordinates with the abstract map ones, by Solar System identifier.
+    /// The Solar Systems without abstract coordinates and their stargates are removed.
+    pub fn set_abstract_coordinates(&mut self, points: &HashMap<u32, [f64; 2]>) {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        self.coordinates = ExportCoordinates::Abstract;
+        self.systems
+            .retain_mut(|system| match points.get(&system.id) {
+                Some([x, y]) => {
+                    system.x = *x;
+                    system.y = *y;
+                    true
+                }
+                None => false,
+            });
+        self.connections.retain(|connection| {
+            points.contains_key(&connection.system_a) && points.contains_key(&connection.system_b)
+        });
+        self.collect_areas();
+    }
+
+    /// Writes the export in the format
+    pub fn write(&self, format: ExportFormat) -> Result<String, serde_json::Error> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        match format {
+            ExportFormat::Dot => Ok(self.to_dot()),
+            ExportFormat::GraphMl => Ok(self.to_graphml()),
+            ExportFormat::GeoJson => self.to_geojson(),
+            ExportFormat::Json => self.to_json(),
+        }
+    }
+
+    /// Returns the export as an undirected Graphviz graph, with a cluster by Region and
+    /// the coordinates as fixed node positions
+    pub fn to_dot(&self) -> String {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let mut text = String::from("graph universe {\n");
+        text += "  node [shape=point];\n";
+        for region in &self.regions {
+            text += &format!("  subgraph cluster_{} {{\n", region.id);
+            text += &format!("    label=\"{}\";\n", dot_escape(&region.name));
+            for system in self.systems.iter().filter(|s| s.region_id == region.id) {
+                text += &format!(
+                    "    {} [label=\"{}\", constellation_id={}, constellation=\"{}\", \
+                     region_id={}, security={:.3}, pos=\"{},{}!\"];\n",
+                    system.id,
+                    dot_escape(&system.name),
+                    system.constellation_id,
+                    dot_escape(&system.constellation),
+                    system.region_id,
+                    system.security,
+                    system.x,
+                    system.y
+                );
+            }
+            text += "  }\n";
+        }
+        for connection in &self.connections {
+            text += &format!("  {} -- {};\n", connection.system_a, connection.system_b);
+        }
+        text += "}\n";
+        text
+    }
+
+    /// Returns the export as a GraphML undirected graph
+    pub fn to_graphml(&self) -> String {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let mut text = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        text += "<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n";
+        for (key, kind) in [
+            ("name", "string"),
+            ("constellation_id", "int"),
+            ("constellation", "string"),
+            ("region_id", "int"),
+            ("region", "string"),
+            ("security", "double"),
+            ("x", "double"),
+            ("y", "double"),
+        ] {
+            text += &format!(
+                "  <key id=\"{key}\" for=\"node\" attr.name=\"{key}\" attr.type=\"{kind}\"/>\n"
+            );
+        }
+        text += "  <key id=\"interregional\" for=\"edge\" attr.name=\"interregional\" \
+                 attr.type=\"boolean\"/>\n";
+        text += "  <graph id=\"universe\" edgedefault=\"undirected\">\n";
+        for system in &self.systems {
+            text += &format!("    <node id=\"{}\">\n", system.id);
+            for (key, value) in [
+                ("name", xml_escape(&system.name)),
+                ("constellation_id", system.constellation_id.to_string()),
+                ("constellation", xml_escape(&system.constellation)),
+                ("region_id", system.region_id.to_string()),
+                ("region", xml_escape(&system.region)),
+                ("security", system.security.to_string()),
+                ("x", system.x.to_string()),
+                ("y", system.y.to_string()),
+            ] {
+                text += &format!("      <data key=\"{key}\">{value}</data>\n");
+            }
+            text += "    </node>\n";
+        }
+        for connection in &self.connections {
+            text += &format!(
+                "    <edge id=\"{}\" source=\"{}\" target=\"{}\">\n",
+                connection.id, connection.system_a, connection.system_b
+            );
+            text += &format!(
+                "      <data key=\"interregional\">{}</data>\n",
+                connection.interregional
+            );
+            text += "    </edge>\n";
+        }
+        text += "  </graph>\n</graphml>\n";
+        text
+    }
+
+    /// Returns the export as a GeoJSON feature collection, with a Point by Solar System
+    /// and a LineString by stargate
+    pub fn to_geojson(&self) -> Result<String, serde_json::Error> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let positions: HashMap<u32, [f64; 2]> = self
+            .systems
+            .iter()
+            .map(|system| (system.id, [system.x, system.y]))
+            .collect();
+        let mut features: Vec<Value> =
+            Vec::with_capacity(self.systems.len() + self.connections.len());
+        for system in &self.systems {
+            features.push(json!({
+                "type": "Feature",
+                "id": system.id,
+                "geometry": {"type": "Point", "coordinates": [system.x, system.y]},
+                "properties": {
+                    "kind": "system",
+                    "name": system.name,
+                    "constellation_id": system.constellation_id,
+                    "constellation": system.constellation,
+                    "region_id": system.region_id,
+                    "region": system.region,
+                    "security": system.security,
+                },
+            }));
+        }
+        for connection in &self.connections {
+            features.push(json!({
+                "type": "Feature",
+                "id": connection.id,
+                "geometry": {
+                    "type": "LineString",
+                    "coordinates": [
+                        positions[&connection.system_a],
+                        positions[&connection.system_b],
+                    ],
+                },
+                "properties": {
+                    "kind": "connection",
+                    "system_a": connection.system_a,
+                    "system_b": connection.system_b,
+                    "interregional": connection.interregional,
+                },
+            }));
+        }
+        serde_json::to_string_pretty(&json!({
+            "type": "FeatureCollection",
+            "features": features,
+        }))
+    }
+
+    /// Returns the export as pretty printed JSON
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        serde_json::to_string_pretty(self)
+    }
+
+    // rebuilds the Regions and Constellations from the exported Solar Systems
+    fn collect_areas(&mut self) {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let mut regions = BTreeSet::new();
+        let mut constellations = BTreeSet::new();
+        for system in &self.systems {
+            regions.insert((system.region_id, system.region.clone()));
+            constellations.insert((
+                system.constellation_id,
+                system.constellation.clone(),
+                system.region_id,
+            ));
+        }
+        self.regions = regions
+            .into_iter()
+            .map(|(id, name)| ExportRegion { id, name })
+            .collect();
+        self.constellations = constellations
+            .into_iter()
+            .map(|(id, name, region_id)| ExportConstellation {
+                id,
+                name,
+                region_id,
+            })
+            .collect();
+    }
+}
+
+impl Default for UniverseExport {
+    fn default() -> Self {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        Self::new()
+    }
+}
+
+/// Escapes the quotes and backslashes of a DOT string
+fn dot_escape(text: &str) -> String {
+    #[cfg(feature = "puffin")]
+    puffin::profile_function!();
+
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Escapes the characters that can not be in XML text or attributes
+fn xml_escape(text: &str) -> String {
+    #[cfg(feature = "puffin")]
+    puffin::profile_function!();
+
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spatial::LIGHT_YEAR;
+    use crate::testing::{CONSTELLATION, REGION, placed, universe};
+
+    // 1 - 2 - 3 in The Forge and 3 - 4 crossing to Domain
+    fn export() -> UniverseExport {
+        let mut systems = vec![
+            placed(2, 0.9, [1.0, 0.0, 2.0]),
+            placed(1, 0.9, [0.0, 0.0, 0.0]),
+            placed(3, 0.5, [2.0, 0.0, 0.0]),
+            placed(4, -0.2, [3.0, 0.0, 0.0]),
+        ];
+        systems[0].name = String::from("Jita \"A\" & <B>");
+        systems[3].region = 10000043;
+        systems[3].constellation = 20000322;
+        let universe = universe(systems, &[(1, 2), (2, 3), (3, 2), (3, 4)]);
+        UniverseExport::from_universe(&universe, &[])
+    }
+
+    #[test]
+    fn format_names_and_extensions() {
+        for format in [
+            ExportFormat::Dot,
+            ExportFormat::GraphMl,
+            ExportFormat::GeoJson,
+            ExportFormat::Json,
+        ] {
+            assert_eq!(ExportFormat::from_name(format.extension()), Some(format));
+        }
+        assert_eq!(ExportFormat::from_name("GV"), Some(ExportFormat::Dot));
+        assert_eq!(ExportFormat::from_name("svg"), None);
+    }
+
+    #[test]
+    fn builds_sorted_lists_from_the_universe() {
+        let export = export();
+        let ids: Vec<u32> = export.systems.iter().map(|system| system.id).collect();
+        assert_eq!(ids, vec![1, 2, 3, 4]);
+        let jita = &export.systems[1];
+        assert_eq!(jita.region, format!("Region {REGION}"));
+        assert_eq!(jita.constellation, format!("Constellation {CONSTELLATION}"));
+        assert_eq!(jita.x, LIGHT_YEAR as i64 as f64);
+        assert_eq!(jita.y, (2.0 * LIGHT_YEAR) as i64 as f64);
+
+        // the stargates are written once, the lowest identifier first
+        let connections: Vec<(&str, bool)> = export
+            .connections
+            .iter()
+            .map(|connection| (connection.id.as_str(), connection.interregional))
+            .collect();
+        assert_eq!(
+            connections,
+            vec![("1-2", false), ("2-3", false), ("3-4", true)]
+        );
+        assert_eq!(export.regions.len(), 2);
+        assert_eq!(export.constellations[1].region_id, 10000043);
+    }
+
+    #[test]
+    fn exports_only_the_selected_regions() {
+        let universe = universe(
+            vec![placed(1, 0.9, [0.0; 3]), placed(2, 0.9, [1.0; 3])],
+            &[(1, 2)],
+        );
+        let export = UniverseExport::from_universe(&universe, &[REGION]);
+        assert_eq!(export.systems.len(), 2);
+        let export = UniverseExport::from_universe(&universe, &[10000043]);
+        assert!(export == UniverseExport::new());
+    }
+
+    #[test]
+    fn abstract_coordinates_leave_out_the_systems_without_them() {
+        let mut export = export();
+        let points = HashMap::from([(1, [10.0, 20.0]), (2, [30.0, 40.0]), (4, [0.0, 0.0])]);
+        export.set_abstract_coordinates(&points);
+        assert_eq!(export.coordinates, ExportCoordinates::Abstract);
+        let ids: Vec<u32> = export.systems.iter().map(|system| system.id).collect();
+        assert_eq!(ids, vec![1, 2, 4]);
+        assert_eq!((export.systems[0].x, export.systems[0].y), (10.0, 20.0));
+        assert_eq!(export.connections.len(), 1);
+        assert_eq!(export.connections[0].id, "1-2");
+        // the Regions and Constellations follow the Solar Systems that are left
+        assert_eq!(export.regions.len(), 2);
+    }
+
+    #[test]
+    fn writes_escaped_dot_and_graphml() {
+        let export = export();
+        let dot = export.write(ExportFormat::Dot).unwrap();
+        assert!(dot.starts_with("graph universe {\n"));
+        assert!(dot.contains(&format!("  subgraph cluster_{REGION} {{\n")));
+        assert!(dot.contains("label=\"Jita \\\"A\\\" & <B>\""));
+        assert!(dot.contains("  3 -- 4;\n"));
+        assert_eq!(dot.matches(" -- ").count(), 3);
+
+        let graphml = export.write(ExportFormat::GraphMl).unwrap();
+        assert!(graphml.contains("<data key=\"name\">Jita &quot;A&quot; &amp; &lt;B&gt;</data>"));
+        assert!(graphml.contains("<edge id=\"3-4\" source=\"3\" target=\"4\">"));
+        assert!(graphml.contains("<data key=\"interregional\">true</data>"));
+        assert_eq!(graphml.matches("<node ").count(), 4);
+    }
+
+    #[test]
+    fn writes_geojson_and_json() {
+        let export = export();
+        let geojson: Value =
+            serde_json::from_str(&export.write(ExportFormat::GeoJson).unwrap()).unwrap();
+        assert_eq!(geojson["type"], "FeatureCollection");
+        let features = geojson["features"].as_array().unwrap();
+        assert_eq!(features.len(), 7);
+        assert_eq!(features[0]["geometry"]["type"], "Point");
+        assert_eq!(features[4]["id"], "1-2");
+        assert_eq!(
+            features[4]["geometry"]["coordinates"][1],
+            json!([export.systems[1].x, export.systems[1].y])
+        );
+
+        let json: Value = serde_json::from_str(&export.write(ExportFormat::Json).unwrap()).unwrap();
+        assert_eq!(json["systems"].as_array().unwrap().len(), 4);
+        assert_eq!(json["connections"][2]["interregional"], true);
+        assert!(json.get("coordinates").is_none());
+    }
+}
@@ -0,0 +1,1167 @@
+use crate::SCHEMA_VERSION;
+use crate::wormhole::{WormholeClass, WormholeEffect};
+use rusqlite::{Connection, Transaction, params};
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::error::Error;
+use std::fs::{File, remove_file, rename};
+use std::io::{BufRead, BufReader, Cursor, Read};
+use std::path::{Path, PathBuf};
+use zip::ZipArchive;
+
+/// Tables and indexes created by the importer. They are the ones read by `SdeManager`.
+const SCHEMA: &str = "
+CREATE TABLE mapRegions (
+    regionId INTEGER PRIMARY KEY,
+    regionName TEXT NOT NULL,
+    factionId INTEGER
+);
+CREATE TABLE mapConstellations (
+    constellationId INTEGER PRIMARY KEY,
+    constellationName TEXT NOT NULL,
+    regionId INTEGER NOT NULL,
+    centerX REAL NOT NULL,
+    centerY REAL NOT NULL,
+    centerZ REAL NOT NULL
+);
+CREATE TABLE mapSolarSystems (
+    solarSystemId INTEGER PRIMARY KEY,
+    solarSystemName TEXT NOT NULL,
+    regionId INTEGER NOT NULL,
+    constellationId INTEGER NOT NULL,
+    x REAL NOT NULL,
+    y REAL NOT NULL,
+    z REAL NOT NULL,
+    projX REAL NOT NULL,
+    projY REAL NOT NULL,
+    projZ REAL NOT NULL,
+    security REAL NOT NULL,
+    wormholeClassId INTEGER NOT NULL DEFAULT 0,
+    wormholeEffect TEXT,
+    factionId INTEGER
+);
+CREATE TABLE mapSystemConnections (
+    systemConnectionId TEXT PRIMARY KEY,
+    systemA INTEGER NOT NULL,
+    systemB INTEGER NOT NULL
+);
+CREATE TABLE mapAbstractSystems (
+    solarSystemId INTEGER PRIMARY KEY,
+    regionId INTEGER NOT NULL,
+    x REAL NOT NULL,
+    y REAL NOT NULL
+);
+CREATE TABLE mapPlanets (
+    planetId INTEGER PRIMARY KEY,
+    planetaryIndex INTEGER NOT NULL,
+    solarSystemId INTEGER NOT NULL
+);
+CREATE TABLE mapMoons (
+    moonId INTEGER PRIMARY KEY,
+    moonIndex INTEGER NOT NULL,
+    solarSystemId INTEGER NOT NULL,
+    planetId INTEGER NOT NULL
+);
+CREATE TABLE mapWormholeStatics (
+    solarSystemId INTEGER NOT NULL,
+    wormholeType TEXT NOT NULL,
+    destinationClassId INTEGER NOT NULL,
+    PRIMARY KEY (solarSystemId, wormholeType)
+);
+CREATE TABLE staStations (
+    stationId INTEGER PRIMARY KEY,
+    stationName TEXT NOT NULL,
+    solarSystemId INTEGER NOT NULL,
+    orbitId INTEGER NOT NULL,
+    corporationId INTEGER NOT NULL,
+    operationId INTEGER NOT NULL,
+    typeId INTEGER NOT NULL
+);
+CREATE TABLE chrFactions (
+    factionId INTEGER PRIMARY KEY,
+    factionName TEXT NOT NULL
+);
+CREATE TABLE invCategories (
+    categoryId INTEGER PRIMARY KEY,
+    categoryName TEXT NOT NULL,
+    published INTEGER NOT NULL DEFAULT 0
+);
+CREATE TABLE invGroups (
+    groupId INTEGER PRIMARY KEY,
+    groupName TEXT NOT NULL,
+    categoryId INTEGER NOT NULL,
+    published INTEGER NOT NULL DEFAULT 0
+);
+CREATE TABLE invTypes (
+    typeId INTEGER PRIMARY KEY,
+    typeName TEXT NOT NULL,
+    groupId INTEGER NOT NULL,
+    published INTEGER NOT NULL DEFAULT 0
+);
+CREATE TABLE staServices (
+    serviceId INTEGER PRIMARY KEY,
+    serviceName TEXT NOT NULL
+);
+CREATE TABLE staOperationServices (
+    operationId INTEGER NOT NULL,
+    serviceId INTEGER NOT NULL,
+    PRIMARY KEY (operationId, serviceId)
+);
+CREATE INDEX idx_constellations_region ON mapConstellations (regionId);
+CREATE INDEX idx_systems_constellation ON mapSolarSystems (constellationId);
+CREATE INDEX idx_systems_region ON mapSolarSystems (regionId);
+CREATE INDEX idx_connections_a ON mapSystemConnections (systemA);
+CREATE INDEX idx_connections_b ON mapSystemConnections (systemB);
+CREATE INDEX idx_abstract_region ON mapAbstractSystems (regionId);
+CREATE INDEX idx_planets_system ON mapPlanets (solarSystemId);
+CREATE INDEX idx_moons_planet ON mapMoons (planetId);
+CREATE INDEX idx_stations_system ON staStations (solarSystemId);
+CREATE INDEX idx_groups_category ON invGroups (categoryId);
+CREATE INDEX idx_types_group ON invTypes (groupId);
+";
+
+/// File format of the static data export
+#[derive(Hash, PartialEq, Eq, Clone, Copy)]
+pub enum SdeFormat {
+    /// One JSON object per line, with the identifier in the `_key` field
+    JsonLines,
+    /// One YAML mapping per file, with the identifier as the key
+    Yaml,
+}
+
+impl SdeFormat {
+    fn extension(&self) -> &'static str {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        match self {
+            SdeFormat::JsonLines => "jsonl",
+            SdeFormat::Yaml => "yaml",
+        }
+    }
+}
+
+/// Number of rows written on every table by the importer
+#[derive(Hash, PartialEq, Eq, Clone, Default)]
+pub struct ImportSummary {
+    /// Regions imported
+    pub regions: usize,
+    /// Constellations imported
+    pub constellations: usize,
+    /// Solar Systems imported
+    pub solar_systems: usize,
+    /// Stargate connections between Solar Systems imported
+    pub connections: usize,
+    /// Planets imported
+    pub planets: usize,
+    /// Moons imported
+    pub moons: usize,
+    /// NPC Stations imported
+    pub stations: usize,
+    /// Station services imported
+    pub services: usize,
+    /// Wormhole space Solar Systems imported
+    pub wormhole_systems: usize,
+    /// Wormhole statics imported
+    pub statics: usize,
+    /// NPC factions imported
+    pub factions: usize,
+    /// Item categories imported
+    pub categories: usize,
+    /// Item groups imported
+    pub groups: usize,
+    /// Item types imported
+    pub types: usize,
+}
+
+#[derive(Deserialize)]
+struct Localized {
+    en: String,
+}
+
+#[derive(Deserialize)]
+struct Position {
+    x: f64,
+    y: f64,
+    z: f64,
+}
+
+#[derive(Deserialize)]
+struct RegionRecord {
+    #[serde(rename = "_key", default)]
+    key: u32,
+    name: Localized,
+    #[serde(rename = "wormholeClassID", default)]
+    wormhole_class_id: Option<u8>,
+    #[serde(rename = "factionID", default)]
+    faction_id: Option<u32>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ConstellationRecord {
+    #[serde(rename = "_key", default)]
+    key: u32,
+    name: Localized,
+    #[serde(rename = "regionID")]
+    region_id: u32,
+    position: Position,
+    #[serde(rename = "wormholeClassID", default)]
+    wormhole_class_id: Option<u8>,
+    #[serde(rename = "factionID", default)]
+    faction_id: Option<u32>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SolarSystemRecord {
+    #[serde(rename = "_key", default)]
+    key: u32,
+    name: Localized,
+    #[serde(rename = "regionID")]
+    region_id: u32,
+    #[serde(rename = "constellationID")]
+    constellation_id: u32,
+    position: Position,
+    #[serde(default)]
+    security_status: f64,
+    #[serde(rename = "wormholeClassID", default)]
+    wormhole_class_id: Option<u8>,
+    #[serde(default)]
+    secondary_sun: Option<SecondarySun>,
+    #[serde(rename = "factionID", default)]
+    faction_id: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct SecondarySun {
+    #[serde(rename = "typeID")]
+    type_id: u32,
+}
+
+#[derive(Deserialize)]
+struct TypeRecord {
+    #[serde(rename = "_key", default)]
+    key: u32,
+    name: Localized,
+    #[serde(rename = "groupID", default)]
+    group_id: u32,
+    #[serde(default)]
+    published: bool,
+}
+
+#[derive(Deserialize)]
+struct GroupRecord {
+    #[serde(rename = "_key", default)]
+    key: u32,
+    name: Localized,
+    #[serde(rename = "categoryID")]
+    category_id: u32,
+    #[serde(default)]
+    published: bool,
+}
+
+#[derive(Deserialize)]
+struct CategoryRecord {
+    #[serde(rename = "_key", default)]
+    key: u32,
+    name: Localized,
+    #[serde(default)]
+    published: bool,
+}
+
+#[derive(Deserialize)]
+struct StargateDestination {
+    #[serde(rename = "solarSystemID")]
+    solar_system_id: u32,
+}
+
+#[derive(Deserialize)]
+struct StargateRecord {
+    #[serde(rename = "_key", default)]
+    key: u32,
+    #[serde(rename = "solarSystemID")]
+    solar_system_id: u32,
+    destination: StargateDestination,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PlanetRecord {
+    #[serde(rename = "_key", default)]
+    key: u32,
+    #[serde(rename = "solarSystemID")]
+    solar_system_id: u32,
+    celestial_index: u32,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MoonRecord {
+    #[serde(rename = "_key", default)]
+    key: u32,
+    #[serde(rename = "solarSystemID")]
+    solar_system_id: u32,
+    #[serde(rename = "orbitID")]
+    orbit_id: u32,
+    orbit_index: u32,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CorporationRecord {
+    #[serde(rename = "_key", default)]
+    key: u32,
+    name: Localized,
+}
+
+#[derive(Deserialize)]
+struct FactionRecord {
+    #[serde(rename = "_key", default)]
+    key: u32,
+    name: Localized,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StationServiceRecord {
+    #[serde(rename = "_key", default)]
+    key: u32,
+    service_name: Localized,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StationOperationRecord {
+    #[serde(rename = "_key", default)]
+    key: u32,
+    operation_name: Localized,
+    #[serde(default)]
+    services: Vec<u32>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StationRecord {
+    #[serde(rename = "_key", default)]
+    key: u32,
+    #[serde(rename = "solarSystemID")]
+    solar_system_id: u32,
+    #[serde(rename = "orbitID")]
+    orbit_id: u32,
+    #[serde(rename = "ownerID")]
+    owner_id: u32,
+    #[serde(rename = "operationID")]
+    operation_id: u32,
+    #[serde(rename = "typeID")]
+    type_id: u32,
+    #[serde(default)]
+    use_operation_name: bool,
+}
+
+/// Records that carry their identifier, in YAML files it comes from the mapping key
+trait Keyed {
+    fn set_key(&mut self, key: u32);
+}
+
+macro_rules! keyed {
+    ($($record:ty),*) => {
+        $(impl Keyed for $record {
+            fn set_key(&mut self, key: u32) {
+                self.key = key;
+            }
+        })*
+    };
+}
+
+keyed!(
+    RegionRecord,
+    ConstellationRecord,
+    SolarSystemRecord,
+    StargateRecord,
+    PlanetRecord,
+    MoonRecord,
+    CorporationRecord,
+    FactionRecord,
+    StationServiceRecord,
+    StationOperationRecord,
+    StationRecord,
+    TypeRecord,
+    GroupRecord,
+    CategoryRecord
+);
+
+/// Builds the SDE sqlite database used by `SdeManager` from CCP's official static data
+/// export. The export can be the downloaded zip archive or a folder with the extracted
+/// files, in JSON lines or YAML format.
+///
+/// `mapAbstractSystems` is not part of the export, so the table is created empty. It can be
+/// filled with `SdeManager::generate_abstract_layout`.
+/// Wormhole statics are not part of it either, they are read from the optional statics
+/// file, see `SdeImporter::statics`.
+pub struct SdeImporter {
+    source: PathBuf,
+    /// Format of the export, when it is `None` it is detected from the file names
+    pub format: Option<SdeFormat>,
+    /// Text file with the wormhole statics, one per line with the Solar System name or
+    /// identifier, the wormhole type and the destination class separated by commas or
+    /// tabs, like `J100001,H296,C5`. Empty lines and lines starting with `#` are skipped.
+    pub statics: Option<PathBuf>,
+}
+
+impl SdeImporter {
+    /// Creates a new SdeImporter that reads the export from a zip archive or a folder
+    pub fn new(source: &Path) -> Self {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        SdeImporter {
+            source: source.to_path_buf(),
+            format: None,
+            statics: None,
+        }
+    }
+
+    /// Reads the export and writes the database on the destination path, replacing it if
+    /// exists. The database is written on a temporary file first, so the previous one is
+    /// kept if the import fails.
+    pub fn import(&self, destination: &Path) -> Result<ImportSummary, Box<dyn Error>> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let mut source = Source::open(&self.source)?;
+        let format = match self.format {
+            Some(format) => format,
+            None => source.detect_format()?,
+        };
+
+        let temp_path = destination.with_extension("import");
+        if temp_path.exists() {
+            remove_file(&temp_path)?;
+        }
+        let mut connection = Connection::open(&temp_path)?;
+        connection.execute_batch(SCHEMA)?;
+        connection.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+        let transaction = connection.transaction()?;
+        let mut summary = Self::write_tables(&mut source, format, &transaction)?;
+        if let Some(statics) = &self.statics {
+            summary.statics = Self::write_statics(statics, &transaction)?;
+        }
+        transaction.commit()?;
+        connection.close().map_err(|(_, t_error)| t_error)?;
+
+        rename(&temp_path, destination)?;
+        Ok(summary)
+    }
+
+    fn write_tables(
+        source: &mut Source,
+        format: SdeFormat,
+        transaction: &Transaction<'_>,
+    ) -> Result<ImportSummary, Box<dyn Error>> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let mut summary = ImportSummary::default();
+
+        // the wormhole class and the faction are usually set on the region, and sometimes
+        // on the constellation or on the Solar System
+        let mut classes = HashMap::new();
+        let mut factions = HashMap::new();
+        let mut statement = transaction.prepare(
+            "INSERT INTO mapRegions (regionId, regionName, factionId) VALUES (?1, ?2, ?3)",
+        )?;
+        for region in source.read_table::<RegionRecord>("mapRegions", format)? {
+            if let Some(class) = region.wormhole_class_id {
+                classes.insert(region.key, class);
+            }
+            if let Some(faction) = region.faction_id {
+                factions.insert(region.key, faction);
+            }
+            statement.execute(params![region.key, region.name.en, region.faction_id])?;
+            summary.regions += 1;
+        }
+
+        let mut statement = transaction.prepare(
+            "INSERT INTO mapConstellations (constellationId, constellationName, regionId, \
+             centerX, centerY, centerZ) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        )?;
+        for constellation in
+            source.read_table::<ConstellationRecord>("mapConstellations", format)?
+        {
+            if let Some(class) = constellation
+                .wormhole_class_id
+                .or(classes.get(&constellation.region_id).copied())
+            {
+                classes.insert(constellation.key, class);
+            }
+            if let Some(faction) = constellation
+                .faction_id
+                .or(factions.get(&constellation.region_id).copied())
+            {
+                factions.insert(constellation.key, faction);
+            }
+            statement.execute(params![
+                constellation.key,
+                constellation.name.en,
+                constellation.region_id,
+                constellation.position.x,
+                constellation.position.y,
+                constellation.position.z
+            ])?;
+            summary.constellations += 1;
+        }
+
+        // the universe map is drawn from the top, so the projection is the real position
+        let mut statement = transaction.prepare(
+            "INSERT INTO mapSolarSystems (solarSystemId, solarSystemName, regionId, \
+             constellationId, x, y, z, projX, projY, projZ, security, wormholeClassId, \
+             wormholeEffect, factionId) \
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+        )?;
+        let systems = source.read_table::<SolarSystemRecord>("mapSolarSystems", format)?;
+        // the effect comes from the secondary sun, its name is only in the types table
+        let suns: HashSet<u32> = systems
+            .iter()
+            .filter_map(|system| system.secondary_sun.as_ref())
+            .map(|sun| sun.type_id)
+            .collect();
+        // the types are also written to the catalog, older exports do not have them
+        let types = if source.has_table("types", format)? {
+            source.read_table::<TypeRecord>("types", format)?
+        } else {
+            Vec::new()
+        };
+        let mut effects = HashMap::new();
+        if !suns.is_empty() {
+            for item in &types {
+                if suns.contains(&item.key)
+                    && let Some(effect) = WormholeEffect::from_name(&item.name.en)
+                {
+                    effects.insert(item.key, effect.name());
+                }
+            }
+        }
+        // names of the celestials, NPC Station names are built from them
+        let mut names = HashMap::new();
+        for system in systems {
+            names.insert(system.key, system.name.en.clone());
+            let class = system
+                .wormhole_class_id
+                .or(classes.get(&system.constellation_id).copied())
+                .unwrap_or_default();
+            if WormholeClass::from_id(class).is_jspace() {
+                summary.wormhole_systems += 1;
+            }
+            let effect = system
+                .secondary_sun
+                .and_then(|sun| effects.get(&sun.type_id).copied());
+            let faction = system
+                .faction_id
+                .or(factions.get(&system.constellation_id).copied());
+            statement.execute(params![
+                system.key,
+                system.name.en,
+                system.region_id,
+                system.constellation_id,
+                system.position.x,
+                system.position.y,
+                system.position.z,
+                system.security_status,
+                class,
+                effect,
+                faction
+            ])?;
+            summary.solar_systems += 1;
+        }
+
+        // older exports do not have the factions file, the identifiers are kept anyway
+        if source.has_table("factions", format)? {
+            let mut statement = transaction
+                .prepare("INSERT INTO chrFactions (factionId, factionName) VALUES (?1, ?2)")?;
+            for faction in source.read_table::<FactionRecord>("factions", format)? {
+                statement.execute(params![faction.key, faction.name.en])?;
+                summary.factions += 1;
+            }
+        }
+
+        if source.has_table("categories", format)? {
+            let mut statement = transaction.prepare(
+                "INSERT INTO invCategories (categoryId, categoryName, published) \
+                 VALUES (?1, ?2, ?3)",
+            )?;
+            for category in source.read_table::<CategoryRecord>("categories", format)? {
+                statement.execute(params![category.key, category.name.en, category.published])?;
+                summary.categories += 1;
+            }
+        }
+        if source.has_table("groups", format)? {
+            let mut statement = transaction.prepare(
+                "INSERT INTO invGroups (groupId, groupName, categoryId, published) \
+                 VALUES (?1, ?2, ?3, ?4)",
+            )?;
+            for group in source.read_table::<GroupRecord>("groups", format)? {
+                statement.execute(params![
+                    group.key,
+                    group.name.en,
+                    group.category_id,
+                    group.published
+                ])?;
+                summary.groups += 1;
+            }
+        }
+        let mut statement = transaction.prepare(
+            "INSERT INTO invTypes (typeId, typeName, groupId, published) VALUES (?1, ?2, ?3, ?4)",
+        )?;
+        for item in types {
+            statement.execute(params![
+                item.key,
+                item.name.en,
+                item.group_id,
+                item.published
+            ])?;
+            summary.types += 1;
+        }
+
+        // every stargate has its pair in the destination, only one row is stored per pair
+        let mut statement = transaction.prepare(
+            "INSERT INTO mapSystemConnections (systemConnectionId, systemA, systemB) \
+             VALUES (?1, ?2, ?3)",
+        )?;
+        let mut connections = HashSet::new();
+        for stargate in source.read_table::<StargateRecord>("mapStargates", format)? {
+            let system_a = stargate
+                .solar_system_id
+                .min(stargate.destination.solar_system_id);
+            let system_b = stargate
+                .solar_system_id
+                .max(stargate.destination.solar_system_id);
+            if connections.insert((system_a, system_b)) {
+                let id = system_a.to_string() + "-" + system_b.to_string().as_str();
+                statement.execute(params![id, system_a, system_b])?;
+                summary.connections += 1;
+            }
+        }
+
+        let mut statement = transaction.prepare(
+            "INSERT INTO mapPlanets (planetId, planetaryIndex, solarSystemId) VALUES (?1, ?2, ?3)",
+        )?;
+        for planet in source.read_table::<PlanetRecord>("mapPlanets", format)? {
+            let mut name = names
+                .get(&planet.solar_system_id)
+                .cloned()
+                .unwrap_or_default();
+            name += " ";
+            name += roman_numeral(planet.celestial_index).as_str();
+            names.insert(planet.key, name);
+            statement.execute(params![
+                planet.key,
+                planet.celestial_index,
+                planet.solar_system_id
+            ])?;
+            summary.planets += 1;
+        }
+
+        let mut statement = transaction.prepare(
+            "INSERT INTO mapMoons (moonId, moonIndex, solarSystemId, planetId) \
+             VALUES (?1, ?2, ?3, ?4)",
+        )?;
+        for moon in source.read_table::<MoonRecord>("mapMoons", format)? {
+            let mut name = names.get(&moon.orbit_id).cloned().unwrap_or_default();
+            name += " - Moon ";
+            name += moon.orbit_index.to_string().as_str();
+            names.insert(moon.key, name);
+            statement.execute(params![
+                moon.key,
+                moon.orbit_index,
+                moon.solar_system_id,
+                moon.orbit_id
+            ])?;
+            summary.moons += 1;
+        }
+
+        let mut statement = transaction
+            .prepare("INSERT INTO staServices (serviceId, serviceName) VALUES (?1, ?2)")?;
+        for service in source.read_table::<StationServiceRecord>("stationServices", format)? {
+            statement.execute(params![service.key, service.service_name.en])?;
+            summary.services += 1;
+        }
+
+        let mut statement = transaction.prepare(
+            "INSERT OR IGNORE INTO staOperationServices (operationId, serviceId) VALUES (?1, ?2)",
+        )?;
+        let mut operations = HashMap::new();
+        for operation in source.read_table::<StationOperationRecord>("stationOperations", format)? {
+            for service in &operation.services {
+                statement.execute(params![operation.key, service])?;
+            }
+            operations.insert(operation.key, operation.operation_name.en);
+        }
+
+        let mut corporations = HashMap::new();
+        for corporation in source.read_table::<CorporationRecord>("npcCorporations", format)? {
+            corporations.insert(corporation.key, corporation.name.en);
+        }
+
+        // the export has no station names, they are built like the game does:
+        // "<orbit name> - <owner corporation> [<operation name>]"
+        let mut statement = transaction.prepare(
+            "INSERT INTO staStations (stationId, stationName, solarSystemId, orbitId, \
+             corporationId, operationId, typeId) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        )?;
+        for station in source.read_table::<StationRecord>("npcStations", format)? {
+            let mut name = names
+                .get(&station.orbit_id)
+                .or(names.get(&station.solar_system_id))
+                .cloned()
+                .unwrap_or_default();
+            name += " - ";
+            name += corporations
+                .get(&station.owner_id)
+                .map(String::as_str)
+                .unwrap_or_default();
+            if station.use_operation_name
+                && let Some(operation) = operations.get(&station.operation_id)
+            {
+                name += " ";
+                name += operation.as_str();
+            }
+            statement.execute(params![
+                station.key,
+                name,
+                station.solar_system_id,
+                station.orbit_id,
+                station.owner_id,
+                station.operation_id,
+                station.type_id
+            ])?;
+            summary.stations += 1;
+        }
+
+        Ok(summary)
+    }
+
+    fn write_statics(path: &Path, transaction: &Transaction<'_>) -> Result<usize, Box<dyn Error>> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let mut find_system = transaction.prepare(
+            "SELECT solarSystemId FROM mapSolarSystems \
+             WHERE solarSystemId = ?1 OR solarSystemName = ?1 COLLATE NOCASE",
+        )?;
+        let mut statement = transaction.prepare(
+            "INSERT OR REPLACE INTO mapWormholeStatics (solarSystemId, wormholeType, \
+             destinationClassId) VALUES (?1, ?2, ?3)",
+        )?;
+        let mut count = 0;
+        for (index, line) in BufReader::new(File::open(path)?).lines().enumerate() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let fields: Vec<&str> = line.split([',', '\t']).map(str::trim).collect();
+            let error = || format!("statics line {}: {}", index + 1, line);
+            if fields.len() != 3 || fields[1].is_empty() {
+                return Err(error().into());
+            }
+            let destination = WormholeClass::from_label(fields[2]).ok_or_else(error)?;
+            let system: u32 = find_system
+                .query_row(params![fields[0]], |row| row.get(0))
+                .map_err(|_| error() + " (unknown Solar System)")?;
+            statement.execute(params![system, fields[1].to_uppercase(), destination.id()])?;
+            count += 1;
+        }
+        Ok(count)
+    }
+}
+
+/// Returns the roman numeral used in the celestial names, like "Jita IV"
+fn roman_numeral(value: u32) -> String {
+    #[cfg(feature = "puffin")]
+    puffin::profile_function!();
+
+    let numerals = [
+        (1000, "M"),
+        (900, "CM"),
+        (500, "D"),
+        (400, "CD"),
+        (100, "C"),
+        (90, "XC"),
+        (50, "L"),
+        (40, "XL"),
+        (10, "X"),
+        (9, "IX"),
+        (5, "V"),
+        (4, "IV"),
+        (1, "I"),
+    ];
+    let mut result = String::new();
+    let mut value = value;
+    for (number, numeral) in numerals {
+        while value >= number {
+            result += numeral;
+            value -= number;
+        }
+    }
+    result
+}
+
+/// Where the export files are read from
+enum Source {
+    Folder(PathBuf),
+    Archive(ZipArchive<File>),
+}
+
+impl Source {
+    fn open(path: &Path) -> Result<Self, Box<dyn Error>> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        if path.is_dir() {
+            Ok(Source::Folder(path.to_path_buf()))
+        } else {
+            Ok(Source::Archive(ZipArchive::new(File::open(path)?)?))
+        }
+    }
+
+    /// Returns every file name of the export, without folders
+    fn file_names(&self) -> Result<Vec<String>, Box<dyn Error>> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let names = match self {
+            Source::Folder(path) => {
+                let mut names = Vec::new();
+                for entry in path.read_dir()? {
+                    names.push(entry?.file_name().to_string_lossy().to_string());
+                }
+                names
+            }
+            Source::Archive(archive) => archive
+                .file_names()
+                .map(|name| name.rsplit('/').next().unwrap_or(name).to_string())
+                .collect(),
+        };
+        Ok(names)
+    }
+
+    /// Returns true if the export has the file of the table
+    fn has_table(&self, table: &str, format: SdeFormat) -> Result<bool, Box<dyn Error>> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let file_name = table.to_string() + "." + format.extension();
+        Ok(self.file_names()?.contains(&file_name))
+    }
+
+    fn detect_format(&self) -> Result<SdeFormat, Box<dyn Error>> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let names = self.file_names()?;
+        for format in [SdeFormat::JsonLines, SdeFormat::Yaml] {
+            let file_name = "mapSolarSystems.".to_string() + format.extension();
+            if names.contains(&file_name) {
+                return Ok(format);
+            }
+        }
+        Err("mapSolarSystems.jsonl or mapSolarSystems.yaml not found in the export".into())
+    }
+
+    fn open_file(&mut self, file_name: &str) -> Result<Box<dyn Read + '_>, Box<dyn Error>> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        match self {
+            Source::Folder(path) => Ok(Box::new(File::open(path.join(file_name))?)),
+            Source::Archive(archive) => {
+                let full_name = archive
+                    .file_names()
+                    .find(|name| name.rsplit('/').next() == Some(file_name))
+                    .map(String::from)
+                    .ok_or_else(|| file_name.to_string() + " not found in the export")?;
+                let mut data = Vec::new();
+                archive.by_name(&full_name)?.read_to_end(&mut data)?;
+                Ok(Box::new(Cursor::new(data)))
+            }
+        }
+    }
+
+    fn read_table<T: DeserializeOwned + Keyed>(
+        &mut self,
+        table: &str,
+        format: SdeFormat,
+    ) -> Result<Vec<T>, Box<dyn Error>> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let file_name = table.to_string() + "." + format.extension();
+        let reader = self.open_file(&file_name)?;
+        let mut records = Vec::new();
+        match format {
+            SdeFormat::JsonLines => {
+                for line in BufReader::new(reader).lines() {
+                    let line = line?;
+                    if !line.trim().is_empty() {
+                        records.push(serde_json::from_str::<T>(&line)?);
+                    }
+                }
+            }
+            SdeFormat::Yaml => {
+                let table: BTreeMap<u32, T> = serde_yaml::from_reader(reader)?;
+                for (key, mut record) in table {
+                    record.set_key(key);
+                    records.push(record);
+                }
+            }
+        }
+        Ok(records)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SdeManager;
+    use crate::testing::{TempDir, temp_dir};
+    use std::fs::write;
+
+    // a small export with two Solar Systems of The Forge, two of a wormhole region and one
+    // of Pochven that keeps an old gate to Jita
+    const EXPORT: [(&str, &str); 14] = [
+        (
+            "mapRegions.jsonl",
+            concat!(
+                "{\"_key\": 10000002, \"name\": {\"en\": \"The Forge\", \"de\": \"Die Schmiede\"}, \"factionID\": 500001, \"constellationIDs\": [20000020]}\n",
+                "{\"_key\": 11000031, \"name\": {\"en\": \"E-R00031\"}, \"wormholeClassID\": 5}\n",
+                "{\"_key\": 10000070, \"name\": {\"en\": \"Pochven\"}, \"wormholeClassID\": 25}\n",
+            ),
+        ),
+        (
+            "mapConstellations.jsonl",
+            concat!(
+                "{\"_key\": 20000020, \"name\": {\"en\": \"Kimotoro\"}, \"regionID\": 10000002, \"position\": {\"x\": 1.0, \"y\": 2.0, \"z\": 3.0}, \"solarSystemIDs\": [30000142, 30000144]}\n",
+                "{\"_key\": 21000300, \"name\": {\"en\": \"E-C00300\"}, \"regionID\": 11000031, \"position\": {\"x\": 1e19, \"y\": 0, \"z\": 1e19}}\n",
+                "{\"_key\": 20000788, \"name\": {\"en\": \"Krai Perun\"}, \"regionID\": 10000070, \"position\": {\"x\": 1e17, \"y\": 0, \"z\": 1e17}}\n",
+            ),
+        ),
+        (
+            "mapSolarSystems.jsonl",
+            concat!(
+                "{\"_key\": 30000142, \"name\": {\"en\": \"Jita\"}, \"regionID\": 10000002, \"constellationID\": 20000020, \"position\": {\"x\": -1.29e17, \"y\": 6.07e16, \"z\": 1.17e17}, \"securityStatus\": 0.9459, \"stargateIDs\": [50001248]}\n",
+                "{\"_key\": 30000144, \"name\": {\"en\": \"Perimeter\"}, \"regionID\": 10000002, \"constellationID\": 20000020, \"position\": {\"x\": -1.29e17, \"y\": 6.07e16, \"z\": 1.18e17}, \"factionID\": 500010, \"securityStatus\": 0.95}\n",
+                "{\"_key\": 31000001, \"name\": {\"en\": \"J100001\"}, \"regionID\": 11000031, \"constellationID\": 21000300, \"position\": {\"x\": 1.0e19, \"y\": 1e15, \"z\": 1.0e19}, \"securityStatus\": -0.99, \"secondarySun\": {\"typeID\": 30575, \"effectBeaconTypeID\": 30883}}\n",
+                "{\"_key\": 31000002, \"name\": {\"en\": \"J100002\"}, \"regionID\": 11000031, \"constellationID\": 21000300, \"position\": {\"x\": 1.01e19, \"y\": 1e15, \"z\": 1.02e19}, \"securityStatus\": -0.99}\n",
+                "{\"_key\": 30000157, \"name\": {\"en\": \"Otela\"}, \"regionID\": 10000070, \"constellationID\": 20000788, \"position\": {\"x\": 1e17, \"y\": 0, \"z\": 1e17}, \"securityStatus\": -1.0}\n",
+            ),
+        ),
+        (
+            "mapStargates.jsonl",
+            concat!(
+                "{\"_key\": 50001248, \"solarSystemID\": 30000142, \"destination\": {\"solarSystemID\": 30000144, \"stargateID\": 50001249}}\n",
+                "{\"_key\": 50001249, \"solarSystemID\": 30000144, \"destination\": {\"solarSystemID\": 30000142, \"stargateID\": 50001248}}\n",
+                "{\"_key\": 50009999, \"solarSystemID\": 30000157, \"destination\": {\"solarSystemID\": 30000142, \"stargateID\": 50009998}}\n",
+            ),
+        ),
+        (
+            "mapPlanets.jsonl",
+            "{\"_key\": 40009077, \"solarSystemID\": 30000142, \"celestialIndex\": 4, \"orbitID\": 40009076}\n",
+        ),
+        (
+            "mapMoons.jsonl",
+            "{\"_key\": 40009078, \"solarSystemID\": 30000142, \"orbitID\": 40009077, \"orbitIndex\": 4, \"celestialIndex\": 4}\n",
+        ),
+        (
+            "npcCorporations.jsonl",
+            "{\"_key\": 1000035, \"name\": {\"en\": \"Caldari Navy\"}}\n",
+        ),
+        (
+            "npcStations.jsonl",
+            "{\"_key\": 60003760, \"celestialIndex\": 4, \"operationID\": 26, \"orbitID\": 40009078, \"orbitIndex\": 4, \"ownerID\": 1000035, \"solarSystemID\": 30000142, \"typeID\": 1529, \"useOperationName\": true}\n",
+        ),
+        (
+            "stationOperations.jsonl",
+            "{\"_key\": 26, \"activityID\": 1, \"operationName\": {\"en\": \"Assembly Plant\"}, \"services\": [5, 16, 64]}\n",
+        ),
+        (
+            "stationServices.jsonl",
+            concat!(
+                "{\"_key\": 5, \"serviceName\": {\"en\": \"Reprocessing Plant\"}}\n",
+                "{\"_key\": 16, \"serviceName\": {\"en\": \"Repair Facilities\"}}\n",
+                "{\"_key\": 64, \"serviceName\": {\"en\": \"Market\"}}\n",
+            ),
+        ),
+        (
+            "factions.jsonl",
+            concat!(
+                "{\"_key\": 500001, \"name\": {\"en\": \"Caldari State\"}}\n",
+                "{\"_key\": 500010, \"name\": {\"en\": \"Guristas Pirates\"}}\n",
+            ),
+        ),
+        (
+            "categories.jsonl",
+            concat!(
+                "{\"_key\": 4, \"name\": {\"en\": \"Material\"}, \"published\": true}\n",
+                "{\"_key\": 6, \"name\": {\"en\": \"Ship\"}, \"published\": true}\n",
+                "{\"_key\": 2, \"name\": {\"en\": \"Celestial\"}, \"published\": true}\n",
+            ),
+        ),
+        (
+            "groups.jsonl",
+            concat!(
+                "{\"_key\": 18, \"name\": {\"en\": \"Mineral\"}, \"categoryID\": 4, \"published\": true}\n",
+                "{\"_key\": 541, \"name\": {\"en\": \"Interdictor\"}, \"categoryID\": 6, \"published\": true}\n",
+                "{\"_key\": 833, \"name\": {\"en\": \"Force Recon Ship\"}, \"categoryID\": 6, \"published\": true}\n",
+                "{\"_key\": 906, \"name\": {\"en\": \"Combat Recon Ship\"}, \"categoryID\": 6, \"published\": true}\n",
+                "{\"_key\": 995, \"name\": {\"en\": \"Secondary Sun\"}, \"categoryID\": 2, \"published\": false}\n",
+            ),
+        ),
+        (
+            "types.jsonl",
+            concat!(
+                "{\"_key\": 30575, \"name\": {\"en\": \"Black Hole\"}, \"groupID\": 995}\n",
+                "{\"_key\": 34, \"name\": {\"en\": \"Tritanium\"}, \"groupID\": 18}\n",
+                "{\"_key\": 22456, \"name\": {\"en\": \"Sabre\"}, \"groupID\": 541, \"published\": true}\n",
+                "{\"_key\": 22460, \"name\": {\"en\": \"Eris\"}, \"groupID\": 541, \"published\": true}\n",
+                "{\"_key\": 11957, \"name\": {\"en\": \"Falcon\"}, \"groupID\": 833, \"published\": true}\n",
+                "{\"_key\": 11959, \"name\": {\"en\": \"Rook\"}, \"groupID\": 906, \"published\": true}\n",
+                "{\"_key\": 99999, \"name\": {\"en\": \"Sabre\"}, \"groupID\": 541, \"published\": false}\n",
+            ),
+        ),
+    ];
+
+    fn export(name: &str) -> TempDir {
+        let path = temp_dir(name);
+        for (file, content) in EXPORT {
+            write(path.join(file), content).unwrap();
+        }
+        path
+    }
+
+    #[test]
+    fn writes_roman_numerals() {
+        assert_eq!(roman_numeral(4), "IV");
+        assert_eq!(roman_numeral(9), "IX");
+        assert_eq!(roman_numeral(14), "XIV");
+        assert_eq!(roman_numeral(1994), "MCMXCIV");
+    }
+
+    #[test]
+    fn imports_a_json_lines_export() {
+        let source = export("import-jsonl");
+        let database = source.join("sde.db");
+        let summary = SdeImporter::new(&source).import(&database).unwrap();
+        assert_eq!(summary.regions, 3);
+        assert_eq!(summary.constellations, 3);
+        assert_eq!(summary.solar_systems, 5);
+        assert_eq!(summary.connections, 2);
+        assert_eq!(summary.planets, 1);
+        assert_eq!(summary.moons, 1);
+        assert_eq!(summary.stations, 1);
+        assert_eq!(summary.services, 3);
+        assert_eq!(summary.wormhole_systems, 2);
+        assert_eq!(summary.factions, 2);
+        assert_eq!(summary.types, 7);
+
+        let mut manager = SdeManager::open(&database, 1).unwrap();
+        assert_eq!(manager.check_database().unwrap(), SCHEMA_VERSION);
+        manager.get_universe().unwrap();
+        let universe = &manager.universe;
+        let jita = &universe.solar_systems[&30000142];
+        assert_eq!(jita.name, "Jita");
+        assert_eq!(jita.constellation, 20000020);
+        assert_eq!(jita.region, 10000002);
+        assert_eq!(jita.security_status(), 0.9);
+        // the gate from Pochven is left out
+        assert_eq!(jita.connections, vec![30000144]);
+        assert_eq!(jita.planets, vec![40009077]);
+        assert_eq!(universe.planets[&40009077].moons, vec![40009078]);
+        assert_eq!(universe.regions[&10000002].name, "The Forge");
+        let station = &universe.stations[&60003760];
+        assert_eq!(
+            station.name,
+            "Jita IV - Moon 4 - Caldari Navy Assembly Plant"
+        );
+        assert_eq!(station.services, vec![5, 16, 64]);
+        assert_eq!(universe.moons[&40009078].stations, vec![60003760]);
+        assert_eq!(jita.stations, vec![60003760]);
+        let wormhole = &universe.solar_systems[&31000001];
+        assert!(wormhole.wormhole_class == WormholeClass::Class(5));
+        assert!(wormhole.effect == Some(WormholeEffect::BlackHole));
+        // Jita gets the faction of its region, Perimeter has its own
+        assert_eq!(jita.faction, Some(500001));
+        assert_eq!(universe.solar_systems[&30000144].faction, Some(500010));
+        assert_eq!(universe.regions[&10000002].faction, Some(500001));
+        assert_eq!(universe.factions.len(), 2);
+        assert_eq!(universe.factions[&500010].name, "Guristas Pirates");
+        let catalog = manager.get_type_catalog().unwrap();
+        assert_eq!(catalog.types.len(), 7);
+        assert_eq!(catalog.groups.len(), 5);
+        assert_eq!(catalog.categories.len(), 3);
+        // the published Sabre wins over the unpublished one with the same name
+        assert_eq!(catalog.get_type_by_name("sabre").unwrap().id, 22456);
+        assert!(!catalog.get_type(34).unwrap().published);
+    }
+
+    #[test]
+    fn imports_a_yaml_export() {
+        // JSON objects are YAML too, the key of every record moves to the mapping
+        let source = temp_dir("import-yaml");
+        for (file, content) in EXPORT {
+            let mut yaml = String::new();
+            for line in content.lines() {
+                let mut record: serde_json::Value = serde_json::from_str(line).unwrap();
+                let key = record.as_object_mut().unwrap().remove("_key").unwrap();
+                yaml += &format!("{key}: {record}\n");
+            }
+            write(source.join(file.replace(".jsonl", ".yaml")), yaml).unwrap();
+        }
+        let database = source.join("sde.db");
+        let summary = SdeImporter::new(&source).import(&database).unwrap();
+        assert_eq!(summary.solar_systems, 5);
+
+        let mut manager = SdeManager::open(&database, 1).unwrap();
+        manager.get_universe().unwrap();
+        assert_eq!(manager.universe.solar_systems[&30000144].name, "Perimeter");
+        assert_eq!(
+            manager.universe.solar_systems[&30000144].connections,
+            vec![30000142]
+        );
+    }
+
+    #[test]
+    fn imports_the_wormhole_statics() {
+        let source = export("import-statics");
+        let statics = source.join("statics.txt");
+        write(
+            &statics,
+            "# system, type, destination\nJ100001,H296,C5\n\n31000002\tn062\tC5\n",
+        )
+        .unwrap();
+        let mut importer = SdeImporter::new(&source);
+        importer.statics = Some(statics.clone());
+        let database = source.join("sde.db");
+        assert_eq!(importer.import(&database).unwrap().statics, 2);
+
+        let mut manager = SdeManager::open(&database, 1).unwrap();
+        manager.get_universe().unwrap();
+        let statics = &manager.universe.solar_systems[&31000002].statics;
+        assert_eq!(statics.len(), 1);
+        assert_eq!(statics[0].name, "N062");
+
+        write(source.join("statics.txt"), "Nowhere,H296,C5\n").unwrap();
+        assert!(importer.import(&database).is_err());
+    }
+
+    #[test]
+    fn fails_without_the_solar_systems() {
+        let source = temp_dir("import-empty");
+        assert!(
+            SdeImporter::new(&source)
+                .import(&source.join("sde.db"))
+                .is_err()
+        );
+    }
+}
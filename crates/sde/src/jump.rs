@@ -0,0 +1,303 @@
+use crate::objects::Universe;
+use crate::spatial::SpatialIndex;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::time::Duration;
+
+/// Jump fatigue can not grow over 5 hours
+pub const MAX_FATIGUE: Duration = Duration::from_secs(5 * 60 * 60);
+/// Jump reactivation timer can not grow over 30 minutes
+pub const MAX_REACTIVATION: Duration = Duration::from_secs(30 * 60);
+/// Minimum fatigue used as a base for the fatigue calculation
+pub const BASE_FATIGUE: Duration = Duration::from_secs(10 * 60);
+
+/// One jump drive activation between two Solar Systems
+#[derive(PartialEq, Clone)]
+pub struct JumpHop {
+    /// Solar System where the jump drive is activated
+    pub from: u32,
+    /// Solar System with the cynosural field
+    pub to: u32,
+    /// Distance of the jump in light years
+    pub light_years: f64,
+    /// Time since the first jump of the plan when this jump is done
+    pub departure: Duration,
+    /// Jump fatigue right after the jump
+    pub fatigue: Duration,
+    /// Time until the jump drive can be activated again
+    pub reactivation: Duration,
+}
+
+/// Chain of jump drive activations between two Solar Systems
+#[derive(PartialEq, Clone)]
+pub struct JumpPlan {
+    /// Jumps in travel order
+    pub hops: Vec<JumpHop>,
+}
+
+impl JumpPlan {
+    /// Creates a new JumpPlan Struct with the hops in travel order
+    pub fn new(hops: Vec<JumpHop>) -> Self {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        JumpPlan { hops }
+    }
+
+    /// Sum of the light years of every jump
+    pub fn light_years(&self) -> f64 {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        self.hops.iter().map(|hop| hop.light_years).sum()
+    }
+
+    /// Time since the first jump until the arrival to the destination, waiting only
+    /// the reactivation timers between jumps
+    pub fn arrival(&self) -> Duration {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        self.hops
+            .last()
+            .map(|hop| hop.departure)
+            .unwrap_or_default()
+    }
+
+    /// Jump fatigue after the last jump
+    pub fn fatigue(&self) -> Duration {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        self.hops.last().map(|hop| hop.fatigue).unwrap_or_default()
+    }
+
+    /// Reactivation timer after the last jump
+    pub fn reactivation(&self) -> Duration {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        self.hops
+            .last()
+            .map(|hop| hop.reactivation)
+            .unwrap_or_default()
+    }
+}
+
+/// Plans jump drive routes and calculates the jump fatigue of every jump.
+///
+/// It uses the real space coordinates, so the Universe needs to be loaded before using
+/// the planner.
+pub struct JumpPlanner<'a> {
+    index: SpatialIndex<'a>,
+    /// Maximum jump range of the ship in light years, with skills applied
+    pub range: f64,
+    /// Jump fatigue reduction of the ship, between 0.0 and 1.0 (0.9 for jump freighters)
+    pub fatigue_reduction: f64,
+    /// Jump fatigue the pilot has before the first jump
+    pub fatigue: Duration,
+}
+
+impl<'a> JumpPlanner<'a> {
+    /// Creates a new JumpPlanner for a ship with the given range in light years, without
+    /// fatigue reduction and without previous fatigue
+    pub fn new(universe: &'a Universe, range: f64) -> Self {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        JumpPlanner {
+            index: SpatialIndex::new(universe),
+            range,
+            fatigue_reduction: 0.0,
+            fatigue: Duration::ZERO,
+        }
+    }
+
+    /// Returns the jump plan with the fewest jumps between two Solar Systems, using the
+    /// shortest total distance when there is more than one. Every jump is done as soon
+    /// as the reactivation timer of the previous one ends.
+    ///
+    /// The plan has no jumps when the origin is the destination. `None` is returned when
+    /// the destination can not be reached with the ship range.
+    pub fn plan(&self, origin: u32, destination: u32) -> Option<JumpPlan> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let path = self.find_path(origin, destination)?;
+        let mut hops = Vec::new();
+        let mut fatigue = self.fatigue;
+        let mut departure = Duration::ZERO;
+        let mut last_reactivation = Duration::ZERO;
+        for (index, pair) in path.windows(2).enumerate() {
+            if index > 0 {
+                // waiting the reactivation timer also reduces the fatigue
+                departure += last_reactivation;
+                fatigue = fatigue.saturating_sub(last_reactivation);
+            }
+            let light_years = self.index.distance(pair[0], pair[1])?;
+            let (new_fatigue, reactivation) = self.timers(light_years, fatigue);
+            hops.push(JumpHop {
+                from: pair[0],
+                to: pair[1],
+                light_years,
+                departure,
+                fatigue: new_fatigue,
+                reactivation,
+            });
+            fatigue = new_fatigue;
+            last_reactivation = reactivation;
+        }
+        Some(JumpPlan::new(hops))
+    }
+
+    /// Returns the jump fatigue and the reactivation timer after a jump of the given
+    /// light years, starting with the given fatigue
+    pub fn timers(&self, light_years: f64, fatigue: Duration) -> (Duration, Duration) {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let effective = light_years * (1.0 - self.fatigue_reduction.clamp(0.0, 1.0));
+        let reactivation = fatigue
+            .div_f64(10.0)
+            .max(Duration::from_secs_f64((1.0 + effective) * 60.0))
+            .min(MAX_REACTIVATION);
+        let new_fatigue = fatigue
+            .max(BASE_FATIGUE)
+            .mul_f64(1.0 + effective)
+            .min(MAX_FATIGUE);
+        (new_fatigue, reactivation)
+    }
+
+    fn find_path(&self, origin: u32, destination: u32) -> Option<Vec<u32>> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        // unknown Solar Systems have no distance, they get no plan like the stargate routes
+        if origin == destination {
+            return self.index.distance(origin, origin).map(|_| vec![origin]);
+        }
+        // dijkstra over (jumps, distance in thousandths of light year)
+        let mut costs: HashMap<u32, (usize, u64)> = HashMap::new();
+        let mut previous: HashMap<u32, u32> = HashMap::new();
+        let mut heap = BinaryHeap::new();
+        costs.insert(origin, (0, 0));
+        heap.push(Reverse((0usize, 0u64, origin)));
+        while let Some(Reverse((jumps, distance, current))) = heap.pop() {
+            if current == destination {
+                let mut path = vec![destination];
+                let mut system = destination;
+                while system != origin {
+                    system = previous[&system];
+                    path.push(system);
+                }
+                path.reverse();
+                return Some(path);
+            }
+            if costs
+                .get(&current)
+                .is_some_and(|best| (jumps, distance) > *best)
+            {
+                continue;
+            }
+            for (next, light_years) in self.index.jump_destinations(current, self.range) {
+                let candidate = (jumps + 1, distance + (light_years * 1000.0).round() as u64);
+                if costs.get(&next).is_none_or(|best| candidate < *best) {
+                    costs.insert(next, candidate);
+                    previous.insert(next, current);
+                    heap.push(Reverse((candidate.0, candidate.1, next)));
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{placed, universe};
+
+    const JITA: u32 = 30000142;
+
+    // a line of low sec systems 4 light years apart, starting from Jita
+    fn line() -> Universe {
+        universe(
+            vec![
+                placed(JITA, 0.95, [0.0, 0.0, 0.0]),
+                placed(30000001, 0.3, [4.0, 0.0, 0.0]),
+                placed(30000002, 0.2, [8.0, 0.0, 0.0]),
+                placed(30000003, 0.1, [7.0, 0.0, 0.0]),
+                placed(30000004, 0.6, [12.0, 0.0, 0.0]),
+            ],
+            &[],
+        )
+    }
+
+    fn assert_minutes(duration: Duration, minutes: f64) {
+        assert!(
+            (duration.as_secs_f64() - minutes * 60.0).abs() < 1e-3,
+            "{duration:?} is not {minutes} minutes"
+        );
+    }
+
+    #[test]
+    fn fatigue_grows_with_the_distance() {
+        let universe = line();
+        let mut planner = JumpPlanner::new(&universe, 5.0);
+        let (fatigue, reactivation) = planner.timers(5.0, Duration::ZERO);
+        assert_minutes(fatigue, 60.0);
+        assert_minutes(reactivation, 6.0);
+
+        planner.fatigue_reduction = 0.9;
+        let (fatigue, reactivation) = planner.timers(5.0, Duration::ZERO);
+        assert_minutes(fatigue, 15.0);
+        assert_minutes(reactivation, 1.5);
+    }
+
+    #[test]
+    fn fatigue_and_reactivation_are_capped() {
+        let universe = line();
+        let planner = JumpPlanner::new(&universe, 5.0);
+        let (fatigue, reactivation) = planner.timers(5.0, Duration::from_secs(4 * 60 * 60));
+        assert_eq!(fatigue, MAX_FATIGUE);
+        assert_minutes(reactivation, 24.0);
+        let (_, reactivation) = planner.timers(5.0, MAX_FATIGUE.mul_f64(2.0));
+        assert_eq!(reactivation, MAX_REACTIVATION);
+    }
+
+    #[test]
+    fn plans_the_fewest_jumps_out_of_high_sec() {
+        let universe = line();
+        let planner = JumpPlanner::new(&universe, 5.0);
+        let plan = planner.plan(JITA, 30000002).unwrap();
+        let path: Vec<(u32, u32)> = plan.hops.iter().map(|hop| (hop.from, hop.to)).collect();
+        assert_eq!(path, vec![(JITA, 30000001), (30000001, 30000002)]);
+        assert!((plan.light_years() - 8.0).abs() < 1e-6);
+
+        // the second jump waits the reactivation of the first one, that also reduces the
+        // fatigue: 10 * (1 + 4) = 50 minutes, then (50 - 5) * (1 + 4) = 225 minutes
+        assert_minutes(plan.hops[0].fatigue, 50.0);
+        assert_minutes(plan.arrival(), 5.0);
+        assert_minutes(plan.fatigue(), 225.0);
+        assert_minutes(plan.reactivation(), 5.0);
+    }
+
+    #[test]
+    fn no_plan_to_high_sec_or_out_of_range() {
+        let universe = line();
+        let planner = JumpPlanner::new(&universe, 5.0);
+        assert!(planner.plan(JITA, 30000004).is_none());
+        let planner = JumpPlanner::new(&universe, 3.0);
+        assert!(planner.plan(JITA, 30000001).is_none());
+    }
+
+    #[test]
+    fn plan_to_the_origin_has_no_jumps() {
+        let universe = line();
+        let planner = JumpPlanner::new(&universe, 5.0);
+        let plan = planner.plan(JITA, JITA).unwrap();
+        assert!(plan.hops.is_empty());
+        assert_eq!(plan.arrival(), Duration::ZERO);
+        assert!(planner.plan(99, 99).is_none());
+    }
+}
@@ -0,0 +1,311 @@
+use crate::objects::{Projection, SolarSystem};
+use std::collections::HashMap;
+
+/// Width and height of the box where the abstract maps are laid out, in abstract map units
+pub const ABSTRACT_MAP_SIZE: f64 = 1000.0;
+/// Extra distance added when two overlapping nodes are pushed apart, so rounding errors
+/// do not leave them touching
+const OVERLAP_MARGIN: f64 = 0.5;
+
+/// Computes schematic 2D coordinates for the abstract (regional) maps.
+///
+/// It is a force-directed layout: connected Solar Systems pull each other, every pair
+/// pushes the other away and each system is anchored to its projected position, so the
+/// map keeps the shape of the region. When the forces are settled the overlapping nodes
+/// are pushed apart. The result does not depend on any random value, the same input
+/// always gives the same layout.
+pub struct LayoutEngine {
+    /// Number of iterations of the force simulation
+    pub iterations: usize,
+    /// Width of a node, nodes closer than this in the x axis and `node_height` in the
+    /// y axis are overlapping
+    pub node_width: f64,
+    /// Height of a node
+    pub node_height: f64,
+    /// Strength of the pull towards the projected position, 0 ignores the geography
+    pub gravity: f64,
+}
+
+impl LayoutEngine {
+    /// Creates a new LayoutEngine with settings that fit the node template of the maps
+    pub fn new() -> Self {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        LayoutEngine {
+            iterations: 300,
+            node_width: 110.0,
+            node_height: 45.0,
+            gravity: 0.05,
+        }
+    }
+
+    /// Returns the abstract coordinates of every Solar System. Only the stargate
+    /// connections between the given Solar Systems are taken into account.
+    pub fn layout(&self, systems: &[&SolarSystem]) -> HashMap<u32, [f64; 2]> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        if systems.is_empty() {
+            return HashMap::new();
+        }
+        let mut ordered = systems.to_vec();
+        ordered.sort_by_key(|system| system.id);
+        let index: HashMap<u32, usize> = ordered
+            .iter()
+            .enumerate()
+            .map(|(position, system)| (system.id, position))
+            .collect();
+        let mut edges = Vec::new();
+        for (a, system) in ordered.iter().enumerate() {
+            for connection in &system.connections {
+                if let Some(&b) = index.get(connection)
+                    && a < b
+                {
+                    edges.push((a, b));
+                }
+            }
+        }
+
+        let anchors = Self::seed(&ordered);
+        let mut positions = anchors.clone();
+        self.simulate(&mut positions, &anchors, &edges);
+        self.remove_overlaps(&mut positions);
+
+        // the top left node is placed at the origin
+        let min_x = positions.iter().map(|p| p[0]).fold(f64::INFINITY, f64::min);
+        let min_y = positions.iter().map(|p| p[1]).fold(f64::INFINITY, f64::min);
+        ordered
+            .iter()
+            .zip(positions)
+            .map(|(system, position)| (system.id, [position[0] - min_x, position[1] - min_y]))
+            .collect()
+    }
+
+    /// Initial positions, the projected coordinates seen from the top like the maps (the X and
+    /// Z axes) scaled to the map box. When they are all the same the Solar Systems are placed
+    /// on a circle.
+    fn seed(systems: &[&SolarSystem]) -> Vec<[f64; 2]> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let points: Vec<[f64; 2]> = systems
+            .iter()
+            .map(|system| system.projected_coords.project(Projection::Top))
+            .collect();
+        let min_x = points.iter().map(|p| p[0]).fold(f64::INFINITY, f64::min);
+        let max_x = points
+            .iter()
+            .map(|p| p[0])
+            .fold(f64::NEG_INFINITY, f64::max);
+        let min_y = points.iter().map(|p| p[1]).fold(f64::INFINITY, f64::min);
+        let max_y = points
+            .iter()
+            .map(|p| p[1])
+            .fold(f64::NEG_INFINITY, f64::max);
+        let span = (max_x - min_x).max(max_y - min_y);
+
+        if span <= 0.0 || !span.is_finite() {
+            let count = systems.len() as f64;
+            return (0..systems.len())
+                .map(|position| {
+                    let angle = std::f64::consts::TAU * position as f64 / count;
+                    [
+                        ABSTRACT_MAP_SIZE / 2.0 * (1.0 + angle.cos()),
+                        ABSTRACT_MAP_SIZE / 2.0 * (1.0 + angle.sin()),
+                    ]
+                })
+                .collect();
+        }
+        let scale = ABSTRACT_MAP_SIZE / span;
+        points
+            .into_iter()
+            .map(|p| [(p[0] - min_x) * scale, (p[1] - min_y) * scale])
+            .collect()
+    }
+
+    /// Fruchterman-Reingold simulation with a pull towards the anchors
+    fn simulate(&self, positions: &mut [[f64; 2]], anchors: &[[f64; 2]], edges: &[(usize, usize)]) {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let count = positions.len();
+        // ideal distance between nodes, never shorter than a node
+        let ideal = (ABSTRACT_MAP_SIZE * ABSTRACT_MAP_SIZE / count as f64)
+            .sqrt()
+            .max(self.node_width);
+        let start_temperature = ABSTRACT_MAP_SIZE / 10.0;
+
+        for iteration in 0..self.iterations {
+            let temperature = start_temperature * (1.0 - iteration as f64 / self.iterations as f64);
+            let mut moves = vec![[0.0f64; 2]; count];
+
+            for a in 0..count {
+                for b in (a + 1)..count {
+                    let (dx, dy, distance) = Self::delta(positions, a, b);
+                    let force = ideal * ideal / distance;
+                    moves[a][0] += dx / distance * force;
+                    moves[a][1] += dy / distance * force;
+                    moves[b][0] -= dx / distance * force;
+                    moves[b][1] -= dy / distance * force;
+                }
+            }
+            for &(a, b) in edges {
+                let (dx, dy, distance) = Self::delta(positions, a, b);
+                let force = distance * distance / ideal;
+                moves[a][0] -= dx / distance * force;
+                moves[a][1] -= dy / distance * force;
+                moves[b][0] += dx / distance * force;
+                moves[b][1] += dy / distance * force;
+            }
+            for (node, movement) in moves.iter_mut().enumerate() {
+                movement[0] += (anchors[node][0] - positions[node][0]) * self.gravity * ideal;
+                movement[1] += (anchors[node][1] - positions[node][1]) * self.gravity * ideal;
+                let length = (movement[0] * movement[0] + movement[1] * movement[1]).sqrt();
+                if length > 0.0 {
+                    let step = length.min(temperature);
+                    positions[node][0] += movement[0] / length * step;
+                    positions[node][1] += movement[1] / length * step;
+                }
+            }
+        }
+    }
+
+    /// Pushes apart the nodes that overlap, along the axis where they overlap less
+    fn remove_overlaps(&self, positions: &mut [[f64; 2]]) {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        for _ in 0..500 {
+            let mut moved = false;
+            for a in 0..positions.len() {
+                for b in (a + 1)..positions.len() {
+                    let (dx, dy, _) = Self::delta(positions, a, b);
+                    let overlap_x = self.node_width - dx.abs();
+                    let overlap_y = self.node_height - dy.abs();
+                    if overlap_x <= 0.0 || overlap_y <= 0.0 {
+                        continue;
+                    }
+                    moved = true;
+                    if overlap_x / self.node_width < overlap_y / self.node_height {
+                        let shift = (overlap_x / 2.0 + OVERLAP_MARGIN) * dx.signum();
+                        positions[a][0] += shift;
+                        positions[b][0] -= shift;
+                    } else {
+                        let shift = (overlap_y / 2.0 + OVERLAP_MARGIN) * dy.signum();
+                        positions[a][1] += shift;
+                        positions[b][1] -= shift;
+                    }
+                }
+            }
+            if !moved {
+                break;
+            }
+        }
+    }
+
+    /// Vector from `b` to `a` and its length. Nodes in the same place are separated by a
+    /// small offset that depends on their order, so they do not stay together.
+    fn delta(positions: &[[f64; 2]], a: usize, b: usize) -> (f64, f64, f64) {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let mut dx = positions[a][0] - positions[b][0];
+        let mut dy = positions[a][1] - positions[b][1];
+        if dx == 0.0 && dy == 0.0 {
+            let angle = (a * 31 + b * 17) as f64;
+            dx = angle.cos() * 0.01;
+            dy = angle.sin() * 0.01;
+        }
+        (dx, dy, (dx * dx + dy * dy).sqrt())
+    }
+}
+
+impl Default for LayoutEngine {
+    fn default() -> Self {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{placed, system};
+
+    fn assert_no_overlaps(engine: &LayoutEngine, layout: &HashMap<u32, [f64; 2]>) {
+        let positions: Vec<[f64; 2]> = layout.values().copied().collect();
+        for a in 0..positions.len() {
+            for b in (a + 1)..positions.len() {
+                let dx = (positions[a][0] - positions[b][0]).abs();
+                let dy = (positions[a][1] - positions[b][1]).abs();
+                assert!(dx >= engine.node_width || dy >= engine.node_height);
+            }
+        }
+    }
+
+    #[test]
+    fn lays_out_nothing_for_no_systems() {
+        assert!(LayoutEngine::new().layout(&[]).is_empty());
+    }
+
+    #[test]
+    fn keeps_the_shape_of_the_region() {
+        let mut systems: Vec<SolarSystem> = (0..6)
+            .map(|position| placed(30000001 + position, 0.5, [position as f64 * 10.0, 0.0, 0.0]))
+            .collect();
+        for position in 0..5 {
+            systems[position]
+                .connections
+                .push(30000002 + position as u32);
+            systems[position + 1]
+                .connections
+                .push(30000001 + position as u32);
+        }
+        let references: Vec<&SolarSystem> = systems.iter().collect();
+        let engine = LayoutEngine::new();
+        let layout = engine.layout(&references);
+
+        assert_eq!(layout.len(), 6);
+        assert_no_overlaps(&engine, &layout);
+        // the chain goes from west to east like the systems
+        for position in 0..5 {
+            assert!(layout[&(30000001 + position)][0] < layout[&(30000002 + position)][0]);
+        }
+        // the top left corner is the origin
+        let min_x = layout.values().map(|p| p[0]).fold(f64::INFINITY, f64::min);
+        let min_y = layout.values().map(|p| p[1]).fold(f64::INFINITY, f64::min);
+        assert_eq!((min_x, min_y), (0.0, 0.0));
+    }
+
+    #[test]
+    fn same_input_gives_the_same_layout() {
+        let systems: Vec<SolarSystem> = (0..8).map(|id| system(30000001 + id, 0.5)).collect();
+        let references: Vec<&SolarSystem> = systems.iter().collect();
+        let mut reversed = references.clone();
+        reversed.reverse();
+        let engine = LayoutEngine::new();
+        let layout = engine.layout(&references);
+        assert!(layout == engine.layout(&reversed));
+    }
+
+    #[test]
+    fn separates_systems_in_the_same_place() {
+        // every system is at the origin, they start on a circle
+        let systems: Vec<SolarSystem> = (0..12).map(|id| system(30000001 + id, 0.5)).collect();
+        let references: Vec<&SolarSystem> = systems.iter().collect();
+        let engine = LayoutEngine::new();
+        let layout = engine.layout(&references);
+        assert_eq!(layout.len(), 12);
+        assert_no_overlaps(&engine, &layout);
+
+        let circle = LayoutEngine::seed(&references);
+        let center = ABSTRACT_MAP_SIZE / 2.0;
+        for point in circle {
+            let radius = ((point[0] - center).powi(2) + (point[1] - center).powi(2)).sqrt();
+            assert!((radius - center).abs() < 1e-9);
+        }
+    }
+}
@@ -6,30 +6,173 @@
 //! there are these advantages:
 //!
 //!
-use crate::objects::{Constellation, Moon, Planet, Region, SdePoint, SolarSystem, Universe};
+use crate::adjacency::{AdjacencyGraph, AreaKind};
+use crate::cache::MapData;
+use crate::catalog::{ItemCategory, ItemGroup, ItemType, TypeCatalog};
+use crate::chokepoint::{ChokepointAnalysis, GraphScope};
+use crate::error::SdeError;
+#[cfg(feature = "export")]
+use crate::export::{ExportCoordinates, UniverseExport};
+use crate::jump::{JumpPlan, JumpPlanner};
+use crate::layout::{ABSTRACT_MAP_SIZE, LayoutEngine};
+use crate::objects::{
+    Constellation, Faction, Moon, Planet, Region, SdePoint, SolarSystem, Station, StationService,
+    Universe,
+};
+use crate::pool::{ConnectionPool, PooledConnection};
+use crate::route::{Route, RouteMode};
+use crate::search::SearchIndex;
+use crate::spatial::SpatialIndex;
+use crate::wormhole::{WormholeClass, WormholeEffect, WormholeStatic};
 use egui_map::map::objects::{MapLine, MapPoint, RawPoint};
 use objects::EveRegionArea;
 use rusqlite::ToSql;
-use rusqlite::{Connection, Error, OpenFlags, params, vtab::array};
-use std::collections::HashMap;
+use rusqlite::{Connection, OpenFlags, OptionalExtension, params, vtab::array};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
 use std::path::Path;
 use std::rc::Rc;
-
+use std::sync::Arc;
+
+/// Module that finds the Regions and Constellations that share stargates.
+pub mod adjacency;
+/// Module that draws the jump bridges defined by the player on the maps.
+pub mod bridge;
+/// Module that stores the loaded Universe and maps in a binary cache file.
+pub mod cache;
+/// Module that has the catalog of item types, groups and categories.
+pub mod catalog;
+/// Module that finds the chokepoints of the stargate graph.
+pub mod chokepoint;
+/// Module that compares two SDE databases.
+#[cfg(feature = "diff")]
+pub mod diff;
+/// Module with the errors returned while reading the SDE database.
+pub mod error;
+/// Module that writes the Universe and the abstract maps in graph and data formats.
+#[cfg(feature = "export")]
+pub mod export;
+/// Module that builds the SDE database from CCP's official static data export.
+#[cfg(feature = "importer")]
+pub mod importer;
+/// Module that plans jump drive routes and calculates jump fatigue.
+pub mod jump;
+/// Module that computes the schematic layout of the abstract maps.
+pub mod layout;
 /// Module that has Data object abstractions to fill with the database data.
 pub mod objects;
+/// Module that keeps the read-only connections to the SDE database open between queries.
+pub mod pool;
+/// Module that calculates routes over the stargate graph.
+pub mod route;
+/// Module that searches Solar Systems, Constellations and Regions by name.
+pub mod search;
+/// Module that answers distance queries over the real space coordinates.
+pub mod spatial;
+/// Module that draws the maps as SVG images without a window or a GPU.
+#[cfg(feature = "render")]
+pub mod svg;
+/// Module that builds small universes for the unit tests.
+#[cfg(test)]
+mod testing;
+/// Module that has the wormhole space classes, effects and statics.
+pub mod wormhole;
+
+/// Schema version of the SDE database, stored in sqlite's `user_version`. Databases
+/// with version 0 were built before the version was recorded and are accepted if their
+/// tables have the needed columns.
+pub const SCHEMA_VERSION: u32 = 5;
+
+/// Distance between a Solar System and the destinations of its gates that leave the
+/// constellation, in abstract map units
+const EXIT_DISTANCE: f64 = 160.0;
+/// Angle between two destinations reached from the same Solar System, in radians
+const EXIT_FAN_ANGLE: f64 = 0.7;
+
+/// Tables and columns that the SDE database needs to have
+const REQUIRED_SCHEMA: [(&str, &[&str]); 7] = [
+    ("mapRegions", &["regionId", "regionName"]),
+    (
+        "mapConstellations",
+        &["constellationId", "constellationName", "regionId"],
+    ),
+    (
+        "mapSolarSystems",
+        &[
+            "solarSystemId",
+            "solarSystemName",
+            "constellationId",
+            "x",
+            "y",
+            "z",
+            "projX",
+            "projY",
+            "projZ",
+            "security",
+        ],
+    ),
+    (
+        "mapSystemConnections",
+        &["systemConnectionId", "systemA", "systemB"],
+    ),
+    (
+        "mapAbstractSystems",
+        &["solarSystemId", "regionId", "x", "y"],
+    ),
+    (
+        "mapPlanets",
+        &["planetId", "planetaryIndex", "solarSystemId"],
+    ),
+    (
+        "mapMoons",
+        &["moonId", "moonIndex", "solarSystemId", "planetId"],
+    ),
+];
+
+/// Tables and columns of the item type catalog, older databases do not have them and get
+/// an empty catalog
+const CATALOG_SCHEMA: [(&str, &[&str]); 3] = [
+    (
+        "invCategories",
+        &["categoryId", "categoryName", "published"],
+    ),
+    (
+        "invGroups",
+        &["groupId", "groupName", "categoryId", "published"],
+    ),
+    ("invTypes", &["typeId", "typeName", "groupId", "published"]),
+];
+
+/// Columns of the NPC Stations table, older databases do not have it and get no stations
+const STATION_COLUMNS: [&str; 7] = [
+    "stationId",
+    "stationName",
+    "solarSystemId",
+    "orbitId",
+    "corporationId",
+    "operationId",
+    "typeId",
+];
 
 /// Manages the process of reading SDE data and putting into different data structures
 /// for easy in-memory access.
+///
+/// The methods that work on the Solar Systems in memory use the data loaded with
+/// `get_universe`, so it must be called before them.
 #[derive(Clone)]
 pub struct SdeManager<'a> {
     /// The path to the SDE database
     pub path: &'a Path,
-    /// The universe Object that contains all the data
-    pub universe: Universe,
+    /// The universe Object that contains all the data. It is an immutable snapshot that
+    /// can be shared with other threads, `Arc::make_mut` gives a private copy to change.
+    pub universe: Arc<Universe>,
     /// Adjusting factor for coordinates (because are very large numbers)
     pub factor: i64,
     /// Invert the sign of all coordinate values
     pub invert_coordinates: bool,
+    /// Read-only connections to the database, shared with every SdeManager of the same path
+    pub pool: Arc<ConnectionPool>,
 }
 
 impl<'a> SdeManager<'a> {
@@ -40,12 +183,101 @@ impl<'a> SdeManager<'a> {
 
         SdeManager {
             path,
-            universe: Universe::new(factor),
+            universe: Arc::new(Universe::new(factor)),
             factor, // 10000000000000
             invert_coordinates: true,
+            pool: ConnectionPool::shared(path),
         }
     }
 
+    /// Creates a new SdeManager that shares an already loaded Universe, the queries that
+    /// can be answered with it do not read the database
+    pub fn with_universe(path: &Path, factor: i64, universe: Arc<Universe>) -> SdeManager<'_> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let mut manager = SdeManager::new(path, factor);
+        manager.universe = universe;
+        manager
+    }
+
+    /// Returns the loaded Universe, to share it with other threads or SdeManagers
+    pub fn share_universe(&self) -> Arc<Universe> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        Arc::clone(&self.universe)
+    }
+
+    /// Creates a new SdeManager after checking that the SDE database exists and that its
+    /// schema and version are the ones this crate can read
+    pub fn open(path: &Path, factor: i64) -> Result<SdeManager<'_>, SdeError> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let manager = SdeManager::new(path, factor);
+        manager.check_database()?;
+        Ok(manager)
+    }
+
+    /// Method that checks the SDE database schema and version, and returns the version
+    pub fn check_database(&self) -> Result<u32, SdeError> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        if !self.path.is_file() {
+            return Err(SdeError::MissingDatabase(self.path.to_path_buf()));
+        }
+        let version =
+            self.get_standart_connection()?
+                .query_row("PRAGMA user_version;", [], |row| row.get(0))?;
+        if version > SCHEMA_VERSION {
+            return Err(SdeError::WrongVersion(version, SCHEMA_VERSION));
+        }
+
+        for (table, columns) in REQUIRED_SCHEMA {
+            let found = self.get_table_columns(table)?;
+            if found.is_empty() {
+                return Err(SdeError::MissingTable(table.to_string()));
+            }
+            for column in columns {
+                if !found.iter().any(|name| name.eq_ignore_ascii_case(column)) {
+                    return Err(SdeError::MissingColumn(
+                        table.to_string(),
+                        column.to_string(),
+                    ));
+                }
+            }
+        }
+        Ok(version)
+    }
+
+    /// Method that checks if the SDE database has a table with the given columns. The
+    /// tables that are not in `REQUIRED_SCHEMA` are only read when they are found, so
+    /// older databases still open and get empty collections instead.
+    pub fn has_table(&self, table: &str, columns: &[&str]) -> Result<bool, SdeError> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let found = self.get_table_columns(table)?;
+        Ok(!found.is_empty()
+            && columns
+                .iter()
+                .all(|column| found.iter().any(|name| name.eq_ignore_ascii_case(column))))
+    }
+
+    fn get_table_columns(&self, table: &str) -> Result<Vec<String>, SdeError> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let connection = self.get_standart_connection()?;
+        let mut statement = connection.prepare_cached("SELECT name FROM pragma_table_info(?1);")?;
+        let columns = statement
+            .query_map(params![table], |row| row.get::<usize, String>(0))?
+            .collect::<Result<Vec<String>, _>>()?;
+        Ok(columns)
+    }
+
     /// Method that retrieve all Eve Online universe data and some dictionaries to quick
     /// access the available data.
     ///
@@ -54,20 +286,147 @@ impl<'a> SdeManager<'a> {
     /// - Regions
     /// - Constellations
     /// - Solar Systems
-    pub fn get_universe(&mut self) -> Result<bool, Error> {
+    /// - Planets
+    /// - Moons
+    /// - NPC Stations and their services
+    /// - NPC factions
+    pub fn get_universe(&mut self) -> Result<bool, SdeError> {
         #[cfg(feature = "puffin")]
         puffin::profile_function!();
 
         let filter = Vec::new();
-        self.universe.regions = self.get_region(filter.clone(), None)?;
-        self.universe.constellations = self.get_constellation(filter.clone())?;
-        self.universe.solar_systems = self.get_solarsystem(filter)?;
+        let mut universe = Universe::new(self.factor);
+        universe.regions = self.get_region(filter.clone(), None)?;
+        universe.constellations = self.get_constellation(filter.clone())?;
+        universe.solar_systems = self.get_solarsystem(filter.clone())?;
+        universe.services = self.get_station_services()?;
+        universe.factions = self.get_factions()?;
+
+        // planets and moons come sorted by their cardinal number
+        let mut planets = HashMap::new();
+        for planet in self.get_planet(filter.clone())? {
+            universe
+                .solar_systems
+                .entry(planet.solar_system)
+                .and_modify(|system| system.planets.push(planet.id));
+            planets.insert(planet.id, planet);
+        }
+        let mut moons = HashMap::new();
+        for moon in self.get_moon(filter.clone())? {
+            planets
+                .entry(moon.planet)
+                .and_modify(|planet| planet.moons.push(moon.id));
+            moons.insert(moon.id, moon);
+        }
+        let mut stations = HashMap::new();
+        for station in self.get_station(filter)? {
+            universe
+                .solar_systems
+                .entry(station.solar_system)
+                .and_modify(|system| system.stations.push(station.id));
+            if let Some(planet) = planets.get_mut(&station.orbit) {
+                planet.stations.push(station.id);
+            } else if let Some(moon) = moons.get_mut(&station.orbit) {
+                moon.stations.push(station.id);
+            }
+            stations.insert(station.id, station);
+        }
+        universe.planets = planets;
+        universe.moons = moons;
+        universe.stations = stations;
+        self.universe = Arc::new(universe);
         Ok(true)
     }
 
+    /// Method that returns the stargate route between two Solar Systems using the
+    /// given route mode, the player bridges and avoiding the listed Solar Systems.
+    pub fn get_route(
+        &self,
+        origin: u32,
+        destination: u32,
+        mode: RouteMode,
+        avoid: &HashSet<u32>,
+        bridges: &[(u32, u32)],
+    ) -> Option<Route> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        self.universe
+            .get_route(origin, destination, mode, avoid, bridges)
+    }
+
+    /// Method that returns every Solar System within a number of stargate or player
+    /// bridge jumps of the given Solar Systems, with the distance in jumps to the closest
+    /// one. It is meant to be used with the location of every linked character to build
+    /// the alert area.
+    pub fn get_neighbourhood(
+        &self,
+        centres: &[u32],
+        jumps: usize,
+        bridges: &[(u32, u32)],
+    ) -> HashMap<u32, usize> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        self.universe.get_neighbourhood(centres, jumps, bridges)
+    }
+
+    /// Method that builds an in-memory index to search Solar Systems, Constellations and
+    /// Regions by name, without querying the database on every keystroke.
+    pub fn get_search_index(&self) -> SearchIndex {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        SearchIndex::new(&self.universe)
+    }
+
+    /// Method that returns which Regions or Constellations are adjacent, with the border
+    /// Solar Systems and the stargates that connect them.
+    pub fn get_adjacency(&self, kind: AreaKind) -> AdjacencyGraph {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        self.universe.get_adjacency(kind)
+    }
+
+    /// Method that returns the articulation points, bridges and betweenness centrality
+    /// of the stargate graph of a Region or of the whole known space.
+    pub fn get_chokepoints(&self, scope: GraphScope) -> ChokepointAnalysis {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        self.universe.get_chokepoints(scope)
+    }
+
+    /// Method that builds a spatial index over the real space coordinates, to search the
+    /// Solar Systems within a distance in light years.
+    pub fn get_spatial_index(&self) -> SpatialIndex<'_> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        SpatialIndex::new(&self.universe)
+    }
+
+    /// Method that returns the jump drive plan between two Solar Systems for a ship with
+    /// the given range in light years, with the fatigue and reactivation timers of every jump.
+    pub fn get_jump_plan(
+        &self,
+        origin: u32,
+        destination: u32,
+        range: f64,
+        fatigue_reduction: f64,
+    ) -> Option<JumpPlan> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let mut planner = JumpPlanner::new(&self.universe, range);
+        planner.fatigue_reduction = fatigue_reduction;
+        planner.plan(origin, destination)
+    }
+
     /// Function to get all the K-Space solar systems coordinates from the SDE including data to build a map
     /// and search for basic stuff
-    pub fn get_systempoints(&self) -> Result<HashMap<usize, MapPoint>, Error> {
+    pub fn get_systempoints(&self) -> Result<HashMap<usize, MapPoint>, SdeError> {
         #[cfg(feature = "puffin")]
         puffin::profile_function!();
         let connection = self.get_standart_connection()?;
@@ -76,7 +435,7 @@ impl<'a> SdeManager<'a> {
         // centerX, centerY, centerZ,
         let mut query = String::from("SELECT SolarSystemId, projX, projY, projZ, SolarSystemName ");
         query += " FROM mapSolarSystems WHERE SolarSystemId BETWEEN ?1 AND ?2;";
-        let mut statement = connection.prepare(query.as_str())?;
+        let mut statement = connection.prepare_cached(query.as_str())?;
         let mut rows = statement.query(params![30000000, 30999999])?;
         let mut min_id = usize::MAX;
         while let Some(row) = rows.next()? {
@@ -108,7 +467,7 @@ impl<'a> SdeManager<'a> {
     pub fn get_system_connections(
         &self,
         mut hash_map: HashMap<usize, MapPoint>,
-    ) -> Result<HashMap<usize, MapPoint>, Error> {
+    ) -> Result<HashMap<usize, MapPoint>, SdeError> {
         #[cfg(feature = "puffin")]
         puffin::profile_function!();
 
@@ -117,7 +476,7 @@ impl<'a> SdeManager<'a> {
         let mut query = String::from("SELECT systemConnectionId, ");
         query += "systemA, systemB FROM mapSystemConnections;";
 
-        let mut statement = connection.prepare(query.as_str())?;
+        let mut statement = connection.prepare_cached(query.as_str())?;
         let mut rows = statement.query([])?;
         while let Some(row) = rows.next()? {
             // Optimization: to avoid printing twice the same line, we are just skipping coordinates
@@ -140,7 +499,7 @@ impl<'a> SdeManager<'a> {
         Ok(hash_map)
     }
 
-    pub fn get_region_coordinates(&self) -> Result<Vec<EveRegionArea>, Error> {
+    pub fn get_region_coordinates(&self) -> Result<Vec<EveRegionArea>, SdeError> {
         #[cfg(feature = "puffin")]
         puffin::profile_function!();
         let connection = self.get_standart_connection()?;
@@ -157,7 +516,7 @@ impl<'a> SdeManager<'a> {
         query += "INNER JOIN mapSolarSystems mss ON (mc.constellationId = mss.constellationId) ";
         query += " WHERE mr.regionId BETWEEN 10000000 AND 10999999 GROUP BY mr.regionId, mr.regionName, mc.constellationId) ";
         query += "AS reg GROUP BY reg.regionId;";
-        let mut statement = connection.prepare(query.as_str())?;
+        let mut statement = connection.prepare_cached(query.as_str())?;
         let mut rows = statement.query([])?;
         let mut areas = Vec::new();
         while let Some(row) = rows.next()? {
@@ -188,7 +547,7 @@ impl<'a> SdeManager<'a> {
     pub fn get_system_id(
         &self,
         name: String,
-    ) -> Result<Vec<(usize, String, usize, String)>, Error> {
+    ) -> Result<Vec<(usize, String, usize, String)>, SdeError> {
         #[cfg(feature = "puffin")]
         puffin::profile_function!();
         let connection = self.get_standart_connection()?;
@@ -202,7 +561,7 @@ impl<'a> SdeManager<'a> {
         query += "INNER JOIN mapRegions AS mr ON (mr.RegionId = mc.RegionId) ";
         query += "WHERE LOWER(mss.SolarSystemName) LIKE ?1; ";
 
-        let mut statement = connection.prepare(query.as_str())?;
+        let mut statement = connection.prepare_cached(query.as_str())?;
         let system_like_name = "%".to_string() + name.as_str() + "%";
         let mut rows = statement.query(params![system_like_name])?;
         let mut results = Vec::new();
@@ -212,15 +571,31 @@ impl<'a> SdeManager<'a> {
         Ok(results)
     }
 
-    pub fn get_system_coords(&self, id_node: usize) -> Result<Option<SdePoint>, Error> {
+    /// Returns the projected coordinates of a Solar System with the adjusting factor
+    /// applied. When the Universe is loaded they come from it, without reading the database.
+    pub fn get_system_coords(&self, id_node: usize) -> Result<SdePoint, SdeError> {
         #[cfg(feature = "puffin")]
         puffin::profile_function!();
+
+        if !self.universe.solar_systems.is_empty() {
+            let Some(system) = self.universe.solar_systems.get(&(id_node as u32)) else {
+                return Err(SdeError::SystemNotFound(id_node as u32));
+            };
+            // the Universe coordinates are already inverted when they are loaded
+            let mut coord = system.projected_coords.clone();
+            if self.factor > 1 {
+                coord /= self.factor;
+            } else if self.factor < -1 {
+                coord *= self.factor.abs();
+            }
+            return Ok(coord);
+        }
         let connection = self.get_standart_connection()?;
 
         let mut query = String::from("SELECT mss.ProjX, mss.ProjY, mss.ProjZ ");
         query += "FROM mapSolarSystems AS mss WHERE mss.SolarSystemId = ?1; ";
 
-        let mut statement = connection.prepare(query.as_str())?;
+        let mut statement = connection.prepare_cached(query.as_str())?;
         let system_like_name = id_node.to_string();
         let mut rows = statement.query(params![system_like_name])?;
         if let Some(row) = rows.next()? {
@@ -237,12 +612,12 @@ impl<'a> SdeManager<'a> {
             if self.invert_coordinates {
                 coord *= -1;
             }
-            return Ok(Some(coord));
+            return Ok(coord);
         }
-        Ok(None)
+        Err(SdeError::SystemNotFound(id_node as u32))
     }
 
-    pub fn get_connections(&self) -> Result<HashMap<String, MapLine>, Error> {
+    pub fn get_connections(&self) -> Result<HashMap<String, MapLine>, SdeError> {
         #[cfg(feature = "puffin")]
         puffin::profile_function!();
 
@@ -254,7 +629,7 @@ impl<'a> SdeManager<'a> {
         query += "ON(msc.systemA = mssa.solarSystemId) INNER JOIN mapSolarSystems AS mssb ";
         query += "ON(msc.systemB = mssb.solarSystemId);";
 
-        let mut statement = connection.prepare(query.as_str())?;
+        let mut statement = connection.prepare_cached(query.as_str())?;
         let mut rows = statement.query([])?;
         let mut hmap: HashMap<String, MapLine> = HashMap::new();
         while let Some(row) = rows.next()? {
@@ -288,7 +663,7 @@ impl<'a> SdeManager<'a> {
     pub fn get_abstract_systems(
         &self,
         regions: Vec<u32>,
-    ) -> Result<HashMap<usize, MapPoint>, Error> {
+    ) -> Result<HashMap<usize, MapPoint>, SdeError> {
         #[cfg(feature = "puffin")]
         puffin::profile_function!();
         let connection = self.get_standart_connection()?;
@@ -299,7 +674,7 @@ impl<'a> SdeManager<'a> {
             query += "WHERE regionId IN rarray(?1);";
         }
 
-        let mut statement = connection.prepare(query.as_str())?;
+        let mut statement = connection.prepare_cached(query.as_str())?;
         let mut rows;
         if regions.is_empty() {
             rows = statement.query([])?;
@@ -326,11 +701,122 @@ impl<'a> SdeManager<'a> {
         Ok(hash_map)
     }
 
+    /// Function to get the points of a region that has no abstract map, like the wormhole
+    /// regions. The real coordinates seen from the top are scaled to the size of the
+    /// abstract maps, so the same factor can be used.
+    pub fn get_region_systempoints(
+        &self,
+        region_id: u32,
+    ) -> Result<HashMap<usize, MapPoint>, SdeError> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let connection = self.get_standart_connection()?;
+
+        let mut query = String::from("SELECT mss.solarSystemId, mss.x, mss.z, ");
+        query += "mss.solarSystemName FROM mapSolarSystems AS mss ";
+        query += "INNER JOIN mapConstellations AS mc ON(mss.constellationId = mc.constellationId) ";
+        query += "WHERE mc.regionId = ?1;";
+        let mut statement = connection.prepare_cached(query.as_str())?;
+        let mut rows = statement.query(params![region_id])?;
+        let mut systems = Vec::new();
+        while let Some(row) = rows.next()? {
+            systems.push((
+                row.get::<usize, usize>(0)?,
+                row.get::<usize, f64>(1)?,
+                row.get::<usize, f64>(2)?,
+                row.get::<usize, String>(3)?,
+            ));
+        }
+
+        let min_x = systems.iter().map(|s| s.1).fold(f64::INFINITY, f64::min);
+        let max_x = systems
+            .iter()
+            .map(|s| s.1)
+            .fold(f64::NEG_INFINITY, f64::max);
+        let min_z = systems.iter().map(|s| s.2).fold(f64::INFINITY, f64::min);
+        let max_z = systems
+            .iter()
+            .map(|s| s.2)
+            .fold(f64::NEG_INFINITY, f64::max);
+        let scale = ABSTRACT_MAP_SIZE / (max_x - min_x).max(max_z - min_z).max(1.0);
+
+        let mut hash_map: HashMap<usize, MapPoint> = HashMap::new();
+        for (id, x, z, name) in systems {
+            let mut raw_point =
+                RawPoint::new(((x - min_x) * scale) as f32, ((max_z - z) * scale) as f32);
+            if self.factor > 1 {
+                raw_point /= self.factor;
+            } else if self.factor < -1 {
+                raw_point *= self.factor.abs();
+            }
+            let mut point = MapPoint::new(id, raw_point);
+            point.set_name(name);
+            hash_map.insert(id, point);
+        }
+        Ok(hash_map)
+    }
+
+    /// Computes the abstract map layout of a region from its stargate graph and the
+    /// projected coordinates of its Solar Systems. When `persist` is true the layout is
+    /// stored in `mapAbstractSystems`, replacing the previous one of the region.
+    pub fn generate_abstract_layout(
+        &self,
+        region_id: u32,
+        persist: bool,
+    ) -> Result<HashMap<u32, [f64; 2]>, SdeError> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let constellations: Vec<u32> = self
+            .get_constellation(vec![region_id])?
+            .into_keys()
+            .collect();
+        if constellations.is_empty() {
+            return Err(SdeError::RegionNotFound(region_id));
+        }
+        let systems = self.get_solarsystem(constellations)?;
+        let layout = LayoutEngine::new().layout(&systems.values().collect::<Vec<_>>());
+        if persist {
+            self.save_abstract_layout(region_id, &layout)?;
+        }
+        Ok(layout)
+    }
+
+    /// Stores the abstract map layout of a region in `mapAbstractSystems`, replacing the
+    /// previous one
+    pub fn save_abstract_layout(
+        &self,
+        region_id: u32,
+        layout: &HashMap<u32, [f64; 2]>,
+    ) -> Result<(), SdeError> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let mut connection = self.get_writable_connection()?;
+        let transaction = connection.transaction()?;
+        transaction.execute(
+            "DELETE FROM mapAbstractSystems WHERE regionId = ?1",
+            params![region_id],
+        )?;
+        {
+            let mut statement = transaction.prepare(
+                "INSERT OR REPLACE INTO mapAbstractSystems (solarSystemId, regionId, x, y) \
+                 VALUES (?1, ?2, ?3, ?4)",
+            )?;
+            for (system_id, coords) in layout {
+                statement.execute(params![system_id, region_id, coords[0], coords[1]])?;
+            }
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+
     pub fn get_abstract_system_connections(
         &self,
         mut hash_map: HashMap<usize, MapPoint>,
         regions: Vec<u32>,
-    ) -> Result<HashMap<usize, MapPoint>, Error> {
+    ) -> Result<HashMap<usize, MapPoint>, SdeError> {
         #[cfg(feature = "puffin")]
         puffin::profile_function!();
 
@@ -339,14 +825,14 @@ impl<'a> SdeManager<'a> {
         let mut query =
             String::from("SELECT mas.solarSystemId, mas.regionId, msc.systemConnectionId, ");
         query += " mss.solarSystemName ";
-        query += " FROM mapAbstractSystems AS mas INNER JOIN mapSystemConnections AS msc ";
+        query += " FROM mapAbstractSystems AS mas LEFT JOIN mapSystemConnections AS msc ";
         query += " ON(msc.systemA = mas.solarSystemId OR msc.systemB = mas.solarSystemId) ";
         query += " INNER JOIN mapSolarSystems AS mss ON (mss.solarSystemId = mas.solarSystemId) ";
         if !regions.is_empty() {
             query += " WHERE mas.regionId IN rarray(?1);";
         }
 
-        let mut statement = connection.prepare(query.as_str())?;
+        let mut statement = connection.prepare_cached(query.as_str())?;
         let mut rows;
         if regions.is_empty() {
             rows = statement.query([])?;
@@ -375,7 +861,7 @@ impl<'a> SdeManager<'a> {
     pub fn get_abstract_connections(
         &self,
         regions: Vec<u32>,
-    ) -> Result<HashMap<String, MapLine>, Error> {
+    ) -> Result<HashMap<String, MapLine>, SdeError> {
         #[cfg(feature = "puffin")]
         puffin::profile_function!();
 
@@ -390,7 +876,7 @@ impl<'a> SdeManager<'a> {
             query += " WHERE masa.regionId IN rarray(?1) AND masb.regionId IN rarray(?2);";
         }
 
-        let mut statement = connection.prepare(query.as_str())?;
+        let mut statement = connection.prepare_cached(query.as_str())?;
         let mut rows;
         if regions.is_empty() {
             rows = statement.query([])?;
@@ -423,7 +909,335 @@ impl<'a> SdeManager<'a> {
         Ok(hash_map)
     }
 
-    fn get_standart_connection(&self) -> Result<Connection, Error> {
+    /// Method that returns the Solar Systems and stargates of the Regions, or of every
+    /// Region when the list is empty, ready to be written with one of the export formats.
+    /// With abstract coordinates only the Solar Systems of the abstract maps are returned.
+    #[cfg(feature = "export")]
+    pub fn get_export(
+        &self,
+        regions: Vec<u32>,
+        coordinates: ExportCoordinates,
+    ) -> Result<UniverseExport, SdeError> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let mut export = UniverseExport::from_universe(&self.universe, &regions);
+        if coordinates == ExportCoordinates::Abstract {
+            let points = self
+                .get_abstract_systems(regions)?
+                .into_iter()
+                .map(|(id, point)| {
+                    let [x, y] = point.raw_point.components;
+                    (id as u32, [x as f64, y as f64])
+                })
+                .collect();
+            export.set_abstract_coordinates(&points);
+        }
+        Ok(export)
+    }
+
+    /// Returns the SHA-256 hash of the SDE database file, used to know if a cache was
+    /// built from it
+    pub fn get_database_hash(&self) -> Result<[u8; 32], SdeError> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let mut file = File::open(self.path)?;
+        let mut hasher = Sha256::new();
+        std::io::copy(&mut file, &mut hasher)?;
+        Ok(hasher.finalize().into())
+    }
+
+    /// Returns the points and lines of the universe map, the K-Space Solar Systems with
+    /// their stargate connections
+    pub fn get_universe_map(&self) -> Result<MapData, SdeError> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let points = self.get_systempoints()?;
+        Ok(MapData {
+            points: self.get_system_connections(points)?,
+            lines: self.get_connections()?,
+        })
+    }
+
+    /// Returns the points and lines of the abstract map of a region. When the region has
+    /// no abstract map a layout is generated, it is not stored so the database is never
+    /// modified; `generate_abstract_layout` stores it when asked to.
+    pub fn get_region_map(&self, region_id: u32) -> Result<MapData, SdeError> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let points = self.get_abstract_systems(vec![region_id])?;
+        if points.is_empty() {
+            let layout = self.generate_abstract_layout(region_id, false)?;
+            return self.get_layout_map(&layout);
+        }
+        Ok(MapData {
+            points: self.get_abstract_system_connections(points, vec![region_id])?,
+            lines: self.get_abstract_connections(vec![region_id])?,
+        })
+    }
+
+    /// Returns the points and lines of a generated abstract layout, with the stargates
+    /// between its Solar Systems
+    fn get_layout_map(&self, layout: &HashMap<u32, [f64; 2]>) -> Result<MapData, SdeError> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let connection = self.get_standart_connection()?;
+        let id_list: array::Array = Rc::new(
+            layout
+                .keys()
+                .copied()
+                .map(rusqlite::types::Value::from)
+                .collect::<Vec<rusqlite::types::Value>>(),
+        );
+        let mut data = MapData::new();
+
+        let mut statement = connection.prepare_cached(
+            "SELECT solarSystemId, solarSystemName FROM mapSolarSystems \
+             WHERE solarSystemId IN rarray(?1);",
+        )?;
+        let mut rows = statement.query(params![id_list])?;
+        while let Some(row) = rows.next()? {
+            let id = row.get::<usize, u32>(0)?;
+            let mut point = MapPoint::new(id as usize, self.abstract_rawpoint(layout[&id]));
+            point.set_name(row.get::<usize, String>(1)?);
+            data.points.insert(id as usize, point);
+        }
+
+        let mut query = String::from("SELECT systemConnectionId, systemA, systemB ");
+        query += "FROM mapSystemConnections ";
+        query += "WHERE systemA IN rarray(?1) AND systemB IN rarray(?1);";
+        let mut statement = connection.prepare_cached(query.as_str())?;
+        let mut rows = statement.query(params![id_list])?;
+        while let Some(row) = rows.next()? {
+            let line_id = row.get::<usize, String>(0)?;
+            let system_a = row.get::<usize, u32>(1)?;
+            let system_b = row.get::<usize, u32>(2)?;
+            let mut line = MapLine::new(
+                self.abstract_rawpoint(layout[&system_a]),
+                self.abstract_rawpoint(layout[&system_b]),
+            );
+            line.id = Some(line_id.clone());
+            for system in [system_a, system_b] {
+                if let Some(point) = data.points.get_mut(&(system as usize)) {
+                    point.connections.push(line_id.clone());
+                }
+            }
+            data.lines.insert(line_id, line);
+        }
+        Ok(data)
+    }
+
+    /// Returns the points and lines of the abstract map of a constellation. The Solar
+    /// Systems keep their place in the abstract map of the region, and every stargate
+    /// that leaves the constellation is drawn to an extra point placed outside, named
+    /// after the destination Solar System and its Constellation, or its Region when the
+    /// gate leaves the region.
+    pub fn get_constellation_map(&self, constellation_id: u32) -> Result<MapData, SdeError> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let connection = self.get_standart_connection()?;
+        let region_id: u32 = connection
+            .query_row(
+                "SELECT regionId FROM mapConstellations WHERE constellationId = ?1;",
+                params![constellation_id],
+                |row| row.get(0),
+            )
+            .optional()?
+            .ok_or(SdeError::ConstellationNotFound(constellation_id))?;
+        // the layout of the region is generated when it is not stored, without storing it
+        let stored = !self.get_abstract_systems(vec![region_id])?.is_empty();
+        let layout = if stored {
+            HashMap::new()
+        } else {
+            self.generate_abstract_layout(region_id, false)?
+        };
+
+        let mut query = String::from("SELECT mss.solarSystemId, mss.solarSystemName ");
+        if stored {
+            query += ", mas.x, mas.y FROM mapAbstractSystems AS mas INNER JOIN mapSolarSystems ";
+            query += "AS mss ON(mss.solarSystemId = mas.solarSystemId) ";
+        } else {
+            query += "FROM mapSolarSystems AS mss ";
+        }
+        query += "WHERE mss.constellationId = ?1;";
+        let mut statement = connection.prepare_cached(query.as_str())?;
+        let mut rows = statement.query(params![constellation_id])?;
+        let mut positions: HashMap<u32, [f64; 2]> = HashMap::new();
+        let mut data = MapData::new();
+        while let Some(row) = rows.next()? {
+            let id = row.get::<usize, u32>(0)?;
+            let position = if stored {
+                [row.get::<usize, f64>(2)?, row.get::<usize, f64>(3)?]
+            } else {
+                layout[&id]
+            };
+            let mut point = MapPoint::new(id as usize, self.abstract_rawpoint(position));
+            point.set_name(row.get::<usize, String>(1)?);
+            positions.insert(id, position);
+            data.points.insert(id as usize, point);
+        }
+        if positions.is_empty() {
+            return Ok(data);
+        }
+
+        let mut query = String::from("SELECT msc.systemConnectionId, msc.systemA, msc.systemB ");
+        query += "FROM mapSystemConnections AS msc INNER JOIN mapSolarSystems AS mssa ";
+        query += "ON(msc.systemA = mssa.solarSystemId) INNER JOIN mapSolarSystems AS mssb ";
+        query += "ON(msc.systemB = mssb.solarSystemId) ";
+        query += "WHERE mssa.constellationId = ?1 OR mssb.constellationId = ?1;";
+        let mut statement = connection.prepare_cached(query.as_str())?;
+        let mut rows = statement.query(params![constellation_id])?;
+        // destination Solar System with the gates that lead to it, as (line, inner system)
+        let mut exits: HashMap<u32, Vec<(String, u32)>> = HashMap::new();
+        while let Some(row) = rows.next()? {
+            let line_id = row.get::<usize, String>(0)?;
+            let system_a = row.get::<usize, u32>(1)?;
+            let system_b = row.get::<usize, u32>(2)?;
+            match (positions.get(&system_a), positions.get(&system_b)) {
+                (Some(position_a), Some(position_b)) => {
+                    let mut line = MapLine::new(
+                        self.abstract_rawpoint(*position_a),
+                        self.abstract_rawpoint(*position_b),
+                    );
+                    line.id = Some(line_id.clone());
+                    data.lines.insert(line_id.clone(), line);
+                    for system in [system_a, system_b] {
+                        if let Some(point) = data.points.get_mut(&(system as usize)) {
+                            point.connections.push(line_id.clone());
+                        }
+                    }
+                }
+                (Some(_), None) => exits.entry(system_b).or_default().push((line_id, system_a)),
+                (None, Some(_)) => exits.entry(system_a).or_default().push((line_id, system_b)),
+                (None, None) => {}
+            }
+        }
+        if exits.is_empty() {
+            return Ok(data);
+        }
+
+        let mut query = String::from("SELECT mss.solarSystemId, mss.solarSystemName, ");
+        query += "mc.regionId, mc.constellationName, mr.regionName FROM mapSolarSystems AS mss ";
+        query += "INNER JOIN mapConstellations AS mc ON(mss.constellationId = mc.constellationId) ";
+        query += "INNER JOIN mapRegions AS mr ON(mc.regionId = mr.regionId) ";
+        query += "WHERE mss.solarSystemId IN rarray(?1);";
+        let mut statement = connection.prepare_cached(query.as_str())?;
+        let id_list: array::Array = Rc::new(
+            exits
+                .keys()
+                .copied()
+                .map(rusqlite::types::Value::from)
+                .collect::<Vec<rusqlite::types::Value>>(),
+        );
+        let mut rows = statement.query(params![id_list])?;
+        let mut names = HashMap::new();
+        while let Some(row) = rows.next()? {
+            let destination = if row.get::<usize, u32>(2)? == region_id {
+                row.get::<usize, String>(3)?
+            } else {
+                row.get::<usize, String>(4)?
+            };
+            let name =
+                String::from("→ ") + &row.get::<usize, String>(1)? + " (" + &destination + ")";
+            names.insert(row.get::<usize, u32>(0)?, name);
+        }
+
+        let count = positions.len() as f64;
+        let centre = positions.values().fold([0.0, 0.0], |sum, position| {
+            [sum[0] + position[0] / count, sum[1] + position[1] / count]
+        });
+        for (&system_id, position) in Self::place_exits(&exits, &positions, centre).iter() {
+            let mut point = MapPoint::new(system_id as usize, self.abstract_rawpoint(*position));
+            point.set_name(names.get(&system_id).cloned().unwrap_or_default());
+            for (line_id, inner) in &exits[&system_id] {
+                let mut line = MapLine::new(
+                    self.abstract_rawpoint(positions[inner]),
+                    self.abstract_rawpoint(*position),
+                );
+                line.id = Some(line_id.clone());
+                data.lines.insert(line_id.clone(), line);
+                point.connections.push(line_id.clone());
+                if let Some(inner_point) = data.points.get_mut(&(*inner as usize)) {
+                    inner_point.connections.push(line_id.clone());
+                }
+            }
+            data.points.insert(system_id as usize, point);
+        }
+        Ok(data)
+    }
+
+    /// Places the destinations of the gates that leave a constellation outside of it,
+    /// away from the centre. Destinations reached from the same Solar System are spread
+    /// in a fan so they do not overlap.
+    fn place_exits(
+        exits: &HashMap<u32, Vec<(String, u32)>>,
+        positions: &HashMap<u32, [f64; 2]>,
+        centre: [f64; 2],
+    ) -> HashMap<u32, [f64; 2]> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        // destinations are grouped by the first inner Solar System that reaches them
+        let mut groups: HashMap<u32, Vec<u32>> = HashMap::new();
+        for (destination, gates) in exits {
+            let inner = gates
+                .iter()
+                .map(|(_, inner)| *inner)
+                .min()
+                .unwrap_or_default();
+            groups.entry(inner).or_default().push(*destination);
+        }
+        let mut result = HashMap::new();
+        for (inner, mut destinations) in groups {
+            destinations.sort_unstable();
+            let origin = positions[&inner];
+            let mut angle = (origin[1] - centre[1]).atan2(origin[0] - centre[0]);
+            if origin == centre {
+                angle = -std::f64::consts::FRAC_PI_2;
+            }
+            let middle = (destinations.len() as f64 - 1.0) / 2.0;
+            for (index, destination) in destinations.into_iter().enumerate() {
+                let fan_angle = angle + (index as f64 - middle) * EXIT_FAN_ANGLE;
+                result.insert(
+                    destination,
+                    [
+                        origin[0] + fan_angle.cos() * EXIT_DISTANCE,
+                        origin[1] + fan_angle.sin() * EXIT_DISTANCE,
+                    ],
+                );
+            }
+        }
+        result
+    }
+
+    /// Converts abstract map coordinates to a RawPoint, applying the adjusting factor
+    fn abstract_rawpoint(&self, position: [f64; 2]) -> RawPoint {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let mut raw_point = RawPoint::new(position[0] as f32, position[1] as f32);
+        if self.factor > 1 {
+            raw_point /= self.factor;
+        } else if self.factor < -1 {
+            raw_point *= self.factor.abs();
+        }
+        raw_point
+    }
+
+    fn get_standart_connection(&self) -> Result<PooledConnection<'_>, SdeError> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        self.pool.get()
+    }
+
+    // the pooled connections are read-only, the few writes open their own connection
+    fn get_writable_connection(&self) -> Result<Connection, SdeError> {
         #[cfg(feature = "puffin")]
         puffin::profile_function!();
 
@@ -447,7 +1261,7 @@ impl<'a> SdeManager<'a> {
         &self,
         regions: Vec<u32>,
         region_name: Option<String>,
-    ) -> Result<HashMap<u32, Region>, Error> {
+    ) -> Result<HashMap<u32, Region>, SdeError> {
         #[cfg(feature = "puffin")]
         puffin::profile_function!();
 
@@ -459,7 +1273,14 @@ impl<'a> SdeManager<'a> {
         let connection = self.get_standart_connection()?;
         let mut result = HashMap::new();
 
-        let mut query = String::from("SELECT regionId, regionName FROM mapRegions ");
+        let mut query = String::from("SELECT regionId, regionName, ");
+        // databases built before the factions were read have no owners
+        if self.has_table("mapRegions", &["factionId"])? {
+            query += "factionId ";
+        } else {
+            query += "NULL ";
+        }
+        query += "FROM mapRegions ";
         if !regions.is_empty() || region_name.is_some() {
             let mut query_p = String::new();
 
@@ -489,7 +1310,7 @@ impl<'a> SdeManager<'a> {
         }
         query += "ORDER BY regionName ";
 
-        let mut statement = connection.prepare(query.as_str())?;
+        let mut statement = connection.prepare_cached(query.as_str())?;
         let mut rows;
         if params.is_empty() {
             rows = statement.query([])?;
@@ -501,6 +1322,7 @@ impl<'a> SdeManager<'a> {
             let mut region = Region::new();
             region.id = row.get(0)?;
             region.name = row.get(1)?;
+            region.faction = row.get(2)?;
             region_ids.push(row.get(0)?);
             result.insert(row.get(0)?, region);
         }
@@ -510,7 +1332,7 @@ impl<'a> SdeManager<'a> {
             query += " WHERE regionId IN rarray(?1) ";
         }
 
-        let mut statement = connection.prepare(query.as_str())?;
+        let mut statement = connection.prepare_cached(query.as_str())?;
         let mut rows;
 
         if regions.is_empty() && region_name.is_none() {
@@ -534,7 +1356,10 @@ impl<'a> SdeManager<'a> {
         Ok(result)
     }
 
-    fn get_solarsystem(&self, constellation: Vec<u32>) -> Result<HashMap<u32, SolarSystem>, Error> {
+    fn get_solarsystem(
+        &self,
+        constellation: Vec<u32>,
+    ) -> Result<HashMap<u32, SolarSystem>, SdeError> {
         #[cfg(feature = "puffin")]
         puffin::profile_function!();
 
@@ -544,14 +1369,26 @@ impl<'a> SdeManager<'a> {
 
         let mut query =
             String::from("SELECT mss.solarSystemId, mss.solarSystemName, mc.regionId, ");
-        query += " mc.centerX, mc.centerY, mc.centerZ, mss.projX, mss.projY, mss.projZ, ";
-        query += " mss.constellationId FROM mapSolarSystems AS mss ";
+        query += " mss.x, mss.y, mss.z, mss.projX, mss.projY, mss.projZ, ";
+        query += " mss.constellationId, mss.security, ";
+        // databases built before wormhole space was read have every system in known space
+        if self.has_table("mapSolarSystems", &["wormholeClassId", "wormholeEffect"])? {
+            query += " mss.wormholeClassId, mss.wormholeEffect, ";
+        } else {
+            query += " 0, NULL, ";
+        }
+        if self.has_table("mapSolarSystems", &["factionId"])? {
+            query += " mss.factionId ";
+        } else {
+            query += " NULL ";
+        }
+        query += " FROM mapSolarSystems AS mss ";
         query +=
             " INNER JOIN mapConstellations AS mc ON(mss.constellationId = mc.constellationId)  ";
         if !constellation.is_empty() {
             query += " WHERE mss.constellationId IN rarray(?1);";
         }
-        let mut statement = connection.prepare(query.as_str())?;
+        let mut statement = connection.prepare_cached(query.as_str())?;
 
         let id_list = Rc::new(
             constellation
@@ -560,18 +1397,29 @@ impl<'a> SdeManager<'a> {
                 .collect::<Vec<rusqlite::types::Value>>(),
         );
 
-        let mut rows = statement.query(params![id_list])?;
+        let mut rows = if id_list.is_empty() {
+            statement.query([])?
+        } else {
+            statement.query(params![id_list])?
+        };
 
         while let Some(row) = rows.next()? {
             let mut object = SolarSystem::new(self.factor);
             object.id = row.get(0)?;
             object.name = row.get(1)?;
-            object.constellation = row.get(8)?;
+            object.constellation = row.get(9)?;
             object.real_coords.x = row.get::<_, f64>(3)? as i64; //i64
             object.real_coords.y = row.get::<_, f64>(4)? as i64; //i64
             object.real_coords.z = row.get::<_, f64>(5)? as i64; //i64
             object.projected_coords.x = row.get::<_, f64>(6)? as i64; //i64
             object.projected_coords.y = row.get::<_, f64>(7)? as i64; //i64
+            object.projected_coords.z = row.get::<_, f64>(8)? as i64; //i64
+            object.security = row.get::<_, f64>(10)? as f32;
+            object.wormhole_class = WormholeClass::from_id(row.get(11)?);
+            object.effect = row
+                .get::<_, Option<String>>(12)?
+                .and_then(|name| WormholeEffect::from_name(&name));
+            object.faction = row.get(13)?;
 
             // Invert coordinates if needed
             if self.invert_coordinates {
@@ -580,15 +1428,35 @@ impl<'a> SdeManager<'a> {
                 object.real_coords.z *= -1;
                 object.projected_coords.x *= -1;
                 object.projected_coords.y *= -1;
+                object.projected_coords.z *= -1;
             }
             object.region = row.get(2)?;
             result.insert(row.get(0)?, object);
         }
 
+        if self.has_table(
+            "mapWormholeStatics",
+            &["solarSystemId", "wormholeType", "destinationClassId"],
+        )? {
+            let mut statement = connection.prepare_cached(
+                "SELECT solarSystemId, wormholeType, destinationClassId FROM mapWormholeStatics \
+                 ORDER BY solarSystemId, wormholeType",
+            )?;
+            let mut rows = statement.query([])?;
+            while let Some(row) = rows.next()? {
+                let mut wormhole = WormholeStatic::new();
+                wormhole.name = row.get(1)?;
+                wormhole.destination = WormholeClass::from_id(row.get(2)?);
+                result
+                    .entry(row.get(0)?)
+                    .and_modify(|system: &mut SolarSystem| system.statics.push(wormhole));
+            }
+        }
+
         let mut query = String::from("SELECT systemConnectionId, ");
         query += "systemA, systemB FROM mapSystemConnections;";
 
-        let mut statement = connection.prepare(query.as_str())?;
+        let mut statement = connection.prepare_cached(query.as_str())?;
         let mut rows = statement.query([])?;
         while let Some(row) = rows.next()? {
             // Optimization: to avoid printing twice the same line, we are just skipping coordinates
@@ -597,6 +1465,14 @@ impl<'a> SdeManager<'a> {
             let system_a = row.get::<usize, u32>(1)?;
             let system_b = row.get::<usize, u32>(2)?;
 
+            // Pochven is only reached from outside through filaments and conduits, the gates
+            // that crossed its border are gone, so the ones left in older dumps are skipped
+            if let (Some(a), Some(b)) = (result.get(&system_a), result.get(&system_b))
+                && a.is_pochven() != b.is_pochven()
+            {
+                continue;
+            }
+
             //we compare the current system with the first, if not the same then we add the coordinates to hashmap
             result.entry(system_a).and_modify(|point| {
                 point.connections.push(system_b);
@@ -610,7 +1486,10 @@ impl<'a> SdeManager<'a> {
     }
 
     /// Function to get every Constellation or a Constellation based on an specific Region
-    fn get_constellation(&self, regions: Vec<u32>) -> Result<HashMap<u32, Constellation>, Error> {
+    fn get_constellation(
+        &self,
+        regions: Vec<u32>,
+    ) -> Result<HashMap<u32, Constellation>, SdeError> {
         #[cfg(feature = "puffin")]
         puffin::profile_function!();
 
@@ -625,14 +1504,18 @@ impl<'a> SdeManager<'a> {
             query += "WHERE regionId IN rarray(?1);";
         }
 
-        let mut statement = connection.prepare(query.as_str())?;
+        let mut statement = connection.prepare_cached(query.as_str())?;
         let id_list = Rc::new(
             regions
                 .into_iter()
                 .map(rusqlite::types::Value::from)
                 .collect::<Vec<rusqlite::types::Value>>(),
         );
-        let mut rows = statement.query(params![id_list])?;
+        let mut rows = if id_list.is_empty() {
+            statement.query([])?
+        } else {
+            statement.query(params![id_list])?
+        };
 
         //while there are regions left to consume
         while let Some(row) = rows.next()? {
@@ -647,7 +1530,7 @@ impl<'a> SdeManager<'a> {
         let mut query = String::from("SELECT constellationId, solarSystemId FROM mapSolarSystems");
         query += " WHERE constellationId IN rarray(?1);";
 
-        let mut statement = connection.prepare(query.as_str())?;
+        let mut statement = connection.prepare_cached(query.as_str())?;
         let id_list = Rc::new(
             constellations
                 .into_iter()
@@ -666,7 +1549,7 @@ impl<'a> SdeManager<'a> {
     }
 
     /// Function to get every Planet or all Planets for a specific Solar System
-    pub fn get_planet(&self, solar_systems: Vec<u32>) -> Result<Vec<Planet>, Error> {
+    pub fn get_planet(&self, solar_systems: Vec<u32>) -> Result<Vec<Planet>, SdeError> {
         #[cfg(feature = "puffin")]
         puffin::profile_function!();
 
@@ -679,15 +1562,20 @@ impl<'a> SdeManager<'a> {
         if !solar_systems.is_empty() {
             query += " WHERE solarSystemId IN rarray(?1)";
         }
+        query += " ORDER BY solarSystemId, planetaryIndex";
 
-        let mut statement = connection.prepare(query.as_str())?;
+        let mut statement = connection.prepare_cached(query.as_str())?;
         let id_list = Rc::new(
             solar_systems
                 .into_iter()
                 .map(rusqlite::types::Value::from)
                 .collect::<Vec<rusqlite::types::Value>>(),
         );
-        let mut rows = statement.query(params![id_list])?;
+        let mut rows = if id_list.is_empty() {
+            statement.query([])?
+        } else {
+            statement.query(params![id_list])?
+        };
 
         //while there are regions left to consume
         while let Some(row) = rows.next()? {
@@ -702,7 +1590,7 @@ impl<'a> SdeManager<'a> {
     }
 
     /// Function to get every Moon or all Moons for a specific planet
-    pub fn get_moon(&self, planets: Vec<u32>) -> Result<Vec<Moon>, Error> {
+    pub fn get_moon(&self, planets: Vec<u32>) -> Result<Vec<Moon>, SdeError> {
         #[cfg(feature = "puffin")]
         puffin::profile_function!();
 
@@ -714,17 +1602,22 @@ impl<'a> SdeManager<'a> {
         query += "FROM mapMoons ";
 
         if !planets.is_empty() {
-            query += " WHERE planetId=?";
+            query += " WHERE planetId IN rarray(?1)";
         };
+        query += " ORDER BY planetId, moonIndex";
 
-        let mut statement = connection.prepare(query.as_str()).unwrap();
+        let mut statement = connection.prepare_cached(query.as_str())?;
         let id_list = Rc::new(
             planets
                 .into_iter()
                 .map(rusqlite::types::Value::from)
                 .collect::<Vec<rusqlite::types::Value>>(),
         );
-        let mut rows = statement.query(params![id_list])?;
+        let mut rows = if id_list.is_empty() {
+            statement.query([])?
+        } else {
+            statement.query(params![id_list])?
+        };
         //while there are regions left to consume
         while let Some(row) = rows.next()? {
             let mut object = Moon::new();
@@ -737,4 +1630,433 @@ impl<'a> SdeManager<'a> {
 
         Ok(result)
     }
+
+    /// Function to get every NPC Station or all NPC Stations for specific Solar Systems,
+    /// with the services they offer. Databases without the stations table have none.
+    pub fn get_station(&self, solar_systems: Vec<u32>) -> Result<Vec<Station>, SdeError> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let mut result = vec![];
+        if !self.has_table("staStations", &STATION_COLUMNS)? {
+            return Ok(result);
+        }
+        let connection = self.get_standart_connection()?;
+
+        // the services are the same for every station with the same operation
+        let mut operations: HashMap<u32, Vec<u32>> = HashMap::new();
+        if self.has_table("staOperationServices", &["operationId", "serviceId"])? {
+            let mut statement = connection.prepare_cached(
+                "SELECT operationId, serviceId FROM staOperationServices ORDER BY serviceId",
+            )?;
+            let mut rows = statement.query([])?;
+            while let Some(row) = rows.next()? {
+                operations.entry(row.get(0)?).or_default().push(row.get(1)?);
+            }
+        }
+
+        let mut query = String::from("SELECT stationId, stationName, solarSystemId, orbitId, ");
+        query += "corporationId, operationId, typeId FROM staStations";
+        if !solar_systems.is_empty() {
+            query += " WHERE solarSystemId IN rarray(?1)";
+        }
+        query += " ORDER BY solarSystemId, stationName";
+
+        let mut statement = connection.prepare_cached(query.as_str())?;
+        let id_list = Rc::new(
+            solar_systems
+                .into_iter()
+                .map(rusqlite::types::Value::from)
+                .collect::<Vec<rusqlite::types::Value>>(),
+        );
+        let mut rows = if id_list.is_empty() {
+            statement.query([])?
+        } else {
+            statement.query(params![id_list])?
+        };
+
+        while let Some(row) = rows.next()? {
+            let mut object = Station::new();
+            object.id = row.get(0)?;
+            object.name = row.get(1)?;
+            object.solar_system = row.get(2)?;
+            object.orbit = row.get(3)?;
+            object.corporation = row.get(4)?;
+            object.operation = row.get(5)?;
+            object.type_id = row.get(6)?;
+            if let Some(services) = operations.get(&object.operation) {
+                object.services.clone_from(services);
+            }
+            result.push(object);
+        }
+
+        Ok(result)
+    }
+
+    /// Function to get every service offered by NPC Stations, databases without the services
+    /// table have none
+    pub fn get_station_services(&self) -> Result<HashMap<u32, StationService>, SdeError> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let mut result = HashMap::new();
+        if !self.has_table("staServices", &["serviceId", "serviceName"])? {
+            return Ok(result);
+        }
+        let connection = self.get_standart_connection()?;
+
+        let mut statement =
+            connection.prepare_cached("SELECT serviceId, serviceName FROM staServices")?;
+        let mut rows = statement.query([])?;
+        while let Some(row) = rows.next()? {
+            let mut object = StationService::new();
+            object.id = row.get(0)?;
+            object.name = row.get(1)?;
+            result.insert(object.id, object);
+        }
+
+        Ok(result)
+    }
+
+    /// Function to get every NPC faction. Databases without the factions table have none.
+    pub fn get_factions(&self) -> Result<HashMap<u32, Faction>, SdeError> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let mut result = HashMap::new();
+        if !self.has_table("chrFactions", &["factionId", "factionName"])? {
+            return Ok(result);
+        }
+        let connection = self.get_standart_connection()?;
+
+        let mut statement =
+            connection.prepare_cached("SELECT factionId, factionName FROM chrFactions")?;
+        let mut rows = statement.query([])?;
+        while let Some(row) = rows.next()? {
+            let mut object = Faction::new();
+            object.id = row.get(0)?;
+            object.name = row.get(1)?;
+            result.insert(object.id, object);
+        }
+
+        Ok(result)
+    }
+
+    /// Function to get the catalog of item types, with their groups and categories.
+    /// Databases without the type tables get an empty catalog.
+    pub fn get_type_catalog(&self) -> Result<TypeCatalog, SdeError> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let mut categories = HashMap::new();
+        let mut groups = HashMap::new();
+        let mut types = HashMap::new();
+        for (table, columns) in CATALOG_SCHEMA {
+            if !self.has_table(table, columns)? {
+                return Ok(TypeCatalog::new(categories, groups, types));
+            }
+        }
+        let connection = self.get_standart_connection()?;
+
+        let mut statement = connection
+            .prepare_cached("SELECT categoryId, categoryName, published FROM invCategories")?;
+        let mut rows = statement.query([])?;
+        while let Some(row) = rows.next()? {
+            let mut object = ItemCategory::new();
+            object.id = row.get(0)?;
+            object.name = row.get(1)?;
+            object.published = row.get(2)?;
+            categories.insert(object.id, object);
+        }
+
+        let mut statement = connection
+            .prepare_cached("SELECT groupId, groupName, categoryId, published FROM invGroups")?;
+        let mut rows = statement.query([])?;
+        while let Some(row) = rows.next()? {
+            let mut object = ItemGroup::new();
+            object.id = row.get(0)?;
+            object.name = row.get(1)?;
+            object.category = row.get(2)?;
+            object.published = row.get(3)?;
+            groups.insert(object.id, object);
+        }
+
+        let mut statement = connection
+            .prepare_cached("SELECT typeId, typeName, groupId, published FROM invTypes")?;
+        let mut rows = statement.query([])?;
+        while let Some(row) = rows.next()? {
+            let mut object = ItemType::new();
+            object.id = row.get(0)?;
+            object.name = row.get(1)?;
+            object.group = row.get(2)?;
+            object.published = row.get(3)?;
+            types.insert(object.id, object);
+        }
+
+        Ok(TypeCatalog::new(categories, groups, types))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{TempDir, schema_database, temp_dir};
+    use std::path::PathBuf;
+
+    // creates a database with the required tables and the given user_version
+    fn database(name: &str, version: u32) -> (TempDir, PathBuf) {
+        let version = format!("PRAGMA user_version = {version};");
+        schema_database(name, &REQUIRED_SCHEMA, &version)
+    }
+
+    #[test]
+    fn accepts_the_current_and_unversioned_databases() {
+        let (_folder, path) = database("schema-current", SCHEMA_VERSION);
+        assert_eq!(
+            SdeManager::new(&path, 1).check_database().unwrap(),
+            SCHEMA_VERSION
+        );
+        let (_folder, path) = database("schema-unversioned", 0);
+        assert_eq!(SdeManager::new(&path, 1).check_database().unwrap(), 0);
+    }
+
+    #[test]
+    fn rejects_newer_versions() {
+        let (_folder, path) = database("schema-newer", SCHEMA_VERSION + 1);
+        let error = SdeManager::open(&path, 1).err().unwrap();
+        assert!(
+            matches!(error, SdeError::WrongVersion(found, SCHEMA_VERSION) if found == SCHEMA_VERSION + 1)
+        );
+        assert!(error.is_outdated());
+    }
+
+    #[test]
+    fn rejects_missing_databases_tables_and_columns() {
+        let folder = temp_dir("schema-missing");
+        let path = folder.join("sde.db");
+        let error = SdeManager::open(&path, 1).err().unwrap();
+        assert!(matches!(error, SdeError::MissingDatabase(_)));
+
+        let (_folder, path) = database("schema-table", SCHEMA_VERSION);
+        Connection::open(&path)
+            .unwrap()
+            .execute("DROP TABLE mapMoons;", [])
+            .unwrap();
+        let error = SdeManager::open(&path, 1).err().unwrap();
+        assert!(matches!(error, SdeError::MissingTable(table) if table == "mapMoons"));
+
+        let (_folder, path) = database("schema-column", SCHEMA_VERSION);
+        Connection::open(&path)
+            .unwrap()
+            .execute("ALTER TABLE mapRegions DROP COLUMN regionName;", [])
+            .unwrap();
+        let error = SdeManager::open(&path, 1).err().unwrap();
+        assert!(matches!(
+            error,
+            SdeError::MissingColumn(table, column) if table == "mapRegions" && column == "regionName"
+        ));
+    }
+
+    #[test]
+    fn loads_databases_without_the_optional_tables() {
+        let (_folder, path) = database("schema-older", 0);
+        let connection = Connection::open(&path).unwrap();
+        connection
+            .execute_batch(
+                "INSERT INTO mapRegions (regionId, regionName) VALUES (10000002, 'The Forge');
+                 INSERT INTO mapConstellations VALUES (20000020, 'Kimotoro', 10000002);
+                 INSERT INTO mapSolarSystems (solarSystemId, solarSystemName, constellationId,
+                     x, y, z, projX, projY, projZ, security)
+                     VALUES (30000142, 'Jita', 20000020, 1, 2, 3, 1, 2, 3, 0.95);",
+            )
+            .unwrap();
+        let mut manager = SdeManager::open(&path, 1).unwrap();
+        manager.get_universe().unwrap();
+        let jita = &manager.universe.solar_systems[&30000142];
+        assert_eq!(jita.name, "Jita");
+        assert!(jita.wormhole_class == WormholeClass::Unknown(0));
+        assert!(jita.effect.is_none());
+        assert!(jita.statics.is_empty());
+        assert!(manager.universe.stations.is_empty());
+        assert!(manager.universe.services.is_empty());
+        assert!(manager.get_station(vec![30000142]).unwrap().is_empty());
+        assert_eq!(jita.faction, None);
+        assert_eq!(manager.universe.regions[&10000002].faction, None);
+        assert!(manager.universe.factions.is_empty());
+        let catalog = manager.get_type_catalog().unwrap();
+        assert!(catalog.types.is_empty());
+        assert!(catalog.get_type_by_name("Sabre").is_none());
+    }
+
+    #[test]
+    fn reads_the_type_catalog_when_the_database_has_it() {
+        let (_folder, path) = database("schema-catalog", SCHEMA_VERSION);
+        let connection = Connection::open(&path).unwrap();
+        for (table, columns) in CATALOG_SCHEMA {
+            connection
+                .execute(
+                    &format!("CREATE TABLE {table} ({});", columns.join(", ")),
+                    [],
+                )
+                .unwrap();
+        }
+        connection
+            .execute_batch(
+                "INSERT INTO invCategories VALUES (6, 'Ship', 1);
+                 INSERT INTO invGroups VALUES (541, 'Interdictor', 6, 1);
+                 INSERT INTO invTypes VALUES (22456, 'Sabre', 541, 1);",
+            )
+            .unwrap();
+        let manager = SdeManager::open(&path, 1).unwrap();
+        let catalog = manager.get_type_catalog().unwrap();
+        assert!(catalog.is_ship(22456));
+        assert_eq!(catalog.get_group(22456).unwrap().name, "Interdictor");
+
+        // a table without the expected columns gives an empty catalog
+        connection.execute("DROP TABLE invGroups", []).unwrap();
+        connection
+            .execute("CREATE TABLE invGroups (groupId)", [])
+            .unwrap();
+        assert!(manager.get_type_catalog().unwrap().types.is_empty());
+    }
+
+    #[test]
+    fn reads_the_factions_when_the_database_has_them() {
+        let (_folder, path) = database("schema-factions", SCHEMA_VERSION);
+        Connection::open(&path)
+            .unwrap()
+            .execute_batch(
+                "ALTER TABLE mapRegions ADD COLUMN factionId;
+                 ALTER TABLE mapSolarSystems ADD COLUMN factionId;
+                 CREATE TABLE chrFactions (factionId, factionName);
+                 INSERT INTO chrFactions VALUES (500001, 'Caldari State');
+                 INSERT INTO mapRegions VALUES (10000002, 'The Forge', 500001);
+                 INSERT INTO mapConstellations VALUES (20000020, 'Kimotoro', 10000002);
+                 INSERT INTO mapSolarSystems (solarSystemId, solarSystemName, constellationId,
+                     x, y, z, projX, projY, projZ, security, factionId)
+                     VALUES (30000142, 'Jita', 20000020, 1, 2, 3, 1, 2, 3, 0.95, 500001);",
+            )
+            .unwrap();
+        let mut manager = SdeManager::open(&path, 1).unwrap();
+        manager.get_universe().unwrap();
+        assert_eq!(
+            manager.universe.solar_systems[&30000142].faction,
+            Some(500001)
+        );
+        assert_eq!(manager.universe.regions[&10000002].faction, Some(500001));
+        assert_eq!(manager.universe.factions[&500001].name, "Caldari State");
+    }
+
+    #[test]
+    fn checks_the_optional_tables() {
+        let (_folder, path) = database("schema-optional", SCHEMA_VERSION);
+        let manager = SdeManager::new(&path, 1);
+        assert!(
+            manager
+                .has_table("mapRegions", &["regionId", "REGIONNAME"])
+                .unwrap()
+        );
+        assert!(manager.has_table("mapRegions", &[]).unwrap());
+        assert!(
+            !manager
+                .has_table("mapRegions", &["regionId", "unknown"])
+                .unwrap()
+        );
+        assert!(!manager.has_table("unknownTable", &[]).unwrap());
+    }
+
+    #[test]
+    fn region_maps_are_generated_without_writing_the_database() {
+        let (_folder, path) = database("region-map", SCHEMA_VERSION);
+        Connection::open(&path)
+            .unwrap()
+            .execute_batch(
+                "INSERT INTO mapRegions (regionId, regionName) VALUES (10000002, 'The Forge');
+                 INSERT INTO mapConstellations VALUES (20000020, 'Kimotoro', 10000002);
+                 INSERT INTO mapConstellations VALUES (20000019, 'Otsela', 10000002);
+                 INSERT INTO mapSolarSystems (solarSystemId, solarSystemName, constellationId,
+                     x, y, z, projX, projY, projZ, security) VALUES
+                     (30000142, 'Jita', 20000020, 0, 0, 0, 0, 0, 0, 0.95),
+                     (30000144, 'Perimeter', 20000020, 1e16, 0, 0, 1e16, 0, 0, 0.95),
+                     (30000140, 'Maurasi', 20000019, 0, 0, 1e16, 0, 0, 1e16, 0.9);
+                 INSERT INTO mapSystemConnections VALUES
+                     ('30000142-30000144', 30000142, 30000144),
+                     ('30000140-30000142', 30000140, 30000142);",
+            )
+            .unwrap();
+        let manager = SdeManager::open(&path, 1).unwrap();
+        let hash = manager.get_database_hash().unwrap();
+
+        let data = manager.get_region_map(10000002).unwrap();
+        assert_eq!(data.points.len(), 3);
+        assert_eq!(data.lines.len(), 2);
+        assert_eq!(data.points[&30000142].connections.len(), 2);
+        assert_eq!(data.points[&30000140].get_name(), "Maurasi");
+
+        let data = manager.get_constellation_map(20000020).unwrap();
+        assert_eq!(data.points.len(), 3);
+        assert_eq!(data.lines.len(), 2);
+        assert_eq!(data.points[&30000140].get_name(), "→ Maurasi (Otsela)");
+
+        assert!(
+            manager
+                .get_abstract_systems(vec![10000002])
+                .unwrap()
+                .is_empty()
+        );
+        assert_eq!(manager.get_database_hash().unwrap(), hash);
+
+        // storing the layout is an explicit action, the stored one is used afterwards
+        let layout = manager.generate_abstract_layout(10000002, true).unwrap();
+        assert_eq!(
+            manager.get_abstract_systems(vec![10000002]).unwrap().len(),
+            3
+        );
+        let data = manager.get_region_map(10000002).unwrap();
+        let [x, y] = data.points[&30000144].raw_point.components;
+        assert_eq!(
+            [x as f64, y as f64],
+            layout[&30000144].map(|value| value as f32 as f64)
+        );
+        assert!(matches!(
+            manager.get_region_map(10000001),
+            Err(SdeError::RegionNotFound(10000001))
+        ));
+    }
+
+    #[test]
+    fn exits_are_placed_outside_in_a_fan() {
+        let positions = HashMap::from([(1, [0.0, 0.0]), (2, [100.0, 0.0])]);
+        let exits = HashMap::from([
+            (10, vec![(String::from("2-10"), 2)]),
+            (
+                11,
+                vec![(String::from("2-11"), 2), (String::from("1-11"), 1)],
+            ),
+            (12, vec![(String::from("2-12"), 2)]),
+        ]);
+        let placed = SdeManager::place_exits(&exits, &positions, [50.0, 0.0]);
+        assert_eq!(placed.len(), 3);
+
+        // 11 is reached from 1 too, so it goes out of 1 away from the centre
+        let [x, y] = placed[&11];
+        assert!((x + EXIT_DISTANCE).abs() < 1e-9 && y.abs() < 1e-9);
+        // 10 and 12 share a fan out of 2, on both sides of the direction from the centre
+        for (id, side) in [(10, -1.0), (12, 1.0)] {
+            let [x, y] = placed[&id];
+            let angle = side * EXIT_FAN_ANGLE / 2.0;
+            assert!((x - 100.0 - angle.cos() * EXIT_DISTANCE).abs() < 1e-9);
+            assert!((y - angle.sin() * EXIT_DISTANCE).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn unknown_constellations_have_no_map() {
+        let (_folder, path) = database("constellation-map", SCHEMA_VERSION);
+        let manager = SdeManager::open(&path, 1).unwrap();
+        assert!(matches!(
+            manager.get_constellation_map(20000020),
+            Err(SdeError::ConstellationNotFound(20000020))
+        ));
+    }
 }
@@ -1,8 +1,19 @@
+use crate::adjacency::{AdjacencyGraph, AreaKind};
+use crate::chokepoint::{ChokepointAnalysis, GraphScope};
+use crate::route::{Route, RouteMode, RoutePlanner};
+use crate::spatial::{LIGHT_YEAR, POCHVEN_REGION};
+use crate::wormhole::{
+    KNOWN_SPACE_REGIONS, WORMHOLE_SYSTEMS, WormholeClass, WormholeEffect, WormholeStatic,
+};
 use egui_map::map::objects::RawPoint;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::convert::{From, TryInto};
+use std::hash::{Hash, Hasher};
 use std::io::{Error as GenericError, ErrorKind};
-use std::ops::{Add, Div, DivAssign, Mul, MulAssign, Sub};
+use std::ops::{Add, Div, DivAssign, Mul, MulAssign, RangeInclusive, Sub};
+
+/// Constellation identifiers, of both K-Space and J-Space
+pub const CONSTELLATIONS: RangeInclusive<u32> = 20000000..=21999999;
 
 #[derive(Hash, PartialEq, Eq, Clone)]
 pub struct EveRegionArea {
@@ -37,7 +48,7 @@ impl EveRegionArea {
 
 #[derive(Hash, PartialEq, Eq, Clone)]
 pub struct SdeLine {
-    points: [SdePoint; 2],
+    pub(crate) points: [SdePoint; 2],
 }
 
 impl SdeLine {
@@ -48,25 +59,32 @@ impl SdeLine {
         Self { points: [a, b] }
     }
 
-    pub fn distance(self) -> f32 {
+    /// Length of the line in meters
+    pub fn distance(&self) -> f64 {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        SdeVector::from(&self.points[0]).distance(&SdeVector::from(&self.points[1]))
+    }
+
+    /// Length of the line in light years
+    pub fn distance_ly(&self) -> f64 {
         #[cfg(feature = "puffin")]
         puffin::profile_function!();
 
-        let x = self.points[0].x - self.points[1].x;
-        let y = self.points[0].y - self.points[1].y;
-        let z = self.points[0].z - self.points[1].z;
-        let value = (x.pow(2) + y.pow(2) + z.pow(2)) as f32;
-        value.sqrt()
+        self.distance() / LIGHT_YEAR
     }
 
-    pub fn midpoint(self) -> SdePoint {
+    pub fn midpoint(&self) -> SdePoint {
         #[cfg(feature = "puffin")]
         puffin::profile_function!();
 
-        let x = (self.points[0].x + self.points[1].x) / 2;
-        let y = (self.points[0].y + self.points[1].y) / 2;
-        let z = (self.points[0].z + self.points[1].z) / 2;
-        SdePoint::new(x, y, z)
+        // the sum of two real coordinates can be out of the i64 range
+        SdePoint::new(
+            self.points[0].x.midpoint(self.points[1].x),
+            self.points[0].y.midpoint(self.points[1].y),
+            self.points[0].z.midpoint(self.points[1].z),
+        )
     }
 }
 
@@ -94,11 +112,21 @@ impl SdePoint {
         SdePoint { x, y, z }
     }
 
+    /// Returns the 2d coordinates of the point in the given plane
+    pub fn project(&self, projection: Projection) -> [f64; 2] {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        SdeVector::from(self).project(projection)
+    }
+
+    /// Returns the point seen from the top, the plane used by every map
     pub fn to_rawpoint(self) -> RawPoint {
         #[cfg(feature = "puffin")]
         puffin::profile_function!();
 
-        RawPoint::new(self.x as f32, self.z as f32)
+        let [x, y] = self.project(Projection::Top);
+        RawPoint::new(x as f32, y as f32)
     }
 }
 
@@ -142,28 +170,6 @@ impl From<SdePoint> for [f64; 3] {
     }
 }
 
-impl TryInto<[f32; 2]> for SdePoint {
-    type Error = GenericError;
-
-    fn try_into(self) -> Result<[f32; 2], <Self as TryInto<[f32; 2]>>::Error> {
-        #[cfg(feature = "puffin")]
-        puffin::profile_function!();
-
-        if self.x == 0 {
-            Ok([self.y as f32, self.z as f32])
-        } else if self.y == 0 {
-            Ok([self.x as f32, self.z as f32])
-        } else if self.z == 0 {
-            Ok([self.x as f32, self.y as f32])
-        } else {
-            Err(GenericError::new(
-                ErrorKind::NotFound,
-                "projection pivot value not found, it is not possible to determine wich values to return.",
-            ))
-        }
-    }
-}
-
 impl TryInto<[f32; 3]> for SdePoint {
     type Error = GenericError;
 
@@ -184,37 +190,6 @@ impl TryInto<[f32; 3]> for SdePoint {
     }
 }
 
-impl TryInto<[i64; 2]> for SdePoint {
-    type Error = GenericError;
-
-    fn try_into(self) -> Result<[i64; 2], <Self as TryInto<[i64; 2]>>::Error> {
-        #[cfg(feature = "puffin")]
-        puffin::profile_function!();
-
-        if self.x > f32::MAX as i64
-            || self.x < f32::MIN as i64
-            || self.y > f32::MAX as i64
-            || self.y < f32::MIN as i64
-            || self.z > f32::MAX as i64
-            || self.z < f32::MIN as i64
-        {
-            return Err(GenericError::new(ErrorKind::InvalidData, "Value Overflow"));
-        }
-        if self.x == 0 {
-            Ok([self.y, self.z])
-        } else if self.y == 0 {
-            Ok([self.x, self.z])
-        } else if self.z == 0 {
-            Ok([self.x, self.y])
-        } else {
-            Err(GenericError::new(
-                ErrorKind::NotFound,
-                "projection pivot value not found, it is not possible to determine wich values to return.",
-            ))
-        }
-    }
-}
-
 impl From<[f32; 3]> for SdePoint {
     fn from(value: [f32; 3]) -> Self {
         #[cfg(feature = "puffin")]
@@ -422,6 +397,153 @@ impl Sub<&SdePoint> for SdePoint {
     }
 }
 
+/// Plane used to turn the 3d coordinates of the SDE into 2d coordinates
+#[derive(Hash, PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Projection {
+    /// Seen from above, the X and Z axes. This is the plane used by the maps
+    Top,
+    /// Seen from the front, the X and Y axes
+    Front,
+    /// Seen from the side, the Z and Y axes
+    Side,
+}
+
+/// 3d vector with floating point coordinates, in meters.
+///
+/// The coordinates of the SDE are stored as `SdePoint`, but any arithmetic on them can
+/// overflow an i64, so distances and other calculations are done with this type.
+#[derive(PartialEq, Clone, Copy, Debug, Default)]
+pub struct SdeVector {
+    /// X coordinate
+    pub x: f64,
+    /// Y coordinate
+    pub y: f64,
+    /// Z coordinate
+    pub z: f64,
+}
+
+impl SdeVector {
+    /// Creates a new SdeVector
+    pub fn new(x: f64, y: f64, z: f64) -> Self {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        SdeVector { x, y, z }
+    }
+
+    /// Length of the vector in meters
+    pub fn length(&self) -> f64 {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        (self.x.powi(2) + self.y.powi(2) + self.z.powi(2)).sqrt()
+    }
+
+    /// Distance in meters to another point
+    pub fn distance(&self, other: &SdeVector) -> f64 {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        (*self - *other).length()
+    }
+
+    /// Distance in light years to another point
+    pub fn distance_ly(&self, other: &SdeVector) -> f64 {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        self.distance(other) / LIGHT_YEAR
+    }
+
+    /// Returns the 2d coordinates of the vector in the given plane
+    pub fn project(&self, projection: Projection) -> [f64; 2] {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        match projection {
+            Projection::Top => [self.x, self.z],
+            Projection::Front => [self.x, self.y],
+            Projection::Side => [self.z, self.y],
+        }
+    }
+}
+
+impl From<&SdePoint> for SdeVector {
+    fn from(value: &SdePoint) -> Self {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        Self::new(value.x as f64, value.y as f64, value.z as f64)
+    }
+}
+
+impl From<SdePoint> for SdeVector {
+    fn from(value: SdePoint) -> Self {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        Self::from(&value)
+    }
+}
+
+impl From<[f64; 3]> for SdeVector {
+    fn from(value: [f64; 3]) -> Self {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        Self::new(value[0], value[1], value[2])
+    }
+}
+
+impl From<SdeVector> for [f64; 3] {
+    fn from(val: SdeVector) -> Self {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        [val.x, val.y, val.z]
+    }
+}
+
+impl Add<SdeVector> for SdeVector {
+    type Output = SdeVector;
+    fn add(self, rhs: SdeVector) -> Self::Output {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        SdeVector::new(self.x + rhs.x, self.y + rhs.y, self.z + rhs.z)
+    }
+}
+
+impl Sub<SdeVector> for SdeVector {
+    type Output = SdeVector;
+    fn sub(self, rhs: SdeVector) -> Self::Output {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        SdeVector::new(self.x - rhs.x, self.y - rhs.y, self.z - rhs.z)
+    }
+}
+
+impl Mul<f64> for SdeVector {
+    type Output = SdeVector;
+    fn mul(self, rhs: f64) -> Self::Output {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        SdeVector::new(self.x * rhs, self.y * rhs, self.z * rhs)
+    }
+}
+
+impl Div<f64> for SdeVector {
+    type Output = SdeVector;
+    fn div(self, rhs: f64) -> Self::Output {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        SdeVector::new(self.x / rhs, self.y / rhs, self.z / rhs)
+    }
+}
+
 /// Abstraction for a Planet Moons. It store data relevant to this entity
 #[derive(Hash, PartialEq, Eq, Clone)]
 pub struct Moon {
@@ -433,6 +555,8 @@ pub struct Moon {
     pub index: u8,
     /// Moon's Solar System Identifier
     pub solar_system: u32,
+    /// NPC Station identifiers orbiting this moon
+    pub stations: Vec<u32>,
 }
 
 impl Moon {
@@ -446,6 +570,7 @@ impl Moon {
             planet: 0,
             index: 0,
             solar_system: 0,
+            stations: Vec::new(),
         }
     }
 }
@@ -468,6 +593,10 @@ pub struct Planet {
     pub solar_system: u32,
     /// The cardinal number of this planet in the solar system.
     pub index: u8,
+    /// Moon vector with Identifier numbers in their respective cardinal order
+    pub moons: Vec<u32>,
+    /// NPC Station identifiers orbiting this planet
+    pub stations: Vec<u32>,
 }
 
 impl Planet {
@@ -480,6 +609,8 @@ impl Planet {
             id: 0,
             solar_system: 0,
             index: 0,
+            moons: Vec::new(),
+            stations: Vec::new(),
         }
     }
 }
@@ -493,8 +624,139 @@ impl Default for Planet {
     }
 }
 
-/// Abstraction for a Solar System. It store data relevant to this entity
+/// Identifiers of the four empire factions: Caldari State, Minmatar Republic, Amarr Empire
+/// and Gallente Federation
+pub const EMPIRE_FACTIONS: [u32; 4] = [500001, 500002, 500003, 500004];
+
+/// Abstraction for an NPC faction, the owner of empire space and of NPC null-sec
 #[derive(Hash, PartialEq, Eq, Clone)]
+pub struct Faction {
+    /// Faction identifier
+    pub id: u32,
+    /// Faction name
+    pub name: String,
+}
+
+impl Faction {
+    /// Creates a new Faction Strcut. ALl the values are initialized. Needs to be filled
+    pub fn new() -> Self {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        Faction {
+            id: 0,
+            name: String::new(),
+        }
+    }
+
+    /// Returns true if the faction is one of the four empires
+    pub fn is_empire(&self) -> bool {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        EMPIRE_FACTIONS.contains(&self.id)
+    }
+}
+
+impl Default for Faction {
+    fn default() -> Self {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        Self::new()
+    }
+}
+
+/// Abstraction for a service offered by NPC Stations, like Repair Facilities or Market
+#[derive(Hash, PartialEq, Eq, Clone)]
+pub struct StationService {
+    /// Service identifier
+    pub id: u32,
+    /// Service name
+    pub name: String,
+}
+
+impl StationService {
+    /// Creates a new StationService Strcut. ALl the values are initialized. Needs to be filled
+    pub fn new() -> Self {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        StationService {
+            id: 0,
+            name: String::new(),
+        }
+    }
+}
+
+impl Default for StationService {
+    fn default() -> Self {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        Self::new()
+    }
+}
+
+/// Abstraction for an NPC Station. It store data relevant to this entity
+#[derive(Hash, PartialEq, Eq, Clone)]
+pub struct Station {
+    /// Station identifier
+    pub id: u32,
+    /// Station name
+    pub name: String,
+    /// Station's Solar System identifier
+    pub solar_system: u32,
+    /// Identifier of the celestial the station orbits, usually a Planet or a Moon
+    pub orbit: u32,
+    /// Identifier of the NPC corporation that owns the station
+    pub corporation: u32,
+    /// Identifier of the station operation, it defines the services offered
+    pub operation: u32,
+    /// Item type identifier of the station structure
+    pub type_id: u32,
+    /// Service identifiers offered by the station
+    pub services: Vec<u32>,
+}
+
+impl Station {
+    /// Creates a new Station Strcut. ALl the values are initialized. Needs to be filled
+    pub fn new() -> Self {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        Station {
+            id: 0,
+            name: String::new(),
+            solar_system: 0,
+            orbit: 0,
+            corporation: 0,
+            operation: 0,
+            type_id: 0,
+            services: Vec::new(),
+        }
+    }
+
+    /// Returns true if the station offers the service
+    pub fn has_service(&self, service_id: u32) -> bool {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        self.services.contains(&service_id)
+    }
+}
+
+impl Default for Station {
+    fn default() -> Self {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        Self::new()
+    }
+}
+
+/// Abstraction for a Solar System. It store data relevant to this entity
+#[derive(Clone)]
 pub struct SolarSystem {
     /// Solar System identifier
     pub id: u32,
@@ -508,12 +770,25 @@ pub struct SolarSystem {
     pub planets: Vec<u32>,
     /// Vector with Solar system identifiers where this Solar system has connections via Stargates
     pub connections: Vec<u32>,
+    /// NPC Station identifiers in this Solar System
+    pub stations: Vec<u32>,
     /// Solar System 3D Coordinates
     pub real_coords: SdePoint,
     /// Solar System 2D Coordinates with the propourse of representing the system in abstraction map.
     pub projected_coords: SdePoint,
     /// The factor that we need to adjust the coordinates
     pub factor: i64,
+    /// Raw security status as stored in the SDE, without rounding
+    pub security: f32,
+    /// Wormhole class of the Solar System
+    pub wormhole_class: WormholeClass,
+    /// System-wide effect of J-Space Solar Systems with a secondary sun
+    pub effect: Option<WormholeEffect>,
+    /// Wormholes that always spawn in this J-Space Solar System
+    pub statics: Vec<WormholeStatic>,
+    /// NPC faction that owns the Solar System, inherited from its constellation or region
+    /// when the SDE does not set it. Empire space and NPC null-sec have one.
+    pub faction: Option<u32>,
 }
 
 impl SolarSystem {
@@ -529,33 +804,117 @@ impl SolarSystem {
             constellation: 0,
             planets: Vec::new(),
             connections: Vec::new(),
+            stations: Vec::new(),
             real_coords: SdePoint::default(),
             projected_coords: SdePoint::default(),
             factor,
+            security: 0.0,
+            wormhole_class: WormholeClass::default(),
+            effect: None,
+            statics: Vec::new(),
+            faction: None,
+        }
+    }
+
+    /// Security status rounded to one decimal, the same way the game client shows it
+    pub fn security_status(&self) -> f32 {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        // values between 0.0 and 0.05 are shown as 0.1 in game
+        if self.security > 0.0 && self.security < 0.05 {
+            return 0.1;
         }
+        (self.security * 10.0).round() / 10.0
     }
 
-    /// this function that correct the original 2d coordinates using the correction factor
-    pub fn coord2d_to_f64(self) -> [f64; 2] {
+    /// Returns true if the Solar System is High Security space (0.5 - 1.0)
+    pub fn is_highsec(&self) -> bool {
         #[cfg(feature = "puffin")]
         puffin::profile_function!();
 
-        [
-            (self.projected_coords.x / self.factor) as f64,
-            (self.real_coords.y / self.factor) as f64,
-        ]
+        self.security_status() >= 0.5
     }
 
-    /// this function that correct the original 3d coordinates using the correction factor
-    pub fn coord3d_to_f64(self) -> [f64; 3] {
+    /// Returns true if the Solar System is Low Security space (0.1 - 0.4)
+    pub fn is_lowsec(&self) -> bool {
         #[cfg(feature = "puffin")]
         puffin::profile_function!();
 
-        [
-            (self.projected_coords.x / self.factor) as f64,
-            (self.real_coords.y / self.factor) as f64,
-            (self.real_coords.z / self.factor) as f64,
-        ]
+        let security = self.security_status();
+        security > 0.0 && security < 0.5
+    }
+
+    /// Returns true if the Solar System is Null Security space (0.0 and below)
+    pub fn is_nullsec(&self) -> bool {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        self.security_status() <= 0.0
+    }
+
+    /// Returns true if the Solar System is in wormhole space (J-Space)
+    pub fn is_wormhole(&self) -> bool {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        self.wormhole_class.is_jspace() || WORMHOLE_SYSTEMS.contains(&self.id)
+    }
+
+    /// Returns true if the Solar System is in Pochven
+    pub fn is_pochven(&self) -> bool {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        self.region == POCHVEN_REGION
+    }
+
+    /// Distance in light years to another Solar System, using the real coordinates
+    pub fn distance_ly(&self, other: &SolarSystem) -> f64 {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        SdeVector::from(&self.real_coords).distance_ly(&SdeVector::from(&other.real_coords))
+    }
+
+    /// Projected coordinates seen from the top, corrected with the correction factor
+    pub fn coord2d_to_f64(&self) -> [f64; 2] {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        self.scaled_coords().project(Projection::Top)
+    }
+
+    /// Projected 3d coordinates, corrected with the correction factor
+    pub fn coord3d_to_f64(&self) -> [f64; 3] {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        self.scaled_coords().into()
+    }
+
+    // the factor divides the coordinates when it is positive and multiplies them when it is
+    // negative, the same way the map points are built
+    fn scaled_coords(&self) -> SdeVector {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let coords = SdeVector::from(&self.projected_coords);
+        if self.factor > 1 {
+            coords / self.factor as f64
+        } else if self.factor < -1 {
+            coords * self.factor.abs() as f64
+        } else {
+            coords
+        }
+    }
+
+    // 0.0 and -0.0 are the same security status, they get the same bits
+    fn security_bits(&self) -> u32 {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        (self.security + 0.0).to_bits()
     }
 }
 
@@ -568,6 +927,56 @@ impl Default for SolarSystem {
     }
 }
 
+// the security is compared by its bits, so the Solar System can still be used as the key of
+// hash maps and sets
+impl PartialEq for SolarSystem {
+    fn eq(&self, other: &Self) -> bool {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        self.id == other.id
+            && self.name == other.name
+            && self.region == other.region
+            && self.constellation == other.constellation
+            && self.planets == other.planets
+            && self.connections == other.connections
+            && self.stations == other.stations
+            && self.real_coords == other.real_coords
+            && self.projected_coords == other.projected_coords
+            && self.factor == other.factor
+            && self.security_bits() == other.security_bits()
+            && self.wormhole_class == other.wormhole_class
+            && self.effect == other.effect
+            && self.statics == other.statics
+            && self.faction == other.faction
+    }
+}
+
+impl Eq for SolarSystem {}
+
+impl Hash for SolarSystem {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        self.id.hash(state);
+        self.name.hash(state);
+        self.region.hash(state);
+        self.constellation.hash(state);
+        self.planets.hash(state);
+        self.connections.hash(state);
+        self.stations.hash(state);
+        self.real_coords.hash(state);
+        self.projected_coords.hash(state);
+        self.factor.hash(state);
+        self.security_bits().hash(state);
+        self.wormhole_class.hash(state);
+        self.effect.hash(state);
+        self.statics.hash(state);
+        self.faction.hash(state);
+    }
+}
+
 /// Abstraction for a Constellation. It store data relevant to this entity
 #[derive(Hash, PartialEq, Eq, Clone)]
 pub struct Constellation {
@@ -619,6 +1028,8 @@ pub struct Region {
     pub constellations: Vec<u32>,
     /// Region 2D Coordinates with the propourse of representing the system in abstraction map.
     pub projected_coords: SdePoint,
+    /// NPC faction that owns the Region, if any
+    pub faction: Option<u32>,
 }
 
 impl Region {
@@ -632,6 +1043,7 @@ impl Region {
             name: String::new(),
             constellations: Vec::new(),
             projected_coords: SdePoint::default(),
+            faction: None,
         }
     }
 }
@@ -653,6 +1065,8 @@ impl Default for Region {
 /// - SolarSystems
 /// - Planets
 /// - Moons
+/// - NPC Stations and their services
+/// - NPC factions
 /// - and the object dictionaries
 pub struct Universe {
     /// Region objects you can access the data with their Identfiers
@@ -665,6 +1079,12 @@ pub struct Universe {
     pub planets: HashMap<u32, Planet>,
     /// Moon objects you can access the data with their Identfiers
     pub moons: HashMap<u32, Moon>,
+    /// NPC Station objects you can access the data with their Identfiers
+    pub stations: HashMap<u32, Station>,
+    /// Station services you can access the data with their Identfiers
+    pub services: HashMap<u32, StationService>,
+    /// NPC factions you can access the data with their Identfiers
+    pub factions: HashMap<u32, Faction>,
     /// Factor used to correct coordinates
     pub factor: i64,
     /// List of system connections
@@ -683,10 +1103,221 @@ impl Universe {
             solar_systems: HashMap::new(),
             planets: HashMap::new(),
             moons: HashMap::new(),
+            stations: HashMap::new(),
+            services: HashMap::new(),
+            factions: HashMap::new(),
             factor,
             connections: HashMap::new(),
         }
     }
+
+    /// Returns the NPC faction that owns the Solar System, if any
+    pub fn get_faction(&self, system_id: u32) -> Option<&Faction> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        self.solar_systems
+            .get(&system_id)
+            .and_then(|system| system.faction)
+            .and_then(|faction| self.factions.get(&faction))
+    }
+
+    /// Returns the stargate route between two Solar Systems using the route mode,
+    /// taking the player bridges and skipping the Solar Systems in the avoid list
+    pub fn get_route(
+        &self,
+        origin: u32,
+        destination: u32,
+        mode: RouteMode,
+        avoid: &HashSet<u32>,
+        bridges: &[(u32, u32)],
+    ) -> Option<Route> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let mut planner = RoutePlanner::new(self);
+        planner.set_bridges(bridges);
+        planner.mode = mode;
+        planner.avoid.clone_from(avoid);
+        planner.find_route(origin, destination)
+    }
+
+    /// Returns every Solar System within `jumps` stargate or player bridge jumps of any
+    /// of the centres, with the distance in jumps to the closest one
+    pub fn get_neighbourhood(
+        &self,
+        centres: &[u32],
+        jumps: usize,
+        bridges: &[(u32, u32)],
+    ) -> HashMap<u32, usize> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let mut planner = RoutePlanner::new(self);
+        planner.set_bridges(bridges);
+        planner.neighbourhood(centres, jumps)
+    }
+
+    /// Returns the number of stargate or player bridge jumps of the shortest route
+    /// between two Solar Systems
+    pub fn get_jumps(
+        &self,
+        origin: u32,
+        destination: u32,
+        bridges: &[(u32, u32)],
+    ) -> Option<usize> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let mut planner = RoutePlanner::new(self);
+        planner.set_bridges(bridges);
+        planner.jumps(origin, destination)
+    }
+
+    /// Returns the box that contains the projected coordinates of the Solar Systems of
+    /// every known space Region, sorted by Region identifier
+    pub fn get_region_areas(&self) -> Vec<EveRegionArea> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let mut areas: HashMap<u32, EveRegionArea> = HashMap::new();
+        for system in self.solar_systems.values() {
+            if !KNOWN_SPACE_REGIONS.contains(&system.region) {
+                continue;
+            }
+            let coords = &system.projected_coords;
+            let area = areas.entry(system.region).or_insert_with(|| EveRegionArea {
+                region_id: system.region,
+                name: self
+                    .regions
+                    .get(&system.region)
+                    .map(|region| region.name.clone())
+                    .unwrap_or_default(),
+                min: coords.clone(),
+                max: coords.clone(),
+            });
+            area.min = SdePoint::new(
+                area.min.x.min(coords.x),
+                area.min.y.min(coords.y),
+                area.min.z.min(coords.z),
+            );
+            area.max = SdePoint::new(
+                area.max.x.max(coords.x),
+                area.max.y.max(coords.y),
+                area.max.z.max(coords.z),
+            );
+        }
+        let mut result: Vec<EveRegionArea> = areas.into_values().collect();
+        result.sort_unstable_by_key(|area| area.region_id);
+        result
+    }
+
+    /// Returns the Regions or Constellations that share stargates, with the border
+    /// Solar Systems and the gates between them
+    pub fn get_adjacency(&self, kind: AreaKind) -> AdjacencyGraph {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        AdjacencyGraph::new(self, kind)
+    }
+
+    /// Returns the Solar Systems and stargates that split the stargate graph of a Region
+    /// or of the known space, with the betweenness centrality of every Solar System
+    pub fn get_chokepoints(&self, scope: GraphScope) -> ChokepointAnalysis {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        ChokepointAnalysis::new(self, scope)
+    }
+
+    /// Returns the Planets of a Solar System in their cardinal order
+    pub fn get_planets(&self, solar_system: u32) -> Vec<&Planet> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        self.solar_systems
+            .get(&solar_system)
+            .map(|system| {
+                system
+                    .planets
+                    .iter()
+                    .filter_map(|id| self.planets.get(id))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Returns the Moons of a Planet in their cardinal order
+    pub fn get_moons(&self, planet: u32) -> Vec<&Moon> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        self.planets
+            .get(&planet)
+            .map(|planet| {
+                planet
+                    .moons
+                    .iter()
+                    .filter_map(|id| self.moons.get(id))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Returns every Moon of a Solar System, ordered by Planet and then by Moon
+    pub fn get_system_moons(&self, solar_system: u32) -> Vec<&Moon> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        self.get_planets(solar_system)
+            .into_iter()
+            .flat_map(|planet| self.get_moons(planet.id))
+            .collect()
+    }
+
+    /// Returns the NPC Stations of a Solar System, where people can dock
+    pub fn get_stations(&self, solar_system: u32) -> Vec<&Station> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        self.solar_systems
+            .get(&solar_system)
+            .map(|system| {
+                system
+                    .stations
+                    .iter()
+                    .filter_map(|id| self.stations.get(id))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Returns the services offered by an NPC Station
+    pub fn get_station_services(&self, station: u32) -> Vec<&StationService> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        self.stations
+            .get(&station)
+            .map(|station| {
+                station
+                    .services
+                    .iter()
+                    .filter_map(|id| self.services.get(id))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Returns true if the Solar System has at least one NPC Station to dock
+    pub fn has_stations(&self, solar_system: u32) -> bool {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        self.solar_systems
+            .get(&solar_system)
+            .is_some_and(|system| !system.stations.is_empty())
+    }
 }
 
 impl Default for Universe {
@@ -697,3 +1328,85 @@ impl Default for Universe {
         Self::new(1)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::system;
+
+    #[test]
+    fn rounds_the_security_like_the_game() {
+        assert_eq!(system(1, 0.04).security_status(), 0.1);
+        assert_eq!(system(1, 0.449).security_status(), 0.4);
+        assert_eq!(system(1, 0.45).security_status(), 0.5);
+        assert_eq!(system(1, -0.04).security_status(), 0.0);
+        assert_eq!(system(1, -0.36).security_status(), -0.4);
+    }
+
+    #[test]
+    fn classifies_the_security() {
+        assert!(system(1, 0.46).is_highsec());
+        assert!(system(1, 0.44).is_lowsec());
+        assert!(system(1, 0.01).is_lowsec());
+        assert!(system(1, 0.0).is_nullsec());
+        assert!(system(1, -0.8).is_nullsec());
+    }
+
+    #[test]
+    fn solar_systems_can_be_hashed() {
+        let mut systems = HashSet::new();
+        systems.insert(system(1, 0.0));
+        assert!(systems.contains(&system(1, -0.0)));
+        assert!(!systems.contains(&system(1, 0.1)));
+        assert!(!systems.contains(&system(2, 0.0)));
+    }
+
+    #[test]
+    fn vectors_measure_without_overflowing() {
+        // the i64 coordinates of opposite sides of New Eden overflow when subtracted
+        let a = SdePoint::new(i64::MAX - 1, 0, 0);
+        let b = SdePoint::new(i64::MIN + 1, 0, 0);
+        let distance = SdeLine::new(a, b).distance();
+        assert!((distance - 2.0 * (i64::MAX - 1) as f64).abs() < 1e4);
+
+        let a = SdeVector::new(1.0, 2.0, 2.0);
+        assert_eq!(a.length(), 3.0);
+        assert_eq!(a.distance(&SdeVector::default()), 3.0);
+        assert_eq!(a + a - a * 2.0, SdeVector::default());
+        assert_eq!((a / 2.0).z, 1.0);
+        assert_eq!(<[f64; 3]>::from(a), [1.0, 2.0, 2.0]);
+        assert_eq!(SdeVector::from([1.0, 2.0, 2.0]), a);
+    }
+
+    #[test]
+    fn measures_light_years() {
+        let origin = SdePoint::new(0, 0, 0);
+        let point = SdePoint::new(0, 0, (2.5 * LIGHT_YEAR) as i64);
+        assert!((SdeLine::new(origin, point).distance_ly() - 2.5).abs() < 1e-9);
+        let vector = SdeVector::new(0.0, 0.0, 2.5 * LIGHT_YEAR);
+        assert!((vector.distance_ly(&SdeVector::default()) - 2.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn projects_on_every_plane() {
+        let vector = SdeVector::new(1.0, 2.0, 3.0);
+        assert_eq!(vector.project(Projection::Top), [1.0, 3.0]);
+        assert_eq!(vector.project(Projection::Front), [1.0, 2.0]);
+        assert_eq!(vector.project(Projection::Side), [3.0, 2.0]);
+        assert_eq!(SdePoint::new(4, 5, 6).project(Projection::Top), [4.0, 6.0]);
+        assert_eq!(SdePoint::new(4, 5, 6).to_rawpoint().components, [4.0, 6.0]);
+    }
+
+    #[test]
+    fn scales_the_projected_coordinates() {
+        let mut jita = system(1, 0.9);
+        jita.projected_coords = SdePoint::new(100, 200, 300);
+        jita.factor = 10;
+        assert_eq!(jita.coord2d_to_f64(), [10.0, 30.0]);
+        assert_eq!(jita.coord3d_to_f64(), [10.0, 20.0, 30.0]);
+        jita.factor = -2;
+        assert_eq!(jita.coord2d_to_f64(), [200.0, 600.0]);
+        jita.factor = 1;
+        assert_eq!(jita.coord3d_to_f64(), [100.0, 200.0, 300.0]);
+    }
+}
@@ -0,0 +1,230 @@
+use crate::error::SdeError;
+use rusqlite::{Connection, OpenFlags, vtab::array};
+use std::collections::HashMap;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+
+/// Number of idle connections kept open by every pool
+pub const POOL_SIZE: usize = 4;
+/// Number of prepared statements kept by every connection
+pub const STATEMENT_CACHE_SIZE: usize = 64;
+
+// pools shared by every SdeManager of the process, by database path
+static POOLS: OnceLock<Mutex<HashMap<PathBuf, Arc<ConnectionPool>>>> = OnceLock::new();
+
+/// Small pool of read-only connections to one SDE database.
+///
+/// The connections are opened on demand, with the rarray module loaded and their own
+/// prepared statement cache, and they are kept open after being used so the next query
+/// does not need to open the database again. The pool can be shared between threads,
+/// every connection is used by only one thread at a time.
+pub struct ConnectionPool {
+    path: PathBuf,
+    idle: Mutex<Vec<Connection>>,
+}
+
+impl ConnectionPool {
+    /// Creates a new empty ConnectionPool for the database, the connections are opened
+    /// when they are needed
+    pub fn new(path: &Path) -> Self {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        ConnectionPool {
+            path: path.to_path_buf(),
+            idle: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Returns the pool shared by the whole process for the database, creating it the
+    /// first time
+    pub fn shared(path: &Path) -> Arc<ConnectionPool> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let pools = POOLS.get_or_init(|| Mutex::new(HashMap::new()));
+        let mut pools = pools
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        Arc::clone(
+            pools
+                .entry(path.to_path_buf())
+                .or_insert_with(|| Arc::new(ConnectionPool::new(path))),
+        )
+    }
+
+    /// Takes an idle connection from the pool, or opens a new one when all of them are
+    /// in use. The connection goes back to the pool when it is dropped.
+    pub fn get(&self) -> Result<PooledConnection<'_>, SdeError> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let idle = self.lock().pop();
+        let connection = match idle {
+            Some(connection) => connection,
+            None => self.open()?,
+        };
+        Ok(PooledConnection {
+            pool: self,
+            connection: Some(connection),
+        })
+    }
+
+    /// Closes every idle connection, the next queries open the database again. Useful
+    /// when the database file has been replaced.
+    pub fn clear(&self) {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        self.lock().clear();
+    }
+
+    /// Number of connections waiting to be used
+    pub fn idle_connections(&self) -> usize {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        self.lock().len()
+    }
+
+    fn open(&self) -> Result<Connection, SdeError> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        // the pool gives every connection to a single thread, so sqlite does not need to
+        // serialize the calls
+        let mut flags = OpenFlags::default();
+        flags.set(OpenFlags::SQLITE_OPEN_READ_WRITE, false);
+        flags.set(OpenFlags::SQLITE_OPEN_CREATE, false);
+        flags.set(OpenFlags::SQLITE_OPEN_READ_ONLY, true);
+        flags.set(OpenFlags::SQLITE_OPEN_FULL_MUTEX, false);
+        flags.set(OpenFlags::SQLITE_OPEN_NO_MUTEX, true);
+        let connection = Connection::open_with_flags(&self.path, flags)?;
+
+        // we add the carray module disguised as rarray in rusqlite
+        array::load_module(&connection)?;
+        connection.set_prepared_statement_cache_capacity(STATEMENT_CACHE_SIZE);
+        Ok(connection)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<Connection>> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        // a panic while holding the lock can not leave the list of connections broken
+        self.idle
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Connection borrowed from a ConnectionPool, it goes back to the pool when dropped
+pub struct PooledConnection<'a> {
+    pool: &'a ConnectionPool,
+    connection: Option<Connection>,
+}
+
+impl Deref for PooledConnection<'_> {
+    type Target = Connection;
+
+    fn deref(&self) -> &Self::Target {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        // the connection is only taken out on drop
+        self.connection.as_ref().unwrap()
+    }
+}
+
+impl Drop for PooledConnection<'_> {
+    fn drop(&mut self) {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        if let Some(connection) = self.connection.take() {
+            let mut idle = self.pool.lock();
+            if idle.len() < POOL_SIZE {
+                idle.push(connection);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{TempDir, schema_database, temp_dir};
+
+    fn database(name: &str) -> (TempDir, PathBuf) {
+        let numbers = [("numbers", &["value INTEGER"][..])];
+        schema_database(name, &numbers, "INSERT INTO numbers VALUES (1), (2), (3);")
+    }
+
+    #[test]
+    fn connections_go_back_to_the_pool() {
+        let (_folder, path) = database("pool-reuse");
+        let pool = ConnectionPool::new(&path);
+        assert_eq!(pool.idle_connections(), 0);
+        {
+            let first = pool.get().unwrap();
+            let second = pool.get().unwrap();
+            let sum: i64 = first
+                .query_row("SELECT SUM(value) FROM numbers", [], |row| row.get(0))
+                .unwrap();
+            assert_eq!(sum, 6);
+            assert!(second.is_autocommit());
+            assert_eq!(pool.idle_connections(), 0);
+        }
+        assert_eq!(pool.idle_connections(), 2);
+        let _connection = pool.get().unwrap();
+        assert_eq!(pool.idle_connections(), 1);
+        pool.clear();
+        assert_eq!(pool.idle_connections(), 0);
+    }
+
+    #[test]
+    fn keeps_at_most_the_pool_size() {
+        let (_folder, path) = database("pool-size");
+        let pool = ConnectionPool::new(&path);
+        let connections: Vec<PooledConnection<'_>> =
+            (0..POOL_SIZE + 2).map(|_| pool.get().unwrap()).collect();
+        drop(connections);
+        assert_eq!(pool.idle_connections(), POOL_SIZE);
+    }
+
+    #[test]
+    fn connections_are_read_only_with_rarray() {
+        let (_folder, path) = database("pool-read-only");
+        let pool = ConnectionPool::new(&path);
+        let connection = pool.get().unwrap();
+        assert!(connection.execute("DELETE FROM numbers", []).is_err());
+        let values: array::Array = std::rc::Rc::new(vec![1i64.into(), 3i64.into()]);
+        let count: i64 = connection
+            .query_row(
+                "SELECT COUNT(*) FROM numbers WHERE value IN rarray(?1)",
+                [values],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(count, 2);
+    }
+
+    #[test]
+    fn missing_databases_are_not_created() {
+        let folder = temp_dir("pool-missing");
+        let path = folder.join("sde.db");
+        assert!(ConnectionPool::new(&path).get().is_err());
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn pools_are_shared_by_path() {
+        let (_folder, path) = database("pool-shared");
+        let pool = ConnectionPool::shared(&path);
+        assert!(Arc::ptr_eq(&pool, &ConnectionPool::shared(&path)));
+        let (_other_folder, other_path) = database("pool-other");
+        let other = ConnectionPool::shared(&other_path);
+        assert!(!Arc::ptr_eq(&pool, &other));
+    }
+}
@@ -0,0 +1,434 @@
+use crate::objects::{SolarSystem, Universe};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
+
+/// Cost added for every jump into a Solar System the route mode wants to stay away from.
+/// It is big enough to make any detour inside the preferred space cheaper.
+const PENALTY: u64 = 50_000;
+
+/// Preference used to choose between routes, the same ones the in-game autopilot has
+#[derive(Hash, PartialEq, Eq, Clone, Copy, Default)]
+pub enum RouteMode {
+    /// Fewest stargate jumps
+    #[default]
+    Shortest,
+    /// Prefer High Security space, even if the route is longer
+    Safer,
+    /// Prefer Low and Null Security space, even if the route is longer
+    LessSecure,
+}
+
+/// Path between two Solar Systems following the stargate connections
+#[derive(Hash, PartialEq, Eq, Clone)]
+pub struct Route {
+    /// Solar System identifiers in travel order, including origin and destination
+    pub systems: Vec<u32>,
+}
+
+impl Route {
+    /// Creates a new Route Struct with the Solar Systems in travel order
+    pub fn new(systems: Vec<u32>) -> Self {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        Route { systems }
+    }
+
+    /// Origin Solar System identifier
+    pub fn origin(&self) -> Option<u32> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        self.systems.first().copied()
+    }
+
+    /// Destination Solar System identifier
+    pub fn destination(&self) -> Option<u32> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        self.systems.last().copied()
+    }
+
+    /// Number of stargate jumps needed to travel the route
+    pub fn jumps(&self) -> usize {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        self.systems.len().saturating_sub(1)
+    }
+}
+
+/// Calculates routes over the Universe stargate graph.
+///
+/// The graph is read from `SolarSystem.connections` and the jump bridges set with
+/// `RoutePlanner::set_bridges`, so the Universe needs to be loaded before using the planner.
+pub struct RoutePlanner<'a> {
+    universe: &'a Universe,
+    /// Solar Systems reached through jump bridges or other connections defined by the player
+    pub bridges: HashMap<u32, Vec<u32>>,
+    /// Preference used to choose the route
+    pub mode: RouteMode,
+    /// Solar Systems that the route can not go through. Origin and destination
+    /// are always allowed.
+    pub avoid: HashSet<u32>,
+}
+
+impl<'a> RoutePlanner<'a> {
+    /// Creates a new RoutePlanner over an already loaded Universe, using the shortest
+    /// route mode and an empty avoid list
+    pub fn new(universe: &'a Universe) -> Self {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        RoutePlanner {
+            universe,
+            bridges: HashMap::new(),
+            mode: RouteMode::default(),
+            avoid: HashSet::new(),
+        }
+    }
+
+    /// Replaces the jump bridges and other connections defined by the player. They are
+    /// used by routes and distances in jumps like any stargate. Pairs with an unknown
+    /// Solar System are ignored.
+    pub fn set_bridges(&mut self, bridges: &[(u32, u32)]) {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        self.bridges.clear();
+        for &(system_a, system_b) in bridges {
+            if system_a == system_b
+                || !self.universe.solar_systems.contains_key(&system_a)
+                || !self.universe.solar_systems.contains_key(&system_b)
+            {
+                continue;
+            }
+            for (from, to) in [(system_a, system_b), (system_b, system_a)] {
+                let links = self.bridges.entry(from).or_default();
+                if !links.contains(&to) {
+                    links.push(to);
+                }
+            }
+        }
+    }
+
+    /// Returns the best route between two Solar Systems according to the route mode
+    /// and the avoid list.
+    ///
+    /// `None` is returned when any of the systems does not exist or there is no
+    /// stargate path between them.
+    pub fn find_route(&self, origin: u32, destination: u32) -> Option<Route> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        if !self.universe.solar_systems.contains_key(&origin)
+            || !self.universe.solar_systems.contains_key(&destination)
+        {
+            return None;
+        }
+        if origin == destination {
+            return Some(Route::new(vec![origin]));
+        }
+
+        // dijkstra, ties are broken by the number of jumps
+        let mut costs: HashMap<u32, (u64, usize)> = HashMap::new();
+        let mut previous: HashMap<u32, u32> = HashMap::new();
+        let mut heap = BinaryHeap::new();
+        costs.insert(origin, (0, 0));
+        heap.push(Reverse((0u64, 0usize, origin)));
+        while let Some(Reverse((cost, jumps, current))) = heap.pop() {
+            if current == destination {
+                return Some(Self::build_route(&previous, origin, destination));
+            }
+            if costs
+                .get(&current)
+                .is_some_and(|best| (cost, jumps) > *best)
+            {
+                continue;
+            }
+            let Some(system) = self.universe.solar_systems.get(&current) else {
+                continue;
+            };
+            for next in self.links(system) {
+                if *next != destination && self.avoid.contains(next) {
+                    continue;
+                }
+                let Some(step) = self.jump_cost(*next) else {
+                    continue;
+                };
+                let candidate = (cost + step, jumps + 1);
+                if costs.get(next).is_none_or(|best| candidate < *best) {
+                    costs.insert(*next, candidate);
+                    previous.insert(*next, current);
+                    heap.push(Reverse((candidate.0, candidate.1, *next)));
+                }
+            }
+        }
+        None
+    }
+
+    /// Returns the number of stargate jumps of the route between two Solar Systems
+    pub fn jumps(&self, origin: u32, destination: u32) -> Option<usize> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        self.find_route(origin, destination)
+            .map(|route| route.jumps())
+    }
+
+    /// Returns every Solar System within `radius` stargate jumps of any of the centres,
+    /// with the number of jumps to the closest centre.
+    ///
+    /// The centres are included with distance 0. The route mode is not used here, but
+    /// Solar Systems in the avoid list are not crossed. Unknown centres are ignored.
+    pub fn neighbourhood(&self, centres: &[u32], radius: usize) -> HashMap<u32, usize> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let mut result: HashMap<u32, usize> = HashMap::new();
+        let mut queue = VecDeque::new();
+        for centre in centres {
+            if self.universe.solar_systems.contains_key(centre) && !result.contains_key(centre) {
+                result.insert(*centre, 0);
+                queue.push_back(*centre);
+            }
+        }
+        // multi source breadth first search, so every system gets the closest centre
+        while let Some(current) = queue.pop_front() {
+            let distance = result[&current];
+            if distance >= radius {
+                continue;
+            }
+            let Some(system) = self.universe.solar_systems.get(&current) else {
+                continue;
+            };
+            for next in self.links(system) {
+                if result.contains_key(next)
+                    || self.avoid.contains(next)
+                    || !self.universe.solar_systems.contains_key(next)
+                {
+                    continue;
+                }
+                result.insert(*next, distance + 1);
+                queue.push_back(*next);
+            }
+        }
+        result
+    }
+
+    /// Solar Systems reached in one jump from a Solar System, through stargates or jump bridges
+    fn links<'b>(&'b self, system: &'b SolarSystem) -> impl Iterator<Item = &'b u32> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        system
+            .connections
+            .iter()
+            .chain(self.bridges.get(&system.id).into_iter().flatten())
+    }
+
+    /// Cost of jumping into a Solar System according to the route mode
+    fn jump_cost(&self, system_id: u32) -> Option<u64> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let system = self.universe.solar_systems.get(&system_id)?;
+        let cost = match self.mode {
+            RouteMode::Shortest => 1,
+            RouteMode::Safer if !system.is_highsec() => PENALTY,
+            RouteMode::LessSecure if system.is_highsec() => PENALTY,
+            _ => 1,
+        };
+        Some(cost)
+    }
+
+    fn build_route(previous: &HashMap<u32, u32>, origin: u32, destination: u32) -> Route {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let mut systems = vec![destination];
+        let mut current = destination;
+        while current != origin {
+            current = previous[&current];
+            systems.push(current);
+        }
+        systems.reverse();
+        Route::new(systems)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{system, universe};
+
+    // 1 - 2 - 3 - 4 - 5 and a longer way 1 - 6 - 7 - 8 - 9 - 5, 10 is isolated
+    fn ring() -> Universe {
+        universe(
+            (1..=10).map(|id| system(id, 0.9)).collect(),
+            &[
+                (1, 2),
+                (2, 3),
+                (3, 4),
+                (4, 5),
+                (1, 6),
+                (6, 7),
+                (7, 8),
+                (8, 9),
+                (9, 5),
+            ],
+        )
+    }
+
+    #[test]
+    fn finds_the_shortest_route() {
+        let universe = ring();
+        let route = RoutePlanner::new(&universe).find_route(1, 5).unwrap();
+        assert_eq!(route.systems, vec![1, 2, 3, 4, 5]);
+        assert_eq!(route.origin(), Some(1));
+        assert_eq!(route.destination(), Some(5));
+        assert_eq!(route.jumps(), 4);
+    }
+
+    #[test]
+    fn route_to_the_origin_has_no_jumps() {
+        let universe = ring();
+        let route = RoutePlanner::new(&universe).find_route(3, 3).unwrap();
+        assert_eq!(route.systems, vec![3]);
+        assert_eq!(route.jumps(), 0);
+    }
+
+    #[test]
+    fn no_route_to_unknown_or_unreachable_systems() {
+        let universe = ring();
+        let planner = RoutePlanner::new(&universe);
+        assert!(planner.find_route(1, 10).is_none());
+        assert!(planner.find_route(1, 99).is_none());
+        assert!(planner.find_route(99, 1).is_none());
+    }
+
+    // 1 - 2 - 3 through low sec and 1 - 4 - 5 - 3 through high sec
+    fn security_split() -> Universe {
+        universe(
+            vec![
+                system(1, 0.9),
+                system(2, 0.3),
+                system(3, 0.9),
+                system(4, 0.8),
+                system(5, 0.7),
+            ],
+            &[(1, 2), (2, 3), (1, 4), (4, 5), (5, 3)],
+        )
+    }
+
+    #[test]
+    fn route_modes_follow_the_security() {
+        let universe = security_split();
+        let none = HashSet::new();
+        let route = |mode| universe.get_route(1, 3, mode, &none, &[]).unwrap().systems;
+        assert_eq!(route(RouteMode::Shortest), vec![1, 2, 3]);
+        assert_eq!(route(RouteMode::Safer), vec![1, 4, 5, 3]);
+        assert_eq!(route(RouteMode::LessSecure), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn safer_route_crosses_low_sec_when_there_is_no_other_way() {
+        let universe = universe(
+            vec![system(1, 0.9), system(2, 0.2), system(3, 0.9)],
+            &[(1, 2), (2, 3)],
+        );
+        let route = universe.get_route(1, 3, RouteMode::Safer, &HashSet::new(), &[]);
+        assert_eq!(route.unwrap().systems, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn avoided_systems_are_not_crossed() {
+        let universe = security_split();
+        let avoid = HashSet::from([2]);
+        let route = universe.get_route(1, 3, RouteMode::Shortest, &avoid, &[]);
+        assert_eq!(route.unwrap().systems, vec![1, 4, 5, 3]);
+        let avoid = HashSet::from([2, 4]);
+        assert!(
+            universe
+                .get_route(1, 3, RouteMode::Shortest, &avoid, &[])
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn avoided_origin_and_destination_are_allowed() {
+        let universe = security_split();
+        let avoid = HashSet::from([1, 3]);
+        let route = universe.get_route(1, 3, RouteMode::Shortest, &avoid, &[]);
+        assert_eq!(route.unwrap().systems, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn counts_the_jumps() {
+        let universe = ring();
+        assert_eq!(universe.get_jumps(6, 3, &[]), Some(3));
+        assert_eq!(universe.get_jumps(6, 10, &[]), None);
+    }
+
+    #[test]
+    fn neighbourhood_keeps_the_closest_centre() {
+        let universe = ring();
+        let around = universe.get_neighbourhood(&[1], 2, &[]);
+        let expected = HashMap::from([(1, 0), (2, 1), (6, 1), (3, 2), (7, 2)]);
+        assert_eq!(around, expected);
+
+        let around = universe.get_neighbourhood(&[1, 5], 1, &[]);
+        let expected = HashMap::from([(1, 0), (5, 0), (2, 1), (6, 1), (4, 1), (9, 1)]);
+        assert_eq!(around, expected);
+    }
+
+    #[test]
+    fn neighbourhood_of_radius_zero_is_the_centres() {
+        let universe = ring();
+        let around = universe.get_neighbourhood(&[3, 99], 0, &[]);
+        assert_eq!(around, HashMap::from([(3, 0)]));
+    }
+
+    #[test]
+    fn neighbourhood_does_not_cross_avoided_systems() {
+        let universe = ring();
+        let mut planner = RoutePlanner::new(&universe);
+        planner.avoid.insert(2);
+        let around = planner.neighbourhood(&[1], 3);
+        let expected = HashMap::from([(1, 0), (6, 1), (7, 2), (8, 3)]);
+        assert_eq!(around, expected);
+    }
+
+    #[test]
+    fn routes_go_through_the_bridges() {
+        let universe = ring();
+        let mut planner = RoutePlanner::new(&universe);
+        planner.set_bridges(&[(5, 1), (1, 5), (3, 3), (2, 99)]);
+        assert_eq!(planner.bridges[&1], vec![5]);
+        assert_eq!(planner.bridges[&5], vec![1]);
+        assert_eq!(planner.bridges.len(), 2);
+        assert_eq!(planner.find_route(2, 5).unwrap().systems, vec![2, 1, 5]);
+        assert_eq!(planner.jumps(6, 4), Some(3));
+        assert_eq!(planner.neighbourhood(&[5], 1).len(), 4);
+
+        // the public distances take the same bridges
+        let bridges = [(1, 5)];
+        let route = universe.get_route(2, 5, RouteMode::Shortest, &HashSet::new(), &bridges);
+        assert_eq!(route.unwrap().systems, vec![2, 1, 5]);
+        assert_eq!(universe.get_jumps(1, 5, &bridges), Some(1));
+        assert_eq!(universe.get_neighbourhood(&[5], 1, &bridges).len(), 4);
+        assert_eq!(universe.get_jumps(1, 5, &[]), Some(4));
+    }
+
+    #[test]
+    fn bridges_are_replaced() {
+        let universe = ring();
+        let mut planner = RoutePlanner::new(&universe);
+        planner.set_bridges(&[(1, 10)]);
+        assert_eq!(planner.jumps(2, 10), Some(2));
+        planner.set_bridges(&[]);
+        assert!(planner.bridges.is_empty());
+        assert!(planner.find_route(2, 10).is_none());
+    }
+}
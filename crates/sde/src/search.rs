@@ -0,0 +1,343 @@
+use crate::objects::Universe;
+
+/// Kind of object found by the SearchIndex
+#[derive(Hash, PartialEq, Eq, Clone, Copy, PartialOrd, Ord)]
+pub enum SearchKind {
+    /// Solar System
+    SolarSystem,
+    /// Constellation
+    Constellation,
+    /// Region
+    Region,
+}
+
+/// How a name matched the searched text, from best to worst
+#[derive(Hash, PartialEq, Eq, Clone, Copy, PartialOrd, Ord)]
+pub enum MatchKind {
+    /// The whole name is the searched text
+    Exact,
+    /// The name starts with the searched text
+    Prefix,
+    /// A word of the name starts with the searched text
+    WordPrefix,
+    /// The searched text is somewhere inside the name
+    Substring,
+    /// The searched text is close to the start of the name, allowing some typos
+    Typo(u8),
+}
+
+/// Object found by the SearchIndex
+#[derive(Hash, PartialEq, Eq, Clone)]
+pub struct SearchResult {
+    /// Identifier of the object
+    pub id: u32,
+    /// Name of the object
+    pub name: String,
+    /// Kind of the object
+    pub kind: SearchKind,
+    /// Region identifier, for Regions it is the same identifier
+    pub region: u32,
+    /// How the name matched the searched text
+    pub matched: MatchKind,
+}
+
+/// Name prepared to be matched against searched texts, shared by the indexes that
+/// search by name
+pub(crate) struct NameKey {
+    // lowercase name with only letters and numbers, so "1dq" finds "1DQ1-A"
+    normalized: String,
+    // lowercase words of the name
+    words: Vec<String>,
+}
+
+impl NameKey {
+    pub(crate) fn new(name: &str) -> Self {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        NameKey {
+            normalized: normalize(name),
+            words: name
+                .to_lowercase()
+                .split(|c: char| !c.is_alphanumeric())
+                .filter(|word| !word.is_empty())
+                .map(String::from)
+                .collect(),
+        }
+    }
+
+    /// Returns how the name matches the searched text. `query` is the normalized text
+    /// and `lowercase` the trimmed lowercase one.
+    pub(crate) fn matches(&self, query: &str, lowercase: &str) -> Option<MatchKind> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        if self.normalized == query {
+            return Some(MatchKind::Exact);
+        }
+        if self.normalized.starts_with(query) {
+            return Some(MatchKind::Prefix);
+        }
+        if self.words.iter().any(|word| word.starts_with(lowercase)) {
+            return Some(MatchKind::WordPrefix);
+        }
+        if self.normalized.contains(query) {
+            return Some(MatchKind::Substring);
+        }
+        // very short texts would match almost everything with a typo
+        let query_len = query.chars().count();
+        if query_len < 3 {
+            return None;
+        }
+        let allowed = if query_len < 6 { 1 } else { 2 };
+        let prefix: String = self.normalized.chars().take(query_len).collect();
+        let distance = distance(query, &prefix).min(distance(query, &self.normalized));
+        if distance <= allowed {
+            return Some(MatchKind::Typo(distance as u8));
+        }
+        None
+    }
+}
+
+/// Returns the text in lowercase with only letters and numbers
+pub(crate) fn normalize(text: &str) -> String {
+    #[cfg(feature = "puffin")]
+    puffin::profile_function!();
+
+    text.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Optimal string alignment distance, it counts insertions, deletions, substitutions
+/// and transpositions of adjacent characters
+fn distance(a: &str, b: &str) -> usize {
+    #[cfg(feature = "puffin")]
+    puffin::profile_function!();
+
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut matrix = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for (i, row) in matrix.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    matrix[0] = (0..=b.len()).collect();
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut value = (matrix[i - 1][j] + 1)
+                .min(matrix[i][j - 1] + 1)
+                .min(matrix[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                value = value.min(matrix[i - 2][j - 2] + 1);
+            }
+            matrix[i][j] = value;
+        }
+    }
+    matrix[a.len()][b.len()]
+}
+
+struct SearchEntry {
+    id: u32,
+    name: String,
+    kind: SearchKind,
+    region: u32,
+    key: NameKey,
+}
+
+/// In-memory index to search Solar Systems, Constellations and Regions by name.
+///
+/// It supports prefix, substring and typo tolerant matches, and the results are ranked
+/// so the best match comes first.
+pub struct SearchIndex {
+    entries: Vec<SearchEntry>,
+}
+
+impl SearchIndex {
+    /// Creates a new SearchIndex with every Solar System, Constellation and Region of
+    /// the Universe
+    pub fn new(universe: &Universe) -> Self {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let mut entries = Vec::with_capacity(
+            universe.solar_systems.len() + universe.constellations.len() + universe.regions.len(),
+        );
+        for system in universe.solar_systems.values() {
+            entries.push(Self::entry(
+                system.id,
+                &system.name,
+                SearchKind::SolarSystem,
+                system.region,
+            ));
+        }
+        for constellation in universe.constellations.values() {
+            entries.push(Self::entry(
+                constellation.id,
+                &constellation.name,
+                SearchKind::Constellation,
+                constellation.region,
+            ));
+        }
+        for region in universe.regions.values() {
+            entries.push(Self::entry(
+                region.id,
+                &region.name,
+                SearchKind::Region,
+                region.id,
+            ));
+        }
+        SearchIndex { entries }
+    }
+
+    /// Returns the objects whose name matches the text, best matches first. When `kind`
+    /// is set only that kind of objects is returned. `limit` caps the number of results.
+    pub fn search(&self, text: &str, kind: Option<SearchKind>, limit: usize) -> Vec<SearchResult> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let query = normalize(text);
+        if query.is_empty() {
+            return Vec::new();
+        }
+        let lowercase = text.trim().to_lowercase();
+        let mut results: Vec<(MatchKind, &SearchEntry)> = self
+            .entries
+            .iter()
+            .filter(|entry| kind.is_none_or(|value| value == entry.kind))
+            .filter_map(|entry| {
+                entry
+                    .key
+                    .matches(&query, &lowercase)
+                    .map(|matched| (matched, entry))
+            })
+            .collect();
+        results.sort_by(|a, b| {
+            a.0.cmp(&b.0)
+                .then_with(|| a.1.name.len().cmp(&b.1.name.len()))
+                .then_with(|| a.1.kind.cmp(&b.1.kind))
+                .then_with(|| a.1.name.cmp(&b.1.name))
+        });
+        results
+            .into_iter()
+            .take(limit)
+            .map(|(matched, entry)| SearchResult {
+                id: entry.id,
+                name: entry.name.clone(),
+                kind: entry.kind,
+                region: entry.region,
+                matched,
+            })
+            .collect()
+    }
+
+    fn entry(id: u32, name: &str, kind: SearchKind, region: u32) -> SearchEntry {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        SearchEntry {
+            id,
+            name: name.to_string(),
+            kind,
+            region,
+            key: NameKey::new(name),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{system, universe};
+
+    fn index() -> SearchIndex {
+        let names = [
+            "Jita",
+            "Jitanen",
+            "Perimeter",
+            "1DQ1-A",
+            "Niarja",
+            "New Caldari",
+        ];
+        let systems = names
+            .iter()
+            .zip(30000001..)
+            .map(|(name, id)| {
+                let mut system = system(id, 0.5);
+                system.name = name.to_string();
+                system
+            })
+            .collect();
+        SearchIndex::new(&universe(systems, &[]))
+    }
+
+    fn names(results: &[SearchResult]) -> Vec<&str> {
+        results.iter().map(|result| result.name.as_str()).collect()
+    }
+
+    #[test]
+    fn counts_typos_and_transpositions() {
+        assert_eq!(distance("kitten", "sitting"), 3);
+        assert_eq!(distance("jtia", "jita"), 1);
+        assert_eq!(distance("", "abc"), 3);
+        assert_eq!(distance("same", "same"), 0);
+    }
+
+    #[test]
+    fn normalizes_the_names() {
+        assert_eq!(normalize(" 1DQ1-A "), "1dq1a");
+        assert_eq!(normalize("New Caldari"), "newcaldari");
+    }
+
+    #[test]
+    fn ranks_exact_matches_before_prefixes() {
+        let index = index();
+        let results = index.search("jita", Some(SearchKind::SolarSystem), 10);
+        assert_eq!(names(&results), vec!["Jita", "Jitanen"]);
+        assert!(results[0].matched == MatchKind::Exact);
+        assert!(results[1].matched == MatchKind::Prefix);
+    }
+
+    #[test]
+    fn finds_words_substrings_and_typos() {
+        let index = index();
+        let kind = Some(SearchKind::SolarSystem);
+        let results = index.search("caldari", kind, 10);
+        assert_eq!(names(&results), vec!["New Caldari"]);
+        assert!(results[0].matched == MatchKind::WordPrefix);
+
+        let results = index.search("1dq", kind, 10);
+        assert_eq!(names(&results), vec!["1DQ1-A"]);
+        assert!(results[0].matched == MatchKind::Prefix);
+
+        let results = index.search("rimet", kind, 10);
+        assert_eq!(names(&results), vec!["Perimeter"]);
+        assert!(results[0].matched == MatchKind::Substring);
+
+        let results = index.search("Nirja", kind, 10);
+        assert_eq!(names(&results), vec!["Niarja"]);
+        assert!(results[0].matched == MatchKind::Typo(1));
+    }
+
+    #[test]
+    fn filters_by_kind_and_limits_the_results() {
+        let index = index();
+        let results = index.search("region", Some(SearchKind::Region), 10);
+        assert_eq!(results.len(), 1);
+        assert!(results[0].kind == SearchKind::Region);
+        assert_eq!(results[0].id, crate::testing::REGION);
+        assert_eq!(index.search("j", None, 1).len(), 1);
+        assert!(index.search(" - ", None, 10).is_empty());
+    }
+
+    #[test]
+    fn short_texts_do_not_match_with_typos() {
+        let index = index();
+        assert!(
+            index
+                .search("jx", Some(SearchKind::SolarSystem), 10)
+                .is_empty()
+        );
+    }
+}
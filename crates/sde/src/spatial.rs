@@ -0,0 +1,187 @@
+use crate::objects::{SolarSystem, Universe};
+use kdtree::KdTree;
+use kdtree::distance::squared_euclidean;
+
+/// Meters in a light year
+pub const LIGHT_YEAR: f64 = 9_460_730_472_580_800.0;
+/// Pochven region identifier, jump drives can not be used there
+pub const POCHVEN_REGION: u32 = 10000070;
+/// Zarzakh Solar System identifier, it does not allow cynosural fields
+pub const ZARZAKH_SYSTEM: u32 = 30100000;
+
+/// Spatial index over the real space coordinates of the known space Solar Systems.
+///
+/// The coordinates are read from `SolarSystem.real_coords`, so the Universe needs to be
+/// loaded before building the index.
+pub struct SpatialIndex<'a> {
+    universe: &'a Universe,
+    tree: KdTree<f64, u32, [f64; 3]>,
+}
+
+impl<'a> SpatialIndex<'a> {
+    /// Creates a new SpatialIndex with every known space Solar System of the Universe
+    pub fn new(universe: &'a Universe) -> Self {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let mut tree = KdTree::with_capacity(3, universe.solar_systems.len());
+        for system in universe.solar_systems.values() {
+            if Self::is_known_space(system) {
+                // the points are always finite and with 3 dimensions, so add never fails
+                let _ = tree.add(system.real_coords.clone().into(), system.id);
+            }
+        }
+        SpatialIndex { universe, tree }
+    }
+
+    /// Returns every known space Solar System within `light_years` of the given Solar System,
+    /// sorted by distance and with the distance in light years. The Solar System itself is
+    /// not included.
+    pub fn systems_within(&self, system_id: u32, light_years: f64) -> Vec<(u32, f64)> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let Some(system) = self.universe.solar_systems.get(&system_id) else {
+            return Vec::new();
+        };
+        let point: [f64; 3] = system.real_coords.clone().into();
+        let radius = light_years * LIGHT_YEAR;
+        match self
+            .tree
+            .within(&point, radius * radius, &squared_euclidean)
+        {
+            Ok(found) => found
+                .into_iter()
+                .filter(|(_, id)| **id != system_id)
+                .map(|(distance, id)| (*id, distance.sqrt() / LIGHT_YEAR))
+                .collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    /// Returns the Solar Systems a jump drive can reach from the given Solar System with
+    /// the given range in light years, sorted by distance.
+    ///
+    /// Jump drives can be activated in High Security space, but a cynosural field can not
+    /// be lit there, nor in Pochven or Zarzakh, so those are not included.
+    pub fn jump_destinations(&self, system_id: u32, light_years: f64) -> Vec<(u32, f64)> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        match self.universe.solar_systems.get(&system_id) {
+            Some(system) if Self::allows_jump_drive(system) => self
+                .systems_within(system_id, light_years)
+                .into_iter()
+                .filter(|(id, _)| {
+                    self.universe
+                        .solar_systems
+                        .get(id)
+                        .is_some_and(Self::allows_cyno)
+                })
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Distance in light years between two Solar Systems
+    pub fn distance(&self, system_a: u32, system_b: u32) -> Option<f64> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let a = self.universe.solar_systems.get(&system_a)?;
+        let b = self.universe.solar_systems.get(&system_b)?;
+        Some(a.distance_ly(b))
+    }
+
+    /// Returns true if a jump drive can be activated in the Solar System
+    pub fn allows_jump_drive(system: &SolarSystem) -> bool {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        Self::is_known_space(system) && system.region != POCHVEN_REGION
+    }
+
+    /// Returns true if a cynosural field can be lit in the Solar System
+    pub fn allows_cyno(system: &SolarSystem) -> bool {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        Self::allows_jump_drive(system) && !system.is_highsec() && system.id != ZARZAKH_SYSTEM
+    }
+
+    fn is_known_space(system: &SolarSystem) -> bool {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        (30000000..31000000).contains(&system.id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{placed, universe};
+
+    const JITA: u32 = 30000142;
+
+    fn space() -> Universe {
+        let mut pochven = placed(30000157, -1.0, [1.0, 0.0, 0.0]);
+        pochven.region = POCHVEN_REGION;
+        universe(
+            vec![
+                placed(JITA, 0.95, [0.0, 0.0, 0.0]),
+                placed(30000001, 0.3, [3.0, 0.0, 0.0]),
+                placed(30000002, 0.8, [0.0, 2.0, 0.0]),
+                placed(30000003, -0.2, [0.0, 0.0, 6.0]),
+                placed(30000004, 0.1, [9.0, 0.0, 0.0]),
+                placed(31000005, -1.0, [0.5, 0.0, 0.0]),
+                placed(ZARZAKH_SYSTEM, -1.0, [0.0, 0.0, 1.5]),
+                pochven,
+            ],
+            &[],
+        )
+    }
+
+    #[test]
+    fn finds_the_systems_within_range_by_distance() {
+        let universe = space();
+        let index = SpatialIndex::new(&universe);
+        let ids: Vec<u32> = index
+            .systems_within(JITA, 6.5)
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect();
+        // J-Space is not indexed
+        assert_eq!(
+            ids,
+            vec![30000157, ZARZAKH_SYSTEM, 30000002, 30000001, 30000003]
+        );
+        assert!(index.systems_within(99, 10.0).is_empty());
+    }
+
+    #[test]
+    fn measures_light_years() {
+        let universe = space();
+        let index = SpatialIndex::new(&universe);
+        let distance = index.distance(30000001, 30000002).unwrap();
+        assert!((distance - 13.0f64.sqrt()).abs() < 1e-6);
+        assert!(index.distance(JITA, 99).is_none());
+    }
+
+    #[test]
+    fn jumps_out_of_high_sec_to_a_cyno_in_low_sec() {
+        let universe = space();
+        let index = SpatialIndex::new(&universe);
+        let destinations = index.jump_destinations(JITA, 7.0);
+        let ids: Vec<u32> = destinations.iter().map(|(id, _)| *id).collect();
+        assert_eq!(ids, vec![30000001, 30000003]);
+        assert!((destinations[0].1 - 3.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn no_jumps_out_of_pochven() {
+        let universe = space();
+        let index = SpatialIndex::new(&universe);
+        assert!(index.jump_destinations(30000157, 10.0).is_empty());
+    }
+}
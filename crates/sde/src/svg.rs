@@ -0,0 +1,361 @@
+use crate::bridge::BRIDGE_LINE_PREFIX;
+use crate::cache::MapData;
+use crate::route::Route;
+use std::collections::HashMap;
+
+// colors of the image, close to the dark theme of the map panes
+const BACKGROUND: &str = "#16191d";
+const STARGATE: &str = "#4a5560";
+const BRIDGE: &str = "#3a8fd8";
+const NODE: &str = "#d88e3a";
+const TEXT: &str = "#d0d4d8";
+const REGION_TEXT: &str = "#7f8a94";
+const INTEL: &str = "#e53935";
+const CHARACTER: &str = "#43a047";
+const ROUTE: &str = "#ffd54f";
+
+/// Things drawn on top of the map
+#[derive(Hash, PartialEq, Eq, Clone)]
+pub struct SvgHighlights {
+    /// Solar Systems reported in the intel channels, drawn with a red ring
+    pub intel: Vec<u32>,
+    /// Character names with the Solar System where they are
+    pub characters: Vec<(String, u32)>,
+    /// Routes drawn as a line through their Solar Systems
+    pub routes: Vec<Route>,
+}
+
+impl SvgHighlights {
+    /// Creates a new SvgHighlights without anything to highlight
+    pub fn new() -> Self {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        SvgHighlights {
+            intel: Vec::new(),
+            characters: Vec::new(),
+            routes: Vec::new(),
+        }
+    }
+}
+
+impl Default for SvgHighlights {
+    fn default() -> Self {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        Self::new()
+    }
+}
+
+/// Draws the points and lines of a map as an SVG image, without a window or a GPU.
+///
+/// The map data is the same one given to the map panes, `SdeManager::get_universe_map`
+/// for the universe and `SdeManager::get_region_map` for the abstract map of a region,
+/// so the image looks like the live map.
+pub struct SvgRenderer {
+    /// Width of the image in pixels, the height follows the shape of the map
+    pub width: f64,
+    /// Empty space around the map in pixels
+    pub margin: f64,
+    /// Radius of the Solar System points in pixels
+    pub node_radius: f64,
+    /// Draws the name of every Solar System next to its point
+    pub system_labels: bool,
+    /// Title written in the top left corner
+    pub title: Option<String>,
+    /// Texts drawn in map coordinates, like the Region names of the universe map
+    pub labels: Vec<(String, [f32; 2])>,
+    /// Intel, characters and routes drawn on top of the map
+    pub highlights: SvgHighlights,
+}
+
+impl SvgRenderer {
+    /// Creates a new SvgRenderer for a 1200 pixels wide image with the Solar System names
+    pub fn new() -> Self {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        SvgRenderer {
+            width: 1200.0,
+            margin: 40.0,
+            node_radius: 4.0,
+            system_labels: true,
+            title: None,
+            labels: Vec::new(),
+            highlights: SvgHighlights::new(),
+        }
+    }
+
+    /// Returns the SVG document of the map
+    pub fn render(&self, data: &MapData) -> String {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let points: HashMap<u32, [f64; 2]> = data
+            .points
+            .iter()
+            .map(|(id, point)| {
+                let [x, y] = point.raw_point.components;
+                (*id as u32, [x as f64, y as f64])
+            })
+            .collect();
+        let (mut min, mut max) = ([f64::MAX; 2], [f64::MIN; 2]);
+        for position in points.values() {
+            for axis in 0..2 {
+                min[axis] = min[axis].min(position[axis]);
+                max[axis] = max[axis].max(position[axis]);
+            }
+        }
+        if points.is_empty() {
+            (min, max) = ([0.0; 2], [1.0; 2]);
+        }
+        let inner = (self.width - 2.0 * self.margin).max(1.0);
+        let scale = inner / (max[0] - min[0]).max(max[1] - min[1]).max(f64::EPSILON);
+        let height = (max[1] - min[1]) * scale + 2.0 * self.margin;
+        let place = |position: [f64; 2]| {
+            [
+                self.margin + (position[0] - min[0]) * scale,
+                self.margin + (position[1] - min[1]) * scale,
+            ]
+        };
+
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{:.0}\" height=\"{:.0}\" \
+             viewBox=\"0 0 {:.0} {:.0}\" font-family=\"sans-serif\">\n",
+            self.width, height, self.width, height
+        );
+        svg += &format!("<rect width=\"100%\" height=\"100%\" fill=\"{BACKGROUND}\"/>\n");
+
+        // sorted so the same map always gives the same file
+        let mut lines: Vec<_> = data.lines.iter().collect();
+        lines.sort_by(|a, b| a.0.cmp(b.0));
+        svg += &format!("<g stroke=\"{STARGATE}\" stroke-width=\"1\">\n");
+        for (id, line) in &lines {
+            let [a, b] = line.raw_line.points.map(|point| {
+                let [x, y] = point.components;
+                place([x as f64, y as f64])
+            });
+            let color = if id.starts_with(BRIDGE_LINE_PREFIX) {
+                format!(" stroke=\"{BRIDGE}\"")
+            } else {
+                String::new()
+            };
+            svg += &format!(
+                "<line x1=\"{:.1}\" y1=\"{:.1}\" x2=\"{:.1}\" y2=\"{:.1}\"{color}/>\n",
+                a[0], a[1], b[0], b[1]
+            );
+        }
+        svg += "</g>\n";
+
+        for route in &self.highlights.routes {
+            // a route can leave the map, every part inside of it is drawn on its own
+            for part in route
+                .systems
+                .split(|system| !points.contains_key(system))
+                .filter(|part| part.len() > 1)
+            {
+                let path: Vec<String> = part
+                    .iter()
+                    .map(|system| {
+                        let [x, y] = place(points[system]);
+                        format!("{x:.1},{y:.1}")
+                    })
+                    .collect();
+                svg += &format!(
+                    "<polyline points=\"{}\" fill=\"none\" stroke=\"{ROUTE}\" \
+                     stroke-width=\"3\" stroke-linejoin=\"round\" opacity=\"0.8\"/>\n",
+                    path.join(" ")
+                );
+            }
+        }
+
+        let mut ids: Vec<&u32> = points.keys().collect();
+        ids.sort_unstable();
+        svg += &format!("<g fill=\"{NODE}\">\n");
+        for id in &ids {
+            let [x, y] = place(points[id]);
+            svg += &format!(
+                "<circle cx=\"{x:.1}\" cy=\"{y:.1}\" r=\"{:.1}\"/>\n",
+                self.node_radius
+            );
+        }
+        svg += "</g>\n";
+
+        if self.system_labels {
+            svg += &format!("<g fill=\"{TEXT}\" font-size=\"11\">\n");
+            for id in &ids {
+                let [x, y] = place(points[id]);
+                let name = data.points[&(**id as usize)].get_name();
+                svg += &format!(
+                    "<text x=\"{:.1}\" y=\"{:.1}\">{}</text>\n",
+                    x + self.node_radius + 2.0,
+                    y - self.node_radius,
+                    escape(&name)
+                );
+            }
+            svg += "</g>\n";
+        }
+
+        svg += &format!("<g fill=\"{REGION_TEXT}\" font-size=\"16\" text-anchor=\"middle\">\n");
+        for (text, position) in &self.labels {
+            let [x, y] = place([position[0] as f64, position[1] as f64]);
+            svg += &format!("<text x=\"{x:.1}\" y=\"{y:.1}\">{}</text>\n", escape(text));
+        }
+        svg += "</g>\n";
+
+        for system in &self.highlights.intel {
+            if let Some(position) = points.get(system) {
+                let [x, y] = place(*position);
+                svg += &format!(
+                    "<circle cx=\"{x:.1}\" cy=\"{y:.1}\" r=\"{:.1}\" fill=\"none\" \
+                     stroke=\"{INTEL}\" stroke-width=\"2\"/>\n",
+                    self.node_radius * 2.5
+                );
+            }
+        }
+
+        for (name, system) in &self.highlights.characters {
+            if let Some(position) = points.get(system) {
+                let [x, y] = place(*position);
+                // a triangle pointing down to the Solar System, with the name on top
+                let size = self.node_radius * 2.0;
+                let tip = y - self.node_radius * 1.5;
+                let base = tip - size;
+                svg += &format!(
+                    "<polygon points=\"{x:.1},{tip:.1} {:.1},{base:.1} {:.1},{base:.1}\" \
+                     fill=\"{CHARACTER}\"/>\n",
+                    x - size * 0.6,
+                    x + size * 0.6
+                );
+                svg += &format!(
+                    "<text x=\"{x:.1}\" y=\"{:.1}\" fill=\"{CHARACTER}\" font-size=\"12\" \
+                     text-anchor=\"middle\">{}</text>\n",
+                    base - 3.0,
+                    escape(name)
+                );
+            }
+        }
+
+        if let Some(title) = &self.title {
+            svg += &format!(
+                "<text x=\"{:.1}\" y=\"{:.1}\" fill=\"{TEXT}\" font-size=\"20\">{}</text>\n",
+                self.margin / 2.0,
+                self.margin / 2.0 + 8.0,
+                escape(title)
+            );
+        }
+        svg += "</svg>\n";
+        svg
+    }
+}
+
+impl Default for SvgRenderer {
+    fn default() -> Self {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        Self::new()
+    }
+}
+
+/// Escapes the characters that can not be in SVG text
+fn escape(text: &str) -> String {
+    #[cfg(feature = "puffin")]
+    puffin::profile_function!();
+
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use egui_map::map::objects::{MapLine, MapPoint, RawPoint};
+
+    // 1 and 2 joined by a stargate, 3 by a jump bridge to 1
+    fn map() -> MapData {
+        let mut data = MapData::new();
+        for (id, name, x, y) in [
+            (1, "Jita", 0.0, 0.0),
+            (2, "A&B", 100.0, 0.0),
+            (3, "Perimeter", 100.0, 50.0),
+        ] {
+            let mut point = MapPoint::new(id, RawPoint::new(x, y));
+            point.set_name(name.to_string());
+            data.points.insert(id, point);
+        }
+        let mut line = MapLine::new(RawPoint::new(0.0, 0.0), RawPoint::new(100.0, 0.0));
+        line.id = Some(String::from("1-2"));
+        data.lines.insert(String::from("1-2"), line);
+        data.add_bridges(&[(1, 3)]);
+        data
+    }
+
+    // 240 pixels wide with a margin of 20, so one map unit is two pixels
+    fn renderer() -> SvgRenderer {
+        let mut renderer = SvgRenderer::new();
+        renderer.width = 240.0;
+        renderer.margin = 20.0;
+        renderer
+    }
+
+    #[test]
+    fn draws_the_points_lines_and_names() {
+        let mut renderer = renderer();
+        renderer.title = Some(String::from("The Forge <test>"));
+        let data = map();
+        let svg = renderer.render(&data);
+        assert!(
+            svg.starts_with(
+                "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"240\" height=\"140\""
+            )
+        );
+        assert!(svg.ends_with("</svg>\n"));
+        assert_eq!(svg.matches("<circle ").count(), 3);
+        assert!(svg.contains("<circle cx=\"220.0\" cy=\"120.0\" r=\"4.0\"/>"));
+        assert!(svg.contains("<line x1=\"20.0\" y1=\"20.0\" x2=\"220.0\" y2=\"20.0\"/>"));
+        // the bridge is dashed and has its own color
+        let bridge = format!(" stroke=\"{BRIDGE}\"/>");
+        assert_eq!(svg.matches(bridge.as_str()).count(), data.lines.len() - 1);
+        assert!(svg.contains(">A&amp;B</text>"));
+        assert!(svg.contains(">The Forge &lt;test&gt;</text>"));
+    }
+
+    #[test]
+    fn same_map_gives_the_same_image() {
+        assert_eq!(renderer().render(&map()), renderer().render(&map()));
+    }
+
+    #[test]
+    fn draws_the_highlights_inside_the_map() {
+        let mut renderer = renderer();
+        renderer.system_labels = false;
+        renderer.labels = vec![(String::from("Region"), [50.0, 25.0])];
+        renderer.highlights.intel = vec![2, 99];
+        renderer.highlights.characters =
+            vec![(String::from("Pilot"), 3), (String::from("Gone"), 99)];
+        // the route leaves the map and comes back, the part with one system is not drawn
+        renderer.highlights.routes = vec![Route::new(vec![1, 2, 99, 3])];
+        let svg = renderer.render(&map());
+        assert!(!svg.contains(">Jita</text>"));
+        assert!(svg.contains("<text x=\"120.0\" y=\"70.0\">Region</text>"));
+        assert_eq!(svg.matches(&format!("stroke=\"{INTEL}\"")).count(), 1);
+        assert!(svg.contains("<circle cx=\"220.0\" cy=\"20.0\" r=\"10.0\" fill=\"none\""));
+        assert_eq!(svg.matches("<polygon ").count(), 1);
+        assert!(svg.contains(">Pilot</text>"));
+        assert!(!svg.contains("Gone"));
+        assert_eq!(svg.matches("<polyline ").count(), 1);
+        assert!(svg.contains("<polyline points=\"20.0,20.0 220.0,20.0\""));
+    }
+
+    #[test]
+    fn empty_maps_are_drawn() {
+        let svg = renderer().render(&MapData::new());
+        assert!(svg.contains("width=\"240\" height=\"240\""));
+        assert!(!svg.contains("<circle "));
+        assert!(!svg.contains("NaN"));
+    }
+}
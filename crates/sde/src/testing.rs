@@ -0,0 +1,120 @@
+use crate::objects::{Constellation, Region, SdePoint, SolarSystem, Universe};
+use crate::spatial::LIGHT_YEAR;
+use rusqlite::Connection;
+use std::fs::{create_dir_all, remove_dir_all};
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+
+/// Region of the Solar Systems built with `system`
+pub(crate) const REGION: u32 = 10000002;
+/// Constellation of the Solar Systems built with `system`
+pub(crate) const CONSTELLATION: u32 = 20000020;
+
+/// Creates a Solar System of `REGION` and `CONSTELLATION` with the given security, named
+/// after its identifier
+pub(crate) fn system(id: u32, security: f32) -> SolarSystem {
+    let mut system = SolarSystem::new(1);
+    system.id = id;
+    system.name = format!("System {id}");
+    system.region = REGION;
+    system.constellation = CONSTELLATION;
+    system.security = security;
+    system
+}
+
+/// Creates a Solar System like `system` placed in real space, the coordinates are given in
+/// light years
+pub(crate) fn placed(id: u32, security: f32, position: [f64; 3]) -> SolarSystem {
+    let mut system = system(id, security);
+    let [x, y, z] = position.map(|value| (value * LIGHT_YEAR) as i64);
+    system.real_coords = SdePoint::new(x, y, z);
+    system.projected_coords = SdePoint::new(x, y, z);
+    system
+}
+
+/// Builds a Universe with the Solar Systems and the stargates between them. The Regions
+/// and Constellations of the Solar Systems are created too.
+pub(crate) fn universe(systems: Vec<SolarSystem>, gates: &[(u32, u32)]) -> Universe {
+    let mut universe = Universe::new(1);
+    for mut system in systems {
+        for &(a, b) in gates {
+            if a == system.id {
+                system.connections.push(b);
+            } else if b == system.id {
+                system.connections.push(a);
+            }
+        }
+        let region = universe.regions.entry(system.region).or_insert_with(|| {
+            let mut region = Region::new();
+            region.id = system.region;
+            region.name = format!("Region {}", system.region);
+            region
+        });
+        if !region.constellations.contains(&system.constellation) {
+            region.constellations.push(system.constellation);
+        }
+        universe
+            .constellations
+            .entry(system.constellation)
+            .or_insert_with(|| {
+                let mut constellation = Constellation::new();
+                constellation.id = system.constellation;
+                constellation.name = format!("Constellation {}", system.constellation);
+                constellation.region = system.region;
+                constellation
+            })
+            .solar_systems
+            .push(system.id);
+        universe.solar_systems.insert(system.id, system);
+    }
+    universe
+}
+
+/// Folder for the files written by a test, removed with everything inside when dropped
+pub(crate) struct TempDir {
+    path: PathBuf,
+}
+
+impl Deref for TempDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = remove_dir_all(&self.path);
+    }
+}
+
+/// Returns an empty folder for the files written by a test, the name has to be unique
+pub(crate) fn temp_dir(name: &str) -> TempDir {
+    let path = std::env::temp_dir().join(format!("sde-{name}-{}", std::process::id()));
+    let _ = remove_dir_all(&path);
+    create_dir_all(&path).unwrap();
+    TempDir { path }
+}
+
+/// Creates a `sde.db` in a new test folder with the tables of the schema, then runs the
+/// extra SQL to fill them. The folder has to be kept while the database is used.
+pub(crate) fn schema_database(
+    name: &str,
+    schema: &[(&str, &[&str])],
+    extra_sql: &str,
+) -> (TempDir, PathBuf) {
+    let folder = temp_dir(name);
+    let path = folder.join("sde.db");
+    let connection = Connection::open(&path).unwrap();
+    for (table, columns) in schema {
+        connection
+            .execute(
+                &format!("CREATE TABLE {table} ({});", columns.join(", ")),
+                [],
+            )
+            .unwrap();
+    }
+    connection.execute_batch(extra_sql).unwrap();
+    (folder, path)
+}
@@ -0,0 +1,273 @@
+use std::ops::RangeInclusive;
+
+/// Known space (K-Space) region identifiers
+pub const KNOWN_SPACE_REGIONS: RangeInclusive<u32> = 10000000..=10999999;
+/// Wormhole space (J-Space) region identifiers
+pub const WORMHOLE_REGIONS: RangeInclusive<u32> = 11000000..=11999999;
+/// Wormhole space (J-Space) Solar System identifiers
+pub const WORMHOLE_SYSTEMS: RangeInclusive<u32> = 31000000..=31999999;
+
+/// Wormhole class of a Solar System, as the `wormholeClassID` of the SDE. Known space has
+/// a class too, it is the one used by wormholes to tell where they lead.
+#[derive(Hash, PartialEq, Eq, Clone, Copy)]
+pub enum WormholeClass {
+    /// J-Space classes 1 to 6
+    Class(u8),
+    /// High Security space
+    HighSec,
+    /// Low Security space
+    LowSec,
+    /// Null Security space
+    NullSec,
+    /// Thera
+    Thera,
+    /// Shattered J-Space systems where only frigates can enter
+    Shattered,
+    /// Drifter J-Space systems, classes 14 to 18
+    Drifter(u8),
+    /// Abyssal deadspace, classes 19 to 23
+    Abyssal(u8),
+    /// Pochven
+    Pochven,
+    /// Class without a known meaning, 0 when the SDE has no class
+    Unknown(u8),
+}
+
+impl WormholeClass {
+    /// Creates the WormholeClass from the SDE `wormholeClassID`
+    pub fn from_id(id: u8) -> Self {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        match id {
+            1..=6 => WormholeClass::Class(id),
+            7 => WormholeClass::HighSec,
+            8 => WormholeClass::LowSec,
+            9 => WormholeClass::NullSec,
+            12 => WormholeClass::Thera,
+            13 => WormholeClass::Shattered,
+            14..=18 => WormholeClass::Drifter(id),
+            19..=23 => WormholeClass::Abyssal(id),
+            25 => WormholeClass::Pochven,
+            _ => WormholeClass::Unknown(id),
+        }
+    }
+
+    /// Creates the WormholeClass from the label used by players, like "C5", "HS" or "Thera"
+    pub fn from_label(label: &str) -> Option<Self> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let label = label.trim().to_uppercase();
+        let class = match label.as_str() {
+            "HS" | "HIGHSEC" => WormholeClass::HighSec,
+            "LS" | "LOWSEC" => WormholeClass::LowSec,
+            "NS" | "NULLSEC" => WormholeClass::NullSec,
+            "THERA" | "C12" => WormholeClass::Thera,
+            "POCHVEN" => WormholeClass::Pochven,
+            _ => WormholeClass::from_id(label.strip_prefix('C')?.parse().ok()?),
+        };
+        match class {
+            WormholeClass::Unknown(_) => None,
+            _ => Some(class),
+        }
+    }
+
+    /// Returns the SDE `wormholeClassID`
+    pub fn id(&self) -> u8 {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        match self {
+            WormholeClass::Class(id)
+            | WormholeClass::Drifter(id)
+            | WormholeClass::Abyssal(id)
+            | WormholeClass::Unknown(id) => *id,
+            WormholeClass::HighSec => 7,
+            WormholeClass::LowSec => 8,
+            WormholeClass::NullSec => 9,
+            WormholeClass::Thera => 12,
+            WormholeClass::Shattered => 13,
+            WormholeClass::Pochven => 25,
+        }
+    }
+
+    /// Returns the short label used by players, like "C5", "HS" or "Thera"
+    pub fn label(&self) -> String {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        match self {
+            WormholeClass::HighSec => String::from("HS"),
+            WormholeClass::LowSec => String::from("LS"),
+            WormholeClass::NullSec => String::from("NS"),
+            WormholeClass::Thera => String::from("Thera"),
+            WormholeClass::Pochven => String::from("Pochven"),
+            WormholeClass::Unknown(0) => String::new(),
+            _ => format!("C{}", self.id()),
+        }
+    }
+
+    /// Returns true if the class belongs to wormhole space
+    pub fn is_jspace(&self) -> bool {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        matches!(
+            self,
+            WormholeClass::Class(_)
+                | WormholeClass::Thera
+                | WormholeClass::Shattered
+                | WormholeClass::Drifter(_)
+        )
+    }
+}
+
+impl Default for WormholeClass {
+    fn default() -> Self {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        WormholeClass::Unknown(0)
+    }
+}
+
+/// System-wide effect of a J-Space Solar System, caused by its secondary sun
+#[derive(Hash, PartialEq, Eq, Clone, Copy)]
+pub enum WormholeEffect {
+    /// Black Hole
+    BlackHole,
+    /// Cataclysmic Variable
+    CataclysmicVariable,
+    /// Magnetar
+    Magnetar,
+    /// Pulsar
+    Pulsar,
+    /// Red Giant
+    RedGiant,
+    /// Wolf-Rayet
+    WolfRayet,
+}
+
+impl WormholeEffect {
+    /// Every effect, in alphabetical order
+    pub const ALL: [WormholeEffect; 6] = [
+        WormholeEffect::BlackHole,
+        WormholeEffect::CataclysmicVariable,
+        WormholeEffect::Magnetar,
+        WormholeEffect::Pulsar,
+        WormholeEffect::RedGiant,
+        WormholeEffect::WolfRayet,
+    ];
+
+    /// Returns the effect whose name is part of the text, like the secondary sun or the
+    /// effect beacon type names of the SDE
+    pub fn from_name(text: &str) -> Option<Self> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let text = text.to_lowercase();
+        Self::ALL
+            .into_iter()
+            .find(|effect| text.contains(&effect.name().to_lowercase()))
+    }
+
+    /// Returns the effect name as shown in game
+    pub fn name(&self) -> &'static str {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        match self {
+            WormholeEffect::BlackHole => "Black Hole",
+            WormholeEffect::CataclysmicVariable => "Cataclysmic Variable",
+            WormholeEffect::Magnetar => "Magnetar",
+            WormholeEffect::Pulsar => "Pulsar",
+            WormholeEffect::RedGiant => "Red Giant",
+            WormholeEffect::WolfRayet => "Wolf-Rayet",
+        }
+    }
+}
+
+/// Wormhole that always spawns in a J-Space Solar System
+#[derive(Hash, PartialEq, Eq, Clone)]
+pub struct WormholeStatic {
+    /// Wormhole type code, like "H296"
+    pub name: String,
+    /// Class of the Solar System where the wormhole leads
+    pub destination: WormholeClass,
+}
+
+impl WormholeStatic {
+    /// Creates a new WormholeStatic Strcut. ALl the values are initialized. Needs to be filled
+    pub fn new() -> Self {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        WormholeStatic {
+            name: String::new(),
+            destination: WormholeClass::default(),
+        }
+    }
+}
+
+impl Default for WormholeStatic {
+    fn default() -> Self {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn class_identifiers_round_trip() {
+        for id in 0..=30 {
+            assert_eq!(WormholeClass::from_id(id).id(), id);
+        }
+        assert!(WormholeClass::from_id(5) == WormholeClass::Class(5));
+        assert!(WormholeClass::from_id(25) == WormholeClass::Pochven);
+        assert!(WormholeClass::from_id(0) == WormholeClass::default());
+    }
+
+    #[test]
+    fn reads_and_writes_the_player_labels() {
+        for label in ["C1", "C6", "HS", "LS", "NS", "Thera", "C13", "Pochven"] {
+            assert_eq!(WormholeClass::from_label(label).unwrap().label(), label);
+        }
+        assert!(WormholeClass::from_label(" c5 ") == Some(WormholeClass::Class(5)));
+        assert!(WormholeClass::from_label("highsec") == Some(WormholeClass::HighSec));
+        assert!(WormholeClass::from_label("C12") == Some(WormholeClass::Thera));
+        assert!(WormholeClass::from_label("C99").is_none());
+        assert!(WormholeClass::from_label("H296").is_none());
+        assert_eq!(WormholeClass::default().label(), "");
+    }
+
+    #[test]
+    fn tells_wormhole_space_apart() {
+        assert!(WormholeClass::Class(3).is_jspace());
+        assert!(WormholeClass::Thera.is_jspace());
+        assert!(WormholeClass::Shattered.is_jspace());
+        assert!(WormholeClass::Drifter(14).is_jspace());
+        assert!(!WormholeClass::HighSec.is_jspace());
+        assert!(!WormholeClass::Pochven.is_jspace());
+        assert!(!WormholeClass::Abyssal(19).is_jspace());
+        assert!(!WormholeClass::default().is_jspace());
+    }
+
+    #[test]
+    fn finds_the_effect_in_the_type_names() {
+        assert!(
+            WormholeEffect::from_name("Black Hole Effect Beacon")
+                == Some(WormholeEffect::BlackHole)
+        );
+        assert!(WormholeEffect::from_name("wolf-rayet star") == Some(WormholeEffect::WolfRayet));
+        assert!(WormholeEffect::from_name("Sun G5 (Yellow)").is_none());
+        for effect in WormholeEffect::ALL {
+            assert!(WormholeEffect::from_name(effect.name()) == Some(effect));
+        }
+    }
+}
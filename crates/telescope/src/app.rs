@@ -1,6 +1,6 @@
 use crate::app::file::IntelEventHandler;
-use crate::app::messages::{CharacterSync, MapSync, Message, SettingsPage, Target, Type};
-use crate::app::tiles::{TabPane, TileData, TreeBehavior, UniversePane};
+use crate::app::messages::{CharacterSync, MapSync, MapTints, Message, SettingsPage, Target, Type};
+use crate::app::tiles::{ConstellationPane, TabPane, TileData, TreeBehavior, UniversePane};
 use chrono::Utc;
 use data::AppData;
 use eframe::egui::{
@@ -12,10 +12,18 @@ use egui_map::map::objects::*;
 use egui_tiles::{Tiles, Tree};
 use notify::{Config, RecommendedWatcher, RecursiveMode, Watcher};
 use regex::RegexBuilder;
-use sde::{SdeManager, objects::Universe};
-use settings::Manager;
+use sde::cache::{MapData, SdeCache};
+use sde::chokepoint::{ChokepointAnalysis, GraphScope};
+use sde::search::{SearchIndex, SearchKind};
+use sde::wormhole::{KNOWN_SPACE_REGIONS, WORMHOLE_REGIONS};
+use sde::{
+    SdeManager,
+    objects::{CONSTELLATIONS, Universe},
+};
+use settings::{Manager, MapColoring};
 use std::thread;
 use std::{
+    collections::HashMap,
     fs::File,
     io::{Read, Seek, SeekFrom},
     path::Path,
@@ -25,6 +33,7 @@ use tokio::sync::broadcast::{self, Receiver as BCReceiver, Sender as BCSender};
 use tokio::sync::mpsc::{self, Receiver, Sender, error::TryRecvError};
 use tokio::time::{Duration, sleep};
 use webb::esi::EsiManager;
+use webb::objects::{JumpBridge, SovereigntyMap};
 
 use self::messages::{AuthSpawner, MessageSpawner};
 use self::tiles::RegionPane;
@@ -59,7 +68,26 @@ pub struct TelescopeApp {
     emit_notification: bool,
     search_selected_row: Option<usize>,
     search_results: Vec<(usize, String, usize, String)>,
-    universe: Universe,
+    universe: Arc<Universe>,
+    // set when the SDE database is missing, outdated or can not be read
+    sde_error: Option<String>,
+    // Universe and maps stored on disk, valid while the SDE database does not change
+    cache: SdeCache,
+    // jump bridges defined by the player, with the text boxes used to import them
+    bridges: Vec<JumpBridge>,
+    // the bridges that have not expired as pairs of Solar Systems, kept apart from the
+    // Universe so the panes can share it without copying the whole graph
+    bridge_links: Arc<Vec<(u32, u32)>>,
+    bridge_paste: String,
+    bridge_expiry: String,
+    // owners of the null-sec Solar Systems and the map colors of the coloring mode
+    sovereignty: SovereigntyMap,
+    tints: Arc<MapTints>,
+    // chokepoints of the known space, computed in the background the first time they
+    // are needed
+    chokepoints: Option<Arc<ChokepointAnalysis>>,
+    computing_chokepoints: bool,
+    search_index: Arc<SearchIndex>,
     selected_settings_page: SettingsPage,
     tree: Option<Tree<Box<dyn TabPane>>>,
 
@@ -70,6 +98,16 @@ pub struct TelescopeApp {
     watcher: RecommendedWatcher,
 }
 
+/// Number of ignored lines of a jump bridge paste shown in the notification
+const REJECTED_LINES_SHOWN: usize = 3;
+
+/// Changes requested from the Jump Bridges settings page
+enum BridgeAction {
+    Import,
+    Remove((i32, i32)),
+    Clear,
+}
+
 impl Default for TelescopeApp {
     fn default() -> Self {
         #[cfg(feature = "puffin")]
@@ -82,7 +120,7 @@ impl Default for TelescopeApp {
         let (mtx, mrx) = broadcast::channel::<messages::MapSync>(30);
 
         let app_data = AppData::new();
-        let esi = webb::esi::EsiManager::new(
+        let mut esi = webb::esi::EsiManager::new(
             app_data.user_agent.as_str(),
             app_data.client_id,
             app_data.secret_key,
@@ -92,12 +130,70 @@ impl Default for TelescopeApp {
         );
 
         let mut sde = SdeManager::new(Path::new(&settings.paths.sde_db), settings.factor);
-        let _ = sde.get_universe();
+        let cache_path = Path::new(&settings.paths.cache);
+        let mut cache = SdeCache::new([0; 32]);
+        let mut sde_error = None;
+        let mut cache_error = None;
+        match sde.check_database().and_then(|_| sde.get_database_hash()) {
+            Ok(sde_hash) => {
+                if let Some((t_cache, universe)) = SdeCache::load(cache_path, &sde_hash) {
+                    cache = t_cache;
+                    sde.universe = Arc::new(universe);
+                } else {
+                    cache = SdeCache::new(sde_hash);
+                    match sde.get_universe() {
+                        Ok(_) => {
+                            // a cache that can not be written only makes the next start slower
+                            cache_error = cache.save(cache_path, &sde.universe).err();
+                        }
+                        Err(t_error) => sde_error = Some(t_error.to_string()),
+                    }
+                }
+            }
+            Err(t_error) => sde_error = Some(t_error.to_string()),
+        }
+        // the jump bridges and the sovereignty are left out when the player database
+        // could not be updated
+        let player_data = esi.database_error.is_none();
+        let bridges = if player_data {
+            esi.read_bridges().unwrap_or_default()
+        } else {
+            Vec::new()
+        };
+        let bridge_links = Arc::new(Self::bridge_pairs(&bridges));
+        let search_index = Arc::new(sde.get_search_index());
+        let sovereignty = if player_data {
+            esi.read_sovereignty().unwrap_or_default()
+        } else {
+            SovereigntyMap::default()
+        };
+        let tints = Arc::new(Self::map_tints(
+            settings.mapping.coloring,
+            &sde.universe,
+            &sovereignty,
+            None,
+        ));
 
         let arc_map_sender = Arc::new(mtx);
         let arc_msg_sender = Arc::new(gtx);
         let msgmon = Arc::new(MessageSpawner::new(Arc::clone(&arc_msg_sender)));
         let authmon = AuthSpawner::new(Arc::clone(&arc_msg_sender));
+        if let Some(t_error) = &esi.database_error {
+            msgmon.spawn(Message::GenericNotification((
+                Type::Error,
+                String::from("EsiManager"),
+                String::from("migrate_database"),
+                String::from("Jump bridges and sovereignty disabled: ") + t_error,
+            )));
+        }
+        if let Some(t_error) = cache_error {
+            msgmon.spawn(Message::GenericNotification((
+                Type::Warning,
+                String::from("SdeCache"),
+                String::from("save"),
+                t_error.to_string(),
+            )));
+        }
 
         let intel_event_handler = IntelEventHandler::new(
             settings.channels.monitored.clone(),
@@ -124,14 +220,21 @@ impl Default for TelescopeApp {
             search_text: String::new(),
             search_selected_row: None,
             emit_notification: false,
-            behavior: TreeBehavior::new(
-                Arc::clone(&msgmon),
-                settings.factor,
-                settings.paths.sde_db.clone(),
-            ),
+            behavior: TreeBehavior::new(Arc::clone(&msgmon), Arc::clone(&search_index)),
             search_results: Vec::new(),
             tree: None,
             universe: sde.universe,
+            sde_error,
+            cache,
+            bridges,
+            bridge_links,
+            bridge_paste: String::new(),
+            bridge_expiry: String::new(),
+            sovereignty,
+            tints,
+            chokepoints: None,
+            computing_chokepoints: false,
+            search_index,
             selected_settings_page: SettingsPage::Intelligence,
             task_msg: msgmon,
             task_auth: authmon,
@@ -168,6 +271,17 @@ impl eframe::App for TelescopeApp {
             search_results: _,
             tree: _,
             universe: _,
+            sde_error: _,
+            cache: _,
+            bridges: _,
+            bridge_links: _,
+            bridge_paste: _,
+            bridge_expiry: _,
+            sovereignty: _,
+            tints: _,
+            chokepoints: _,
+            computing_chokepoints: _,
+            search_index: _,
             selected_settings_page: _,
             behavior: _,
             task_msg: _,
@@ -194,7 +308,7 @@ impl eframe::App for TelescopeApp {
                 .regions
                 .keys()
                 .copied()
-                .filter(|val| val < &11000000)
+                .filter(|val| KNOWN_SPACE_REGIONS.contains(val) || WORMHOLE_REGIONS.contains(val))
                 .collect();
 
             for key in &regions {
@@ -225,6 +339,10 @@ impl eframe::App for TelescopeApp {
                 self.start_watchdog(ids);
             }
 
+            if self.settings.mapping.coloring == MapColoring::Chokepoints {
+                self.compute_chokepoints();
+            }
+
             self.initialized = true;
         }
 
@@ -250,6 +368,47 @@ impl eframe::App for TelescopeApp {
                         ctx.send_viewport_cmd(egui::ViewportCommand::Close);
                     }
                 });
+                ui.menu_button("Map", |ui| {
+                    let updated = match self.sovereignty.updated {
+                        Some(date) => date.format("last update: %Y.%m.%d %H:%M").to_string(),
+                        None => String::from("never updated"),
+                    };
+                    if ui
+                        .add_enabled(
+                            self.esi.database_error.is_none(),
+                            Button::new("Update sovereignty"),
+                        )
+                        .on_hover_text(updated)
+                        .clicked()
+                    {
+                        self.update_sovereignty();
+                    }
+                    ui.separator();
+                    let mut coloring = self.settings.mapping.coloring;
+                    ui.radio_value(
+                        &mut coloring,
+                        MapColoring::Sovereignty,
+                        "Color by sovereignty",
+                    );
+                    ui.radio_value(&mut coloring, MapColoring::Faction, "Color by NPC faction");
+                    ui.radio_value(
+                        &mut coloring,
+                        MapColoring::Chokepoints,
+                        "Highlight chokepoints",
+                    )
+                    .on_hover_text(
+                        "Red systems cut the stargate graph in two, \
+                         yellow to orange ones are on many routes",
+                    );
+                    if coloring != self.settings.mapping.coloring {
+                        self.settings.mapping.coloring = coloring;
+                        self.settings.save();
+                        if coloring == MapColoring::Chokepoints {
+                            self.compute_chokepoints();
+                        }
+                        self.refresh_tints();
+                    }
+                });
                 ui.menu_button("Help", |ui| {
                     if ui.button("About Telescope").clicked() {
                         self.open[0] = true;
@@ -279,6 +438,10 @@ impl eframe::App for TelescopeApp {
             self.open_settings_window(ctx);
         }
 
+        if self.sde_error.is_some() {
+            self.open_sde_error_window(ctx);
+        }
+
         egui::CentralPanel::default().show(ctx, |ui| {
             #[cfg(feature = "puffin")]
             puffin::profile_scope!("inserting map");
@@ -332,6 +495,9 @@ impl TelescopeApp {
                 Message::GenericNotification(message) => self.update_status_with_error(message),
                 Message::MapHidden(region_id) => self.hide_abstract_map(region_id),
                 Message::NewRegionalPane(region_id) => self.create_new_regional_pane(region_id),
+                Message::NewConstellationPane(constellation_id) => {
+                    self.create_new_constellation_pane(constellation_id)
+                }
                 Message::MapShown(region_id) => self.show_abstract_map(region_id),
                 Message::PlayerNewLocation((player_id, solar_system_id)) => {
                     self.update_player_location(player_id, solar_system_id)
@@ -339,6 +505,12 @@ impl TelescopeApp {
                 Message::IntelFileChanged(file_name) => {
                     self.load_intel_file(file_name);
                 }
+                Message::SovereigntyUpdated(sovereignty) => self.set_sovereignty(sovereignty),
+                Message::ChokepointsComputed(chokepoints) => {
+                    self.computing_chokepoints = false;
+                    self.chokepoints = Some(chokepoints);
+                    self.refresh_tints();
+                }
             };
         }
     }
@@ -371,10 +543,42 @@ impl TelescopeApp {
             });
     }
 
+    fn open_sde_error_window(&mut self, ctx: &egui::Context) {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let mut open = true;
+        egui::Window::new("Static Data Export")
+            .fixed_size((400.0, 100.0))
+            .collapsible(false)
+            .open(&mut open)
+            .show(ctx, |ui| {
+                ui.label(
+                    RichText::new("⚠ Your SDE database is outdated or missing")
+                        .strong()
+                        .color(Color32::KHAKI),
+                );
+                if let Some(t_error) = &self.sde_error {
+                    ui.label(t_error);
+                }
+                ui.label(
+                    "Maps can not be shown until a valid sde.db is set in the preferences \
+                    or built with the sde-import tool.",
+                );
+                if ui.button("Preferences").clicked() {
+                    self.open[2] = true;
+                }
+            });
+        if !open {
+            self.sde_error = None;
+        }
+    }
+
     fn open_settings_window(&mut self, ctx: &egui::Context) {
         #[cfg(feature = "puffin")]
         puffin::profile_function!();
 
+        let mut bridge_action = None;
         egui::Window::new("Settings")
         .movable(true)
         .resizable(false)
@@ -385,7 +589,7 @@ impl TelescopeApp {
             ui.horizontal(|ui|{
                 ui.vertical(|ui|{
                     let row_height = 25.0;
-                    let labels = ["Intelligence","Data Sources"];
+                    let labels = ["Intelligence","Data Sources","Jump Bridges"];
                     ui.push_id("settings_menu", |ui|{
                         TableBuilder::new(ui)
                         .column(Column::resizable(Column::exact(150.0),false))
@@ -397,6 +601,7 @@ impl TelescopeApp {
                                 let current_page = match row.index(){
                                     0 => SettingsPage::Intelligence,
                                     1 => SettingsPage::DataSources,
+                                    2 => SettingsPage::JumpBridges,
                                     _ => SettingsPage::DataSources,
                                 };
                                 row.col(|ui: &mut egui::Ui|{
@@ -423,7 +628,9 @@ impl TelescopeApp {
                                         .behavior
                                         .tile_data
                                         .keys()
-                                        .copied().collect();
+                                        .copied()
+                                        .filter(|key| !CONSTELLATIONS.contains(&(*key as u32)))
+                                        .collect();
                                     keys.sort_unstable();
                                     let num_rows = keys.len().div_ceil(3);
                                     ui.label(RichText::new("Alerts").font(FontId::proportional(20.0)));
@@ -671,6 +878,56 @@ impl TelescopeApp {
                                         }
                                     });
                                 },
+                                SettingsPage::JumpBridges => {
+                                    ui.label(RichText::new("Jump bridges").font(FontId::proportional(20.0)));
+                                    ui.label("Paste the bridges of your alliance, one per line. They count as jumps for the intel alert area and are drawn as dashed lines on the maps.");
+                                    ui.add(egui::TextEdit::multiline(&mut self.bridge_paste)
+                                        .desired_rows(6)
+                                        .desired_width(470.0)
+                                        .hint_text("1DQ1-A » 8WA-Z6 - Label"));
+                                    ui.horizontal(|ui|{
+                                        ui.label("Expires in (hours):");
+                                        ui.add(egui::TextEdit::singleline(&mut self.bridge_expiry).desired_width(50.0));
+                                        if ui.add_enabled(self.esi.database_error.is_none(), Button::new("Import")).clicked() {
+                                            bridge_action = Some(BridgeAction::Import);
+                                        }
+                                        if ui.add_enabled(!self.bridges.is_empty(), Button::new("✖ Remove all")).clicked() {
+                                            bridge_action = Some(BridgeAction::Clear);
+                                        }
+                                    });
+                                    ui.push_id("bridge_table",|ui|{
+                                        TableBuilder::new(ui)
+                                        .column(Column::exact(250.0))
+                                        .column(Column::exact(170.0))
+                                        .column(Column::exact(40.0))
+                                        .striped(true)
+                                        .vscroll(true)
+                                        .max_scroll_height(250.0)
+                                        .body(|body| {
+                                            body.rows(18.0, self.bridges.len(), |mut row| {
+                                                let bridge = &self.bridges[row.index()];
+                                                let name = |id: i32| match self.universe.solar_systems.get(&(id as u32)) {
+                                                    Some(system) => system.name.clone(),
+                                                    None => id.to_string(),
+                                                };
+                                                row.col(|ui|{
+                                                    ui.label(name(bridge.system_a) + " » " + &name(bridge.system_b));
+                                                });
+                                                row.col(|ui|{
+                                                    match bridge.expiration {
+                                                        Some(expiration) => ui.label(expiration.format("%Y.%m.%d %H:%M").to_string()),
+                                                        None => ui.label(bridge.label.as_str()),
+                                                    };
+                                                });
+                                                row.col(|ui|{
+                                                    if ui.button("✖").clicked() {
+                                                        bridge_action = Some(BridgeAction::Remove((bridge.system_a, bridge.system_b)));
+                                                    }
+                                                });
+                                            });
+                                        });
+                                    });
+                                },
                             }
                         });
                     });
@@ -708,6 +965,98 @@ impl TelescopeApp {
                 }
             });
         });
+        if let Some(action) = bridge_action {
+            self.update_bridges(action);
+        }
+    }
+
+    /// Jump bridges that have not expired as pairs of Solar System identifiers, the lowest
+    /// first, the way the SDE and the maps use them
+    fn bridge_pairs(bridges: &[JumpBridge]) -> Vec<(u32, u32)> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let now = Utc::now();
+        let mut pairs: Vec<(u32, u32)> = bridges
+            .iter()
+            .filter(|bridge| !bridge.is_expired(now))
+            .map(|bridge| {
+                let (system_a, system_b) = (bridge.system_a as u32, bridge.system_b as u32);
+                (system_a.min(system_b), system_a.max(system_b))
+            })
+            .collect();
+        pairs.sort_unstable();
+        pairs.dedup();
+        pairs
+    }
+
+    /// Applies a change made on the Jump Bridges settings page to the player database,
+    /// then updates the open maps
+    fn update_bridges(&mut self, action: BridgeAction) {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let result = match action {
+            BridgeAction::Import => {
+                let names: HashMap<String, i32> = self
+                    .universe
+                    .solar_systems
+                    .values()
+                    .map(|system| (system.name.to_lowercase(), system.id as i32))
+                    .collect();
+                let expiration = self
+                    .bridge_expiry
+                    .trim()
+                    .parse::<i64>()
+                    .ok()
+                    .map(|hours| Utc::now() + chrono::Duration::hours(hours));
+                let (bridges, rejected) = JumpBridge::from_paste(
+                    &self.bridge_paste,
+                    |name| names.get(&name.to_lowercase()).copied(),
+                    expiration,
+                );
+                // a single notification, the UI thread is the only reader of the channel
+                if !rejected.is_empty() {
+                    let mut text = rejected.len().to_string() + " lines ignored: ";
+                    text += &rejected
+                        .iter()
+                        .take(REJECTED_LINES_SHOWN)
+                        .map(String::as_str)
+                        .collect::<Vec<&str>>()
+                        .join(" | ");
+                    if rejected.len() > REJECTED_LINES_SHOWN {
+                        text += " | …";
+                    }
+                    self.task_msg.spawn(Message::GenericNotification((
+                        Type::Warning,
+                        String::from("JumpBridge"),
+                        String::from("from_paste"),
+                        text,
+                    )));
+                }
+                self.bridge_paste.clear();
+                self.esi.write_bridges(&bridges)
+            }
+            BridgeAction::Remove(pair) => self.esi.remove_bridges(vec![pair]),
+            BridgeAction::Clear => self.esi.clear_bridges(),
+        };
+        match result.and_then(|_| self.esi.read_bridges()) {
+            Ok(bridges) => self.bridges = bridges,
+            Err(t_error) => {
+                self.task_msg.spawn(Message::GenericNotification((
+                    Type::Error,
+                    String::from("EsiManager"),
+                    String::from("update_bridges"),
+                    t_error.to_string(),
+                )));
+                return;
+            }
+        }
+        self.bridge_links = Arc::new(Self::bridge_pairs(&self.bridges));
+        let _result = self
+            .map_msg
+            .0
+            .send(MapSync::BridgesChanged(Arc::clone(&self.bridge_links)));
     }
 
     fn load_intel_file(&mut self, file_name: String) {
@@ -755,10 +1104,47 @@ impl TelescopeApp {
                 .case_insensitive(true)
                 .build()
         {
+            // the alert area are the systems around every linked character
+            let radius = self.settings.mapping.warning_area.parse().unwrap_or(4);
+            let centres: Vec<u32> = self
+                .esi
+                .characters
+                .iter()
+                .filter(|character| character.location > 0)
+                .map(|character| character.location as u32)
+                .collect();
+            let warning_area: Vec<(u32, String, usize)> = self
+                .universe
+                .get_neighbourhood(&centres, radius, &self.bridge_links)
+                .into_iter()
+                .filter_map(|(id, jumps)| {
+                    self.universe
+                        .solar_systems
+                        .get(&id)
+                        .map(|system| (id, system.name.to_lowercase(), jumps))
+                })
+                .collect();
             data.lines()
                 .filter(|line| set.is_match(line))
                 .for_each(|intel_line| {
-                    let data_x = intel_line.to_string().clone();
+                    let mut data_x = intel_line.to_string().clone();
+                    let mut notification_type = Type::Info;
+                    let text = set
+                        .captures(intel_line)
+                        .and_then(|captures| captures.get(3))
+                        .map_or(String::new(), |text| text.as_str().to_lowercase());
+                    if let Some((id, _, jumps)) = warning_area
+                        .iter()
+                        .filter(|(_, name, _)| Self::mentions(&text, name))
+                        .min_by_key(|(_, _, jumps)| *jumps)
+                    {
+                        notification_type = Type::Warning;
+                        data_x = format!("{} jumps away: {}", jumps, data_x);
+                        let _result = self.map_msg.0.send(MapSync::SystemNotification((
+                            *id as usize,
+                            tokio::time::Instant::now(),
+                        )));
+                    }
                     let runtime = tokio::runtime::Builder::new_current_thread()
                         .enable_all()
                         .build()
@@ -771,7 +1157,7 @@ impl TelescopeApp {
 
                             let _ = app_msg_tx
                                 .send(Message::GenericNotification((
-                                    Type::Info,
+                                    notification_type,
                                     String::from("TelescopeApp"),
                                     String::from("parse_intel_data"),
                                     data_x,
@@ -786,6 +1172,20 @@ impl TelescopeApp {
         }
     }
 
+    /// Checks if a lowercase intel text names a Solar System as a whole word, as system
+    /// names like `1DQ1-A` carry dashes and some others are made of several words
+    fn mentions(text: &str, name: &str) -> bool {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let is_word = |c: char| c.is_alphanumeric() || c == '-';
+        text.match_indices(name).any(|(start, _)| {
+            let end = start + name.len();
+            !text[..start].chars().next_back().is_some_and(is_word)
+                && !text[end..].chars().next().is_some_and(is_word)
+        })
+    }
+
     fn update_character_into_database(&mut self, response_data: (String, String)) {
         #[cfg(feature = "puffin")]
         puffin::profile_function!();
@@ -899,13 +1299,8 @@ impl TelescopeApp {
         #[cfg(feature = "puffin")]
         puffin::profile_function!();
 
-        let pane = Self::generate_pane(
-            self.map_msg.0.subscribe(),
-            self.settings.paths.sde_db.clone(),
-            self.settings.region_factor,
-            Some(region_id),
-            Arc::clone(&self.task_msg),
-        );
+        let data = self.get_map_data(Some(region_id));
+        let pane = self.generate_pane(Some(region_id), data);
         let tile_id = self.tree.as_mut().unwrap().tiles.insert_pane(pane);
         let root = self.tree.as_ref().unwrap().root.unwrap();
         let counter = self.tree.as_ref().unwrap().tiles.len();
@@ -919,6 +1314,53 @@ impl TelescopeApp {
         });
     }
 
+    fn create_new_constellation_pane(&mut self, constellation_id: usize) {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let Some(constellation) = self.universe.constellations.get(&(constellation_id as u32))
+        else {
+            return;
+        };
+        let name = constellation.name.clone();
+        let sde = SdeManager::new(
+            Path::new(&self.settings.paths.sde_db),
+            self.settings.region_factor,
+        );
+        let data = match sde.get_constellation_map(constellation_id as u32) {
+            Ok(data) => data,
+            Err(t_error) => {
+                self.task_msg.spawn(Message::GenericNotification((
+                    Type::Error,
+                    String::from("SdeManager"),
+                    String::from("get_constellation_map"),
+                    t_error.to_string(),
+                )));
+                return;
+            }
+        };
+        let pane: Box<dyn TabPane> = Box::new(ConstellationPane::new(
+            self.map_msg.0.subscribe(),
+            name.clone(),
+            data,
+            Arc::clone(&self.task_msg),
+        ));
+        let tile_id = self.tree.as_mut().unwrap().tiles.insert_pane(pane);
+        let root = self.tree.as_ref().unwrap().root.unwrap();
+        let counter = self.tree.as_ref().unwrap().tiles.len();
+        self.tree
+            .as_mut()
+            .unwrap()
+            .move_tile_to_container(tile_id, root, counter, false);
+        let data = self
+            .behavior
+            .tile_data
+            .entry(constellation_id)
+            .or_insert(TileData::new(name, false));
+        data.set_visible(true);
+        data.set_tile_id(Some(tile_id));
+    }
+
     fn show_abstract_map(&mut self, region_id: usize) {
         #[cfg(feature = "puffin")]
         puffin::profile_function!();
@@ -967,24 +1409,108 @@ impl TelescopeApp {
         app
     }
 
-    fn generate_pane(
-        receiver: BCReceiver<MapSync>,
-        path: String,
-        factor: i64,
-        region_id: Option<usize>,
-        task_msg: Arc<MessageSpawner>,
-    ) -> Box<dyn TabPane> {
+    fn generate_pane(&self, region_id: Option<usize>, data: MapData) -> Box<dyn TabPane> {
         #[cfg(feature = "puffin")]
         puffin::profile_function!();
 
+        let receiver = self.map_msg.0.subscribe();
+        let universe = Arc::clone(&self.universe);
+        let bridges = Arc::clone(&self.bridge_links);
+        let task_msg = Arc::clone(&self.task_msg);
         let pane: Box<dyn TabPane> = if let Some(region) = region_id {
-            Box::new(RegionPane::new(receiver, path, factor, region, task_msg))
+            let mut pane = RegionPane::new(receiver, universe, region, data, &bridges, task_msg);
+            pane.set_tints(Arc::clone(&self.tints));
+            Box::new(pane)
         } else {
-            Box::new(UniversePane::new(receiver, path, factor, task_msg))
+            let mut pane = UniversePane::new(
+                receiver,
+                self.settings.paths.sde_db.clone(),
+                self.settings.factor,
+                universe,
+                data,
+                &bridges,
+                task_msg,
+            );
+            pane.set_tints(Arc::clone(&self.tints));
+            Box::new(pane)
         };
         pane
     }
 
+    /// Returns the map data of a region, or of the universe when `region_id` is None.
+    /// The data is read from the cache, when it is not there it is read from the SDE
+    /// database and the cache is saved with it.
+    fn get_map_data(&mut self, region_id: Option<usize>) -> MapData {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let factor = if region_id.is_some() {
+            self.settings.region_factor
+        } else {
+            self.settings.factor
+        };
+        let cached = match region_id {
+            Some(region) => self.cache.get_region_map(region as u32, factor),
+            None => self.cache.get_universe_map(factor),
+        };
+        if let Some(data) = cached {
+            return data.clone();
+        }
+
+        let sde = SdeManager::new(Path::new(&self.settings.paths.sde_db), factor);
+        let result = match region_id {
+            Some(region) => sde.get_region_map(region as u32),
+            None => sde.get_universe_map(),
+        };
+        match result {
+            Ok(data) => {
+                match region_id {
+                    Some(region) => {
+                        self.cache
+                            .region_maps
+                            .insert(region as u32, (factor, data.clone()));
+                    }
+                    None => self.cache.universe_map = Some((factor, data.clone())),
+                }
+                if let Err(t_error) = self
+                    .cache
+                    .save(Path::new(&self.settings.paths.cache), &self.universe)
+                {
+                    self.task_msg.spawn(Message::GenericNotification((
+                        Type::Warning,
+                        String::from("SdeCache"),
+                        String::from("save"),
+                        t_error.to_string(),
+                    )));
+                }
+                data
+            }
+            Err(t_error) => {
+                // without an abstract layout the region is drawn with its real coordinates
+                if let Some(region) = region_id
+                    && let Ok(points) = sde.get_region_systempoints(region as u32)
+                {
+                    self.task_msg.spawn(Message::GenericNotification((
+                        Type::Warning,
+                        String::from("SdeManager"),
+                        String::from("get_map_data"),
+                        t_error.to_string(),
+                    )));
+                    let mut data = MapData::new();
+                    data.points = points;
+                    return data;
+                }
+                self.task_msg.spawn(Message::GenericNotification((
+                    Type::Error,
+                    String::from("SdeManager"),
+                    String::from("get_map_data"),
+                    t_error.to_string(),
+                )));
+                MapData::new()
+            }
+        }
+    }
+
     fn hide_abstract_map(&mut self, region_id: usize) {
         #[cfg(feature = "puffin")]
         puffin::profile_function!();
@@ -1006,18 +1532,13 @@ impl TelescopeApp {
         }
     }
 
-    fn create_tree(&self) -> Tree<Box<dyn TabPane>> {
+    fn create_tree(&mut self) -> Tree<Box<dyn TabPane>> {
         #[cfg(feature = "puffin")]
         puffin::profile_function!();
 
+        let data = self.get_map_data(None);
         let mut tiles = Tiles::default();
-        let id = tiles.insert_pane(Self::generate_pane(
-            self.map_msg.0.subscribe(),
-            self.settings.paths.sde_db.clone(),
-            self.settings.factor,
-            None,
-            Arc::clone(&self.task_msg),
-        ));
+        let id = tiles.insert_pane(self.generate_pane(None, data));
         let tile_ids = vec![id];
         let root = tiles.insert_tab_tile(tile_ids);
         egui_tiles::Tree::new("maps", root, tiles)
@@ -1185,22 +1706,26 @@ impl TelescopeApp {
                     ui.label("Name: ");
                     let response = ui.text_edit_singleline(&mut self.search_text);
                     if response.changed() {
-                        if self.search_text.len() >= 3 {
-                            let sde = SdeManager::new(
-                                Path::new(&self.settings.paths.sde_db),
-                                self.settings.factor,
-                            );
-                            match sde.get_system_id(self.search_text.clone().to_lowercase()) {
-                                Ok(system_results) => self.search_results = system_results,
-                                Err(t_error) => {
-                                    self.task_msg.spawn(Message::GenericNotification((
-                                        Type::Error,
-                                        String::from("sde"),
-                                        String::from("get_system_id"),
-                                        t_error.to_string(),
-                                    )));
-                                }
-                            }
+                        if self.search_text.len() >= 2 {
+                            self.search_results = self
+                                .search_index
+                                .search(&self.search_text, Some(SearchKind::SolarSystem), 50)
+                                .into_iter()
+                                .map(|system| {
+                                    let region_name = self
+                                        .universe
+                                        .regions
+                                        .get(&system.region)
+                                        .map(|region| region.name.clone())
+                                        .unwrap_or_default();
+                                    (
+                                        system.id as usize,
+                                        system.name,
+                                        system.region as usize,
+                                        region_name,
+                                    )
+                                })
+                                .collect();
                         }
                         if self.search_text.is_empty() {
                             self.search_results.clear();
@@ -1308,7 +1833,254 @@ impl TelescopeApp {
                         });
                     //
                 });
+                if let Some(selected_row) = self.search_selected_row
+                    && let Some(result) = self.search_results.get(selected_row)
+                {
+                    ui.separator();
+                    Self::system_info_ui(ui, &self.universe, result.0 as u32);
+                }
+            });
+    }
+
+    fn system_info_ui(ui: &mut egui::Ui, universe: &Universe, system_id: u32) {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let Some(system) = universe.solar_systems.get(&system_id) else {
+            return;
+        };
+        ui.heading(system.name.clone());
+        ui.label(format!(
+            "Security: {:.1}   Planets: {}   Moons: {}",
+            system.security_status(),
+            system.planets.len(),
+            universe.get_system_moons(system_id).len()
+        ));
+        if system.is_wormhole() {
+            let mut text = String::from("Class: ") + system.wormhole_class.label().as_str();
+            if let Some(effect) = system.effect {
+                text += "   Effect: ";
+                text += effect.name();
+            }
+            ui.label(text);
+            if !system.statics.is_empty() {
+                let statics: Vec<String> = system
+                    .statics
+                    .iter()
+                    .map(|wormhole| wormhole.name.clone() + " → " + &wormhole.destination.label())
+                    .collect();
+                ui.label(String::from("Statics: ") + statics.join(", ").as_str());
+            }
+        }
+        let stations = universe.get_stations(system_id);
+        if stations.is_empty() {
+            ui.label("No NPC stations to dock");
+            return;
+        }
+        ui.strong("NPC Stations");
+        egui::ScrollArea::vertical()
+            .id_salt("system_info_stations")
+            .max_height(150.0)
+            .show(ui, |ui| {
+                for station in stations {
+                    ui.collapsing(station.name.clone(), |ui| {
+                        for service in universe.get_station_services(station.id) {
+                            ui.label(service.name.clone());
+                        }
+                    });
+                }
+            });
+    }
+
+    /// Downloads the sovereignty in the background, the maps are tinted again when it
+    /// arrives
+    fn update_sovereignty(&mut self) {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        let app_sender = Arc::clone(&self.app_msg.0);
+        let mut t_esi = self.esi.clone();
+        thread::spawn(move || {
+            runtime.block_on(async {
+                #[cfg(feature = "puffin")]
+                puffin::profile_scope!("spawned sovereignty update");
+
+                let message = match t_esi.update_sovereignty().await {
+                    Ok(sovereignty) => Message::SovereigntyUpdated(sovereignty),
+                    Err(t_error) => Message::GenericNotification((
+                        Type::Error,
+                        String::from("EsiManager"),
+                        String::from("update_sovereignty"),
+                        t_error.to_string(),
+                    )),
+                };
+                let _ = app_sender.send(message).await;
             });
+        });
+    }
+
+    fn set_sovereignty(&mut self, sovereignty: SovereigntyMap) {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        self.task_msg.spawn(Message::GenericNotification((
+            Type::Info,
+            String::from("EsiManager"),
+            String::from("update_sovereignty"),
+            sovereignty.systems.len().to_string() + " Solar Systems with sovereignty",
+        )));
+        self.sovereignty = sovereignty;
+        self.refresh_tints();
+    }
+
+    /// Computes the map colors again and sends them to the map panes
+    fn refresh_tints(&mut self) {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        self.tints = Arc::new(Self::map_tints(
+            self.settings.mapping.coloring,
+            &self.universe,
+            &self.sovereignty,
+            self.chokepoints.as_deref(),
+        ));
+        let _result = self
+            .map_msg
+            .0
+            .send(MapSync::TintsChanged(Arc::clone(&self.tints)));
+    }
+
+    /// Computes the chokepoints of the known space in another thread, it takes a while
+    fn compute_chokepoints(&mut self) {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        if self.chokepoints.is_some() || self.computing_chokepoints {
+            return;
+        }
+        self.computing_chokepoints = true;
+        let universe = Arc::clone(&self.universe);
+        let task_msg = Arc::clone(&self.task_msg);
+        thread::spawn(move || {
+            #[cfg(feature = "puffin")]
+            puffin::profile_scope!("spawned chokepoint analysis");
+
+            let chokepoints = universe.get_chokepoints(GraphScope::KnownSpace);
+            task_msg.spawn(Message::ChokepointsComputed(Arc::new(chokepoints)));
+        });
+    }
+
+    /// Map colors of the coloring mode. By sovereignty every alliance or NPC faction
+    /// gets its own color, by faction the Regions are also labeled with their owner and
+    /// by chokepoints with the number of them. The chokepoints are empty until they have
+    /// been computed.
+    fn map_tints(
+        coloring: MapColoring,
+        universe: &Universe,
+        sovereignty: &SovereigntyMap,
+        chokepoints: Option<&ChokepointAnalysis>,
+    ) -> MapTints {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let mut tints = MapTints::new();
+        match coloring {
+            MapColoring::Sovereignty => {
+                tints.systems = sovereignty
+                    .owners()
+                    .into_iter()
+                    .map(|(system_id, owner)| (system_id as usize, Self::owner_color(owner)))
+                    .collect();
+            }
+            MapColoring::Faction => {
+                tints.systems = universe
+                    .solar_systems
+                    .values()
+                    .filter_map(|system| {
+                        let faction = system.faction?;
+                        Some((system.id as usize, Self::faction_color(faction)))
+                    })
+                    .collect();
+                for region in universe.regions.values() {
+                    let Some(faction) = region.faction.and_then(|id| universe.factions.get(&id))
+                    else {
+                        continue;
+                    };
+                    tints.regions.insert(
+                        region.id,
+                        (faction.name.clone(), Self::faction_color(faction.id)),
+                    );
+                }
+            }
+            MapColoring::Chokepoints => {
+                let Some(chokepoints) = chokepoints else {
+                    return tints;
+                };
+                let cut_color = Color32::from_rgb(230, 40, 40);
+                let max = chokepoints
+                    .betweenness
+                    .values()
+                    .copied()
+                    .fold(0.0, f64::max);
+                for (system_id, value) in &chokepoints.betweenness {
+                    let ratio = if max > 0.0 { value / max } else { 0.0 };
+                    // only the busiest systems are colored, from yellow to orange
+                    if ratio >= 0.15 {
+                        let green = 210.0 - 110.0 * ratio;
+                        tints
+                            .systems
+                            .insert(*system_id as usize, Color32::from_rgb(240, green as u8, 50));
+                    }
+                }
+                let mut counts: HashMap<u32, usize> = HashMap::new();
+                for system_id in &chokepoints.articulation_points {
+                    tints.systems.insert(*system_id as usize, cut_color);
+                    if let Some(system) = universe.solar_systems.get(system_id) {
+                        *counts.entry(system.region).or_default() += 1;
+                    }
+                }
+                for (region_id, count) in counts {
+                    let label = match count {
+                        1 => String::from("1 chokepoint"),
+                        _ => count.to_string() + " chokepoints",
+                    };
+                    tints.regions.insert(region_id, (label, cut_color));
+                }
+            }
+        }
+        tints
+    }
+
+    /// Color of an NPC faction, the empires use the colors players know them by
+    fn faction_color(faction: u32) -> Color32 {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        match faction {
+            // Caldari State
+            500001 => Color32::from_rgb(70, 130, 210),
+            // Minmatar Republic
+            500002 => Color32::from_rgb(200, 60, 50),
+            // Amarr Empire
+            500003 => Color32::from_rgb(225, 180, 40),
+            // Gallente Federation
+            500004 => Color32::from_rgb(60, 170, 90),
+            _ => Self::owner_color(faction as i32),
+        }
+    }
+
+    /// Color of an alliance or faction, always the same one for the same identifier
+    fn owner_color(owner: i32) -> Color32 {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        // multiplicative hashing spreads consecutive identifiers around the color wheel
+        let hue = (owner as u32).wrapping_mul(2_654_435_761) as f32 / u32::MAX as f32;
+        egui::ecolor::Hsva::new(hue, 0.75, 0.9, 1.0).into()
     }
 
     fn update_player_location(&mut self, player_id: i32, solar_system_id: i32) {
@@ -1,5 +1,8 @@
+use eframe::egui::Color32;
 use hyper::server::conn::http1;
 use hyper_util::rt::TokioIo;
+use sde::chokepoint::ChokepointAnalysis;
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::thread;
 use std::{future::IntoFuture, net::SocketAddr};
@@ -9,12 +12,43 @@ use tokio::sync::mpsc;
 use tokio::sync::mpsc::Sender;
 use tokio::time::{Duration, Instant, timeout_at};
 use webb::auth_service::AuthService2;
+use webb::objects::SovereigntyMap;
 
 #[derive(Clone)]
 pub enum MapSync {
     CenterOn((usize, Target)),
     SystemNotification((usize, Instant)),
     PlayerMoved((usize, usize)),
+    BridgesChanged(Arc<Vec<(u32, u32)>>),
+    TintsChanged(Arc<MapTints>),
+}
+
+/// Colors of the map, by Solar System for the nodes and by Region for the labels
+pub struct MapTints {
+    pub systems: HashMap<usize, Color32>,
+    /// Name of the owner and its color, for the Regions that have a single one
+    pub regions: HashMap<u32, (String, Color32)>,
+}
+
+impl MapTints {
+    pub fn new() -> Self {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        MapTints {
+            systems: HashMap::new(),
+            regions: HashMap::new(),
+        }
+    }
+}
+
+impl Default for MapTints {
+    fn default() -> Self {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        Self::new()
+    }
 }
 
 pub enum Type {
@@ -34,16 +68,20 @@ pub enum Target {
 pub enum SettingsPage {
     Intelligence,
     DataSources,
+    JumpBridges,
 }
 
 pub enum Message {
     EsiAuthSuccess((String, String)),
     GenericNotification((Type, String, String, String)),
     NewRegionalPane(usize),
+    NewConstellationPane(usize),
     MapHidden(usize),
     MapShown(usize),
     PlayerNewLocation((i32, i32)),
     IntelFileChanged(String),
+    SovereigntyUpdated(SovereigntyMap),
+    ChokepointsComputed(Arc<ChokepointAnalysis>),
 }
 
 pub enum CharacterSync {
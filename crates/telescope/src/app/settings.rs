@@ -14,12 +14,29 @@ pub(crate) struct FilePaths {
     pub intel: Option<PathBuf>,
     pub sde_db: String,
     pub local_db: String,
+    #[serde(skip)]
+    pub cache: String,
+}
+
+/// What the colors of the map nodes show
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+pub(crate) enum MapColoring {
+    /// Alliance or NPC faction holding the null-sec sovereignty
+    #[default]
+    Sovereignty,
+    /// NPC faction owning empire space and NPC null-sec
+    Faction,
+    /// Solar Systems that split the known space stargate graph or that many routes
+    /// go through
+    Chokepoints,
 }
 
 #[derive(Serialize, Deserialize)]
 pub(crate) struct Mapping {
     pub startup_regions: Vec<usize>,
     pub warning_area: String,
+    #[serde(default)]
+    pub coloring: MapColoring,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -157,10 +174,12 @@ impl Default for Manager {
                 settings: settings_file.clone(),
                 sde_db: String::from("assets/sde.db"),
                 local_db: String::from("telescope.db"),
+                cache: String::from("telescope.cache"),
             },
             mapping: Mapping {
                 startup_regions: vec![],
                 warning_area: 4.to_string(),
+                coloring: MapColoring::default(),
             },
             factor: 50000000000000,
             region_factor: -2,
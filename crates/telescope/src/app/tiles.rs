@@ -1,7 +1,7 @@
-use crate::app::messages::{MapSync, Message, Target, Type};
+use crate::app::messages::{MapSync, MapTints, Message, Target, Type};
 use eframe::egui::{
-    self, Align2, Color32, CornerRadius, FontId, Pos2, Rect, Response, Sense, Shape, Stroke, Style,
-    TextStyle, TextWrapMode, Ui, Vec2, WidgetText, epaint::CircleShape, vec2,
+    self, Align2, Color32, CornerRadius, FontId, Pos2, Rect, Response, RichText, Sense, Shape,
+    Stroke, Style, TextStyle, TextWrapMode, Ui, Vec2, WidgetText, epaint::CircleShape, vec2,
 };
 use egui_extras::{Column, TableBuilder};
 use egui_map::map::{
@@ -13,6 +13,11 @@ use egui_map::map::{
 use egui_tiles::{Behavior, SimplificationOptions, TabState, TileId, Tiles, UiResponse};
 //use futures::executor::ThreadPool;
 use sde::SdeManager;
+use sde::cache::MapData;
+use sde::error::SdeError;
+use sde::objects::{CONSTELLATIONS, SdeLine, Universe};
+use sde::search::{SearchIndex, SearchKind};
+use std::cell::{Cell, RefCell};
 use std::collections::HashMap;
 use std::time::Instant;
 use std::{path::Path, rc::Rc, sync::Arc};
@@ -30,10 +35,16 @@ pub trait TabPane {
 
 pub struct UniversePane {
     map: Map,
+    // map data without the jump bridges, they are added on top of it
+    data: MapData,
     mapsync_reciever: Receiver<MapSync>,
     //generic_sender: Arc<Sender<Message>>,
     path: String,
     factor: i64,
+    // snapshot of the Universe shared by every pane
+    universe: Arc<Universe>,
+    // node and region colors, shared with the node template
+    tints: Rc<RefCell<Arc<MapTints>>>,
     task_msg: Arc<MessageSpawner>,
     //tpool: Rc<ThreadPool>,
 }
@@ -43,6 +54,9 @@ impl UniversePane {
         receiver: Receiver<MapSync>,
         path: String,
         factor: i64,
+        universe: Arc<Universe>,
+        data: MapData,
+        bridges: &[(u32, u32)],
         task_msg: Arc<MessageSpawner>,
     ) -> Self {
         #[cfg(feature = "puffin")]
@@ -50,47 +64,78 @@ impl UniversePane {
 
         let mut object = Self {
             map: Map::new(),
+            data,
             mapsync_reciever: receiver,
             path,
             factor,
+            universe,
+            tints: Rc::new(RefCell::new(Arc::new(MapTints::new()))),
             task_msg,
         };
-        object.generate_data(object.path.clone(), object.factor);
+        object.generate_data(bridges);
         object.map.settings = MapSettings::default();
         object.map.settings.node_text_visibility = VisibilitySetting::Hover;
         object.map.set_context_manager(Rc::new(ContextMenu::new()));
         object
+            .map
+            .set_node_template(Rc::new(PointTemplate::new(Rc::clone(&object.tints))));
+        object
     }
 
-    fn generate_data(&mut self, path: String, factor: i64) {
+    fn generate_data(&mut self, bridges: &[(u32, u32)]) {
         #[cfg(feature = "puffin")]
         puffin::profile_function!();
 
-        let t_sde = SdeManager::new(Path::new(path.as_str()), factor);
-        if let Ok(points) = t_sde.get_systempoints() {
-            //we get connections
-            if let Ok(hashmap) = t_sde.get_system_connections(points) {
-                self.map.add_hashmap_points(hashmap);
-            }
+        self.set_bridges(bridges);
+        self.set_labels();
+    }
+}
 
-            if let Ok(hash_conns) = t_sde.get_connections() {
-                self.map.add_lines(hash_conns);
-            }
-        }
-        let t_sde = SdeManager::new(Path::new(path.as_str()), factor);
-        if let Ok(region_areas) = t_sde.get_region_coordinates() {
-            let mut labels = Vec::new();
-            for region in region_areas {
-                let mut label = MapLabel::new();
-                label.text = region.name;
-                label.center = Pos2::new(
-                    (region.min.x / factor) as f32,
-                    (region.min.y / factor) as f32,
-                );
-                labels.push(label);
+impl UniversePane {
+    fn set_labels(&mut self) {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let tints = Arc::clone(&self.tints.borrow());
+        let mut labels = Vec::new();
+        for region in self.universe.get_region_areas() {
+            let mut center = SdeLine::new(region.min, region.max).midpoint();
+            if self.factor > 1 {
+                center /= self.factor;
+            } else if self.factor < -1 {
+                center *= self.factor.abs();
             }
-            self.map.add_labels(labels);
+            let [x, y] = center.to_rawpoint().components;
+            let mut label = MapLabel::new();
+            label.text = match tints.regions.get(&region.region_id) {
+                // the labels can not be colored, so they show the owner instead
+                Some((owner, _)) => region.name + "\n" + owner,
+                None => region.name,
+            };
+            label.center = Pos2::new(x, y);
+            labels.push(label);
         }
+        self.map.add_labels(labels);
+    }
+
+    fn set_bridges(&mut self, bridges: &[(u32, u32)]) {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let mut data = self.data.clone();
+        data.add_bridges(bridges);
+        self.map.add_hashmap_points(data.points);
+        self.map.add_lines(data.lines);
+    }
+
+    /// Replaces the colors of the nodes, the Solar Systems without one keep the default.
+    /// The labels of the Regions with an owner show its name.
+    pub fn set_tints(&mut self, tints: Arc<MapTints>) {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        *self.tints.borrow_mut() = tints;
+        self.set_labels();
     }
 }
 
@@ -134,6 +179,8 @@ impl TabPane for UniversePane {
                     )));
                     self.map.update_marker(player_id, location)
                 }
+                MapSync::BridgesChanged(bridges) => self.set_bridges(&bridges),
+                MapSync::TintsChanged(tints) => self.set_tints(tints),
             };
         }
     }
@@ -144,19 +191,21 @@ impl TabPane for UniversePane {
 
         match message.1 {
             Target::System => {
-                let t_sde = SdeManager::new(Path::new(&self.path), self.factor);
+                let t_sde = SdeManager::with_universe(
+                    Path::new(&self.path),
+                    self.factor,
+                    Arc::clone(&self.universe),
+                );
                 match t_sde.get_system_coords(message.0) {
-                    Ok(Some(coords)) => {
-                        self.map.set_pos(coords.try_into().unwrap());
+                    Ok(coords) => {
+                        self.map.set_pos(coords.to_rawpoint().components);
                     }
-                    Ok(None) => {
-                        let mut msg = String::from("System with Id ");
-                        msg += (message.0.to_string() + " could not be located").as_str();
+                    Err(t_error @ SdeError::SystemNotFound(_)) => {
                         self.task_msg.spawn(Message::GenericNotification((
                             Type::Warning,
                             String::from("SdeManager"),
                             String::from("get_system_coords"),
-                            msg,
+                            t_error.to_string(),
                         )));
                     }
                     Err(t_error) => {
@@ -176,20 +225,24 @@ impl TabPane for UniversePane {
 
 pub struct RegionPane {
     map: Map,
+    // map data without the jump bridges, they are added on top of it
+    data: MapData,
     mapsync_reciever: Receiver<MapSync>,
-    path: String,
-    factor: i64,
+    universe: Arc<Universe>,
     region_id: usize,
     tab_name: String,
+    // node and region colors, shared with the node template
+    tints: Rc<RefCell<Arc<MapTints>>>,
     task_msg: Arc<MessageSpawner>,
 }
 
 impl RegionPane {
     pub fn new(
         receiver: Receiver<MapSync>,
-        path: String,
-        factor: i64,
+        universe: Arc<Universe>,
         region_id: usize,
+        data: MapData,
+        bridges: &[(u32, u32)],
         task_msg: Arc<MessageSpawner>,
     ) -> Self {
         #[cfg(feature = "puffin")]
@@ -197,38 +250,38 @@ impl RegionPane {
 
         let mut object = Self {
             map: Map::new(),
+            data,
             mapsync_reciever: receiver,
-            path,
-            factor,
+            universe,
             region_id,
             tab_name: String::from("Region"),
+            tints: Rc::new(RefCell::new(Arc::new(MapTints::new()))),
             task_msg,
         };
-        object.generate_data(object.path.clone(), object.factor, object.region_id);
+        object.generate_data(bridges);
         object.map.settings = MapSettings::default();
         object.map.settings.node_text_visibility = VisibilitySetting::Hover;
         object.map.set_context_manager(Rc::new(ContextMenu::new()));
-        object.map.set_node_template(Rc::new(Template::new()));
+        object
+            .map
+            .set_node_template(Rc::new(Template::with_tints(Rc::clone(&object.tints))));
         object
     }
 
-    fn generate_data(&mut self, path: String, factor: i64, region_id: usize) {
+    fn generate_data(&mut self, bridges: &[(u32, u32)]) {
         #[cfg(feature = "puffin")]
         puffin::profile_function!();
 
-        let t_sde = SdeManager::new(Path::new(path.as_str()), factor);
+        self.set_bridges(bridges);
 
-        match t_sde.get_abstract_systems(vec![region_id as u32]) {
-            Ok(points) => {
-                if let Ok(points) =
-                    t_sde.get_abstract_system_connections(points, vec![region_id as u32])
-                {
-                    self.map.add_hashmap_points(points);
-                }
-                if let Ok(lines) = t_sde.get_abstract_connections(vec![region_id as u32]) {
-                    self.map.add_lines(lines);
-                }
-            }
+        let t_region_id = self.region_id as u32;
+        match self
+            .universe
+            .regions
+            .get(&t_region_id)
+            .ok_or(SdeError::RegionNotFound(t_region_id))
+        {
+            Ok(region) => self.tab_name = region.name.clone(),
             Err(t_err) => {
                 self.task_msg.spawn(Message::GenericNotification((
                     Type::Error,
@@ -236,14 +289,28 @@ impl RegionPane {
                     "generate_data".to_string(),
                     t_err.to_string(),
                 )));
-                return;
             }
         }
-        let t_region_id = self.region_id;
-        let region = t_sde.get_region(vec![t_region_id as u32], None).unwrap();
-        let keys: Vec<u32> = region.keys().copied().collect();
-        self.tab_name
-            .clone_from(&region.get(&keys[0]).unwrap().name);
+    }
+}
+
+impl RegionPane {
+    fn set_bridges(&mut self, bridges: &[(u32, u32)]) {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let mut data = self.data.clone();
+        data.add_bridges(bridges);
+        self.map.add_hashmap_points(data.points);
+        self.map.add_lines(data.lines);
+    }
+
+    /// Replaces the colors of the nodes, the Solar Systems without one keep the default
+    pub fn set_tints(&mut self, tints: Arc<MapTints>) {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        *self.tints.borrow_mut() = tints;
     }
 }
 
@@ -271,6 +338,104 @@ impl TabPane for RegionPane {
                     )));
                     self.map.update_marker(player_id, location)
                 }
+                MapSync::BridgesChanged(bridges) => self.set_bridges(&bridges),
+                MapSync::TintsChanged(tints) => self.set_tints(tints),
+            };
+        }
+    }
+
+    fn get_title(&self) -> WidgetText {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        match self.tints.borrow().regions.get(&(self.region_id as u32)) {
+            Some((_, color)) => RichText::new(self.tab_name.clone()).color(*color).into(),
+            None => self.tab_name.clone().into(),
+        }
+    }
+
+    fn ui(&mut self, ui: &mut Ui) -> UiResponse {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        self.event_manager();
+        ui.add(&mut self.map);
+        UiResponse::None
+    }
+
+    fn center_on_target(&mut self, message: (usize, Target)) {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        match message.1 {
+            Target::System => {
+                self.map.set_pos_from_nodeid(message.0);
+            }
+            Target::Region => {}
+        }
+    }
+}
+
+pub struct ConstellationPane {
+    map: Map,
+    mapsync_reciever: Receiver<MapSync>,
+    tab_name: String,
+    task_msg: Arc<MessageSpawner>,
+}
+
+impl ConstellationPane {
+    pub fn new(
+        receiver: Receiver<MapSync>,
+        tab_name: String,
+        data: MapData,
+        task_msg: Arc<MessageSpawner>,
+    ) -> Self {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let mut object = Self {
+            map: Map::new(),
+            mapsync_reciever: receiver,
+            tab_name,
+            task_msg,
+        };
+        object.map.add_hashmap_points(data.points);
+        object.map.add_lines(data.lines);
+        object.map.settings = MapSettings::default();
+        object.map.settings.node_text_visibility = VisibilitySetting::Hover;
+        object.map.set_context_manager(Rc::new(ContextMenu::new()));
+        object.map.set_node_template(Rc::new(Template::new()));
+        object
+    }
+}
+
+impl TabPane for ConstellationPane {
+    fn event_manager(&mut self) {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let received_data = self.mapsync_reciever.try_recv();
+        if let Ok(msg) = received_data {
+            match msg {
+                MapSync::SystemNotification((system_id, time)) => {
+                    let _result = self.map.notify(system_id, time.into());
+                }
+                MapSync::CenterOn(message) => {
+                    let t_msg = message.clone();
+                    self.center_on_target(t_msg);
+                }
+                MapSync::PlayerMoved((player_id, location)) => {
+                    self.task_msg.spawn(Message::GenericNotification((
+                        Type::Debug,
+                        String::from("ConstellationPane"),
+                        String::from("event_manager"),
+                        self.tab_name.clone() + " - Player moved RCV",
+                    )));
+                    self.map.update_marker(player_id, location)
+                }
+                // the constellation map does not show jump bridges
+                MapSync::BridgesChanged(_) => {}
+                MapSync::TintsChanged(_) => {}
             };
         }
     }
@@ -366,14 +531,14 @@ pub struct TreeBehavior {
     gap_width: f32,
     task_msg: Arc<MessageSpawner>,
     search_text: String,
-    factor: i64,
-    path: String,
+    search_index: Arc<SearchIndex>,
     search_regions: Vec<usize>,
+    search_constellations: Vec<(usize, String)>,
     pub tile_data: HashMap<usize, TileData>,
 }
 
 impl TreeBehavior {
-    pub fn new(task_msg: Arc<MessageSpawner>, factor: i64, path: String) -> Self {
+    pub fn new(task_msg: Arc<MessageSpawner>, search_index: Arc<SearchIndex>) -> Self {
         #[cfg(feature = "puffin")]
         puffin::profile_function!();
 
@@ -389,11 +554,11 @@ impl TreeBehavior {
             tab_bar_height: 24.0,
             gap_width: 2.0,
             task_msg,
-            factor,
-            path,
+            search_index,
             search_text: String::new(),
             tile_data: HashMap::new(),
             search_regions: Vec::new(),
+            search_constellations: Vec::new(),
         }
     }
 
@@ -407,6 +572,9 @@ impl TreeBehavior {
         if visible {
             if tile_id.is_some() {
                 self.task_msg.spawn(Message::MapShown(region_id));
+            } else if CONSTELLATIONS.contains(&(region_id as u32)) {
+                self.task_msg
+                    .spawn(Message::NewConstellationPane(region_id));
             } else {
                 self.task_msg.spawn(Message::NewRegionalPane(region_id));
             }
@@ -567,14 +735,19 @@ impl Behavior<Box<dyn TabPane>> for TreeBehavior {
             let mut _data: Vec<usize> = Vec::new();
             ui.label("Search region:");
             if ui.text_edit_singleline(&mut self.search_text).changed() {
-                self.search_regions.clear();
-                if self.search_text.len() > 3 {
-                    let t_sde = SdeManager::new(Path::new(&self.path), self.factor);
-                    let regions = t_sde
-                        .get_region(vec![], Some(self.search_text.clone()))
-                        .unwrap();
-                    self.search_regions = regions.keys().copied().map(|x| x as usize).collect();
-                }
+                self.search_regions = self
+                    .search_index
+                    .search(&self.search_text, Some(SearchKind::Region), usize::MAX)
+                    .into_iter()
+                    .map(|region| region.id as usize)
+                    .filter(|region_id| self.tile_data.contains_key(region_id))
+                    .collect();
+                self.search_constellations = self
+                    .search_index
+                    .search(&self.search_text, Some(SearchKind::Constellation), 10)
+                    .into_iter()
+                    .map(|constellation| (constellation.id as usize, constellation.name))
+                    .collect();
             }
 
             if self.search_regions.is_empty() {
@@ -613,6 +786,24 @@ impl Behavior<Box<dyn TabPane>> for TreeBehavior {
                         });
                     });
                 });
+            if !self.search_text.is_empty() && !self.search_constellations.is_empty() {
+                ui.separator();
+                ui.label("Open constellation:");
+                for (constellation_id, name) in self.search_constellations.clone() {
+                    if ui.button(name).clicked() {
+                        match self.tile_data.get(&constellation_id) {
+                            Some(data) if data.get_tile_id().is_some() => {
+                                self.task_msg.spawn(Message::MapShown(constellation_id));
+                            }
+                            _ => {
+                                self.task_msg
+                                    .spawn(Message::NewConstellationPane(constellation_id));
+                            }
+                        }
+                        ui.close();
+                    }
+                }
+            }
         });
     }
 
@@ -667,14 +858,24 @@ impl ContextMenuManager for ContextMenu {
     }
 }
 
-struct Template {}
+struct Template {
+    // background colors of the nodes by Solar System
+    tints: Rc<RefCell<Arc<MapTints>>>,
+}
 
 impl Template {
     fn new() -> Self {
         #[cfg(feature = "puffin")]
         puffin::profile_function!();
 
-        Self {}
+        Self::with_tints(Rc::new(RefCell::new(Arc::new(MapTints::new()))))
+    }
+
+    fn with_tints(tints: Rc<RefCell<Arc<MapTints>>>) -> Self {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        Self { tints }
     }
 }
 
@@ -702,6 +903,14 @@ impl NodeTemplate for Template {
             CornerRadius::same((10.0 * zoom).round() as u8),
             colors.0,
         ));
+        if let Some(tint) = self.tints.borrow().systems.get(&system.get_id()) {
+            // translucent, so the name is still readable in both themes
+            shapes.push(Shape::rect_filled(
+                rect,
+                CornerRadius::same((10.0 * zoom).round() as u8),
+                tint.gamma_multiply(0.45),
+            ));
+        }
         ui.ctx().fonts(|fonts| {
             shapes.push(Shape::text(
                 fonts,
@@ -819,3 +1028,123 @@ impl NodeTemplate for Template {
         false
     }
 }
+
+/// Node template of the universe map, it draws every Solar System as a small dot like
+/// the default one of the map, but with the color of its owner
+struct PointTemplate {
+    // colors of the nodes by Solar System
+    tints: Rc<RefCell<Arc<MapTints>>>,
+    // node under the pointer, selection_ui is called just before its node_ui
+    selected: Cell<Option<Pos2>>,
+}
+
+impl PointTemplate {
+    fn new(tints: Rc<RefCell<Arc<MapTints>>>) -> Self {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        Self {
+            tints,
+            selected: Cell::new(None),
+        }
+    }
+}
+
+impl NodeTemplate for PointTemplate {
+    fn node_ui(&self, ui: &mut Ui, viewport_point: Pos2, zoom: f32, system: &MapPoint) {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let mut shapes = Vec::new();
+        let color = match self.tints.borrow().systems.get(&system.get_id()) {
+            Some(tint) => *tint,
+            None if ui.visuals().dark_mode => Color32::GOLD,
+            None => Color32::from_rgb(216, 142, 58),
+        };
+        shapes.push(Shape::circle_filled(viewport_point, 4.0 * zoom, color));
+        if self.selected.take() == Some(viewport_point) {
+            ui.ctx().fonts(|fonts| {
+                shapes.push(Shape::text(
+                    fonts,
+                    viewport_point + vec2(3.0 * zoom, -3.0 * zoom),
+                    Align2::LEFT_BOTTOM,
+                    system.get_name(),
+                    FontId::proportional(12.0 * zoom),
+                    ui.visuals().text_color(),
+                ));
+            });
+        }
+        ui.painter().extend(shapes);
+    }
+
+    fn selection_ui(&self, ui: &mut Ui, viewport_point: Pos2, zoom: f32) {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        self.selected.set(Some(viewport_point));
+        let color = if ui.visuals().dark_mode {
+            Color32::YELLOW
+        } else {
+            Color32::KHAKI
+        };
+        ui.painter().add(Shape::Circle(CircleShape::stroke(
+            viewport_point,
+            7.0 * zoom,
+            Stroke::new(2.0 * zoom, color),
+        )));
+    }
+
+    fn marker_ui(&self, ui: &mut Ui, viewport_point: Pos2, zoom: f32) {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let color = if ui.visuals().dark_mode {
+            Color32::LIGHT_GREEN
+        } else {
+            Color32::GREEN
+        };
+        let mut transparency = (chrono::Local::now().timestamp_millis() % 2550) / 5;
+        if transparency > 255 {
+            transparency = 255 - (transparency - 255)
+        }
+        let corrected_color =
+            Color32::from_rgba_unmultiplied(color.r(), color.g(), color.b(), transparency as u8);
+        ui.painter().add(Shape::Circle(CircleShape::stroke(
+            viewport_point,
+            4.0 * zoom,
+            Stroke::new(9.0 * zoom, corrected_color),
+        )));
+        ui.ctx().request_repaint();
+    }
+
+    fn notification_ui(
+        &self,
+        ui: &mut Ui,
+        viewport_point: Pos2,
+        zoom: f32,
+        initial_time: Instant,
+        color: Color32,
+    ) -> bool {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let secs_played = Instant::now().duration_since(initial_time).as_secs_f32();
+        let mut transparency: f32 = 1.00 - (secs_played / 3.50).abs();
+        if transparency < 0.00 {
+            transparency = 0.00;
+        }
+        let corrected_color = Color32::from_rgba_unmultiplied(
+            color.r(),
+            color.g(),
+            color.b(),
+            (255.00 * transparency).round() as u8,
+        );
+        ui.painter().add(Shape::Circle(CircleShape::filled(
+            viewport_point,
+            (4.00 + (40.00 * secs_played)) * zoom,
+            corrected_color,
+        )));
+        ui.ctx().request_repaint();
+        secs_played < 3.50
+    }
+}
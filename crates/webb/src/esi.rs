@@ -1,5 +1,8 @@
 use crate::objects::AuthData;
-use crate::objects::{Alliance, Character, Corporation};
+use crate::objects::{
+    Alliance, Character, Corporation, JumpBridge, SovereigntyMap, SovereigntyStructure,
+    SystemSovereignty,
+};
 use chrono::DateTime;
 use http_body_util::{BodyExt, Empty};
 use hyper::body::Body;
@@ -18,6 +21,9 @@ use uuid::Uuid;
 use self::player_database::PlayerDatabase;
 pub mod player_database;
 
+/// Base URL of the public ESI endpoints
+pub const ESI_URL: &str = "https://esi.evetech.net/";
+
 #[derive(Clone)]
 pub struct EsiManager {
     pub esi: Esi,
@@ -25,6 +31,13 @@ pub struct EsiManager {
     pub characters: Vec<Character>,
     pub path: String,
     pub active_character: Option<i32>,
+    pub user_agent: String,
+    /// Base URL used by the endpoints that do not need authentication, it can point to
+    /// a local server that stands in for ESI
+    pub public_url: String,
+    /// Error of the player database update made on start, the jump bridges and the
+    /// sovereignty can not be used while it is set
+    pub database_error: Option<String>,
 }
 
 impl EsiManager {
@@ -48,8 +61,8 @@ impl EsiManager {
         {
             let uuid = Uuid::new_v5(&Uuid::NAMESPACE_OID, "telescope".as_bytes());
             let query = ["PRAGMA key = '", uuid.to_string().as_str(), "'"].concat();
-            let mut statement = connection.prepare(query.as_str())?;
-            let _ = statement.execute([])?;
+            // sqlcipher answers the key with a row, that `execute` takes as an error
+            connection.execute_batch(query.as_str())?;
         }
 
         statement.finalize()?;
@@ -248,6 +261,94 @@ impl EsiManager {
         Ok(result)
     }
 
+    // Jump bridges
+    /// Stores the jump bridges, replacing the ones between the same Solar Systems
+    pub fn write_bridges(&mut self, bridges: &[JumpBridge]) -> Result<usize, Error> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let conn = self.get_standard_connection()?;
+        let mut rows = 0;
+        for bridge in bridges {
+            rows += PlayerDatabase::insert_bridge(&conn, bridge)?;
+        }
+        Ok(rows)
+    }
+
+    /// Returns the jump bridges that have not expired, the expired ones are deleted
+    pub fn read_bridges(&mut self) -> Result<Vec<JumpBridge>, Error> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let conn = self.get_standard_connection()?;
+        PlayerDatabase::delete_expired_bridges(&conn, chrono::Utc::now())?;
+        PlayerDatabase::select_bridges(&conn)
+    }
+
+    /// Deletes the jump bridges between the pairs of Solar Systems
+    pub fn remove_bridges(&mut self, bridges: Vec<(i32, i32)>) -> Result<usize, Error> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let conn = self.get_standard_connection()?;
+        PlayerDatabase::delete_bridges(&conn, bridges)
+    }
+
+    /// Deletes every jump bridge
+    pub fn clear_bridges(&mut self) -> Result<usize, Error> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let conn = self.get_standard_connection()?;
+        PlayerDatabase::delete_all_bridges(&conn)
+    }
+
+    // Sovereignty
+    /// Returns the sovereignty stored the last time it was downloaded
+    pub fn read_sovereignty(&mut self) -> Result<SovereigntyMap, Error> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let conn = self.get_standard_connection()?;
+        PlayerDatabase::select_sovereignty(&conn)
+    }
+
+    /// Downloads the owners and the sovereignty structures of every Solar System from
+    /// the public endpoints at `public_url` and stores them, replacing the previous ones
+    pub async fn update_sovereignty(
+        &mut self,
+    ) -> Result<SovereigntyMap, Box<dyn std::error::Error + Send + Sync>> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let esi = EsiBuilder::new()
+            .user_agent(&self.user_agent)
+            .base_api_url(&self.public_url)
+            .build()?;
+        let systems: Vec<SystemSovereignty> = esi
+            .query(
+                "GET",
+                RequestType::Public,
+                "latest/sovereignty/map/",
+                None,
+                None,
+            )
+            .await?;
+        let structures: Vec<SovereigntyStructure> = esi
+            .query(
+                "GET",
+                RequestType::Public,
+                "latest/sovereignty/structures/",
+                None,
+                None,
+            )
+            .await?;
+        let sovereignty = SovereigntyMap::from_esi(systems, structures, chrono::Utc::now());
+        let conn = self.get_standard_connection()?;
+        PlayerDatabase::replace_sovereignty(&conn, &sovereignty)?;
+        Ok(sovereignty)
+    }
+
     pub fn new(
         useragent: &str,
         client_id: &str,
@@ -285,18 +386,22 @@ impl EsiManager {
             characters: Vec::new(),
             path: database_path,
             active_character: None,
+            user_agent: useragent.to_string(),
+            public_url: String::from(ESI_URL),
+            database_error: None,
         };
 
         // Path needs to be checked before invoking rusqlite to be effective
         let temp_path = Path::new(&obj.path);
         if !temp_path.exists() || !temp_path.is_file() {
             let conn = obj.get_standard_connection().unwrap();
-            if let Ok(true) = PlayerDatabase::create_database(&conn) {
-                let _ = PlayerDatabase::migrate_database();
-            }
+            let _ = PlayerDatabase::create_database(&conn);
             let _ = conn.close();
         }
         if let Ok(conn) = obj.get_standard_connection() {
+            if let Err(t_error) = PlayerDatabase::migrate_database(&conn) {
+                obj.database_error = Some(t_error.to_string());
+            }
             // load existing players
             if let Ok(chars) = PlayerDatabase::select_characters(&conn, vec![]) {
                 obj.characters = chars;
@@ -475,3 +580,132 @@ impl EsiManager {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http_body_util::Full;
+    use hyper::body::Incoming;
+    use hyper::server::conn::http1;
+    use hyper::service::service_fn;
+    use hyper::{Request, Response, StatusCode};
+    use hyper_util::rt::TokioIo;
+    use std::convert::Infallible;
+    use tokio::net::TcpListener;
+
+    const SYSTEMS: &str = r#"[
+        {"system_id": 30004759, "alliance_id": 1354830081, "corporation_id": 1344654522},
+        {"system_id": 30002187, "faction_id": 500003},
+        {"system_id": 30000142}
+    ]"#;
+
+    const STRUCTURES: &str = r#"[
+        {
+            "alliance_id": 1354830081,
+            "solar_system_id": 30004759,
+            "structure_id": 1018253388776,
+            "structure_type_id": 32226,
+            "vulnerability_occupancy_level": 6.0,
+            "vulnerable_start_time": "2025-09-20T18:00:00Z",
+            "vulnerable_end_time": "2025-09-20T22:00:00Z"
+        },
+        {
+            "alliance_id": 99003581,
+            "solar_system_id": 30004708,
+            "structure_id": 1018253388777,
+            "structure_type_id": 32458
+        }
+    ]"#;
+
+    // stands in for the public ESI endpoints, returns the base URL to use as `public_url`
+    async fn serve() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let connection = http1::Builder::new()
+                    .serve_connection(TokioIo::new(stream), service_fn(respond));
+                tokio::spawn(connection);
+            }
+        });
+        format!("http://{address}/")
+    }
+
+    async fn respond(request: Request<Incoming>) -> Result<Response<Full<Bytes>>, Infallible> {
+        let body = match request.uri().path() {
+            "/latest/sovereignty/map/" => SYSTEMS,
+            "/latest/sovereignty/structures/" => STRUCTURES,
+            _ => {
+                let mut response =
+                    Response::new(Full::new(Bytes::from("{\"error\":\"Not found\"}")));
+                *response.status_mut() = StatusCode::NOT_FOUND;
+                return Ok(response);
+            }
+        };
+        let mut response = Response::new(Full::new(Bytes::from(body)));
+        response
+            .headers_mut()
+            .insert("content-type", "application/json".parse().unwrap());
+        Ok(response)
+    }
+
+    fn manager(name: &str) -> EsiManager {
+        let path = std::env::temp_dir().join(format!("webb-{name}-{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        EsiManager::new(
+            "telescope-test",
+            "client",
+            "secret",
+            "http://localhost/callback",
+            vec![],
+            path.to_string_lossy().to_string(),
+        )
+    }
+
+    #[tokio::test]
+    async fn downloads_and_stores_the_sovereignty() {
+        let mut esi = manager("sovereignty");
+        assert_eq!(esi.read_sovereignty().unwrap().updated, None);
+
+        esi.public_url = serve().await;
+        let downloaded = esi.update_sovereignty().await.unwrap();
+        // systems without owner are only kept when they have structures
+        assert_eq!(downloaded.systems.len(), 3);
+        assert!(downloaded.updated.is_some());
+
+        let stored = esi.read_sovereignty().unwrap();
+        assert_eq!(stored.systems.len(), 3);
+        let system = &stored.systems[&30004759];
+        assert_eq!(system.alliance_id, Some(1354830081));
+        assert_eq!(system.corporation_id, Some(1344654522));
+        assert_eq!(system.structures.len(), 1);
+        let structure = &system.structures[0];
+        assert_eq!(structure.structure_type_id, 32226);
+        assert_eq!(structure.vulnerability_occupancy_level, Some(6.0));
+        assert_eq!(
+            structure.vulnerable_end_time,
+            Some("2025-09-20T22:00:00Z".parse().unwrap())
+        );
+        assert_eq!(stored.systems[&30002187].faction_id, Some(500003));
+        assert_eq!(stored.systems[&30004708].alliance_id, None);
+        assert_eq!(
+            stored.systems[&30004708].structures[0].alliance_id,
+            99003581
+        );
+        assert_eq!(
+            stored.updated.map(|date| date.timestamp()),
+            downloaded.updated.map(|date| date.timestamp())
+        );
+        assert_eq!(stored.owners().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn failed_downloads_keep_the_stored_sovereignty() {
+        let mut esi = manager("sovereignty-missing");
+        esi.public_url = serve().await + "missing/";
+        assert!(esi.update_sovereignty().await.is_err());
+        let stored = esi.read_sovereignty().unwrap();
+        assert!(stored.systems.is_empty());
+        assert_eq!(stored.updated, None);
+    }
+}
@@ -1,10 +1,17 @@
 use crate::esi::Error;
-use crate::objects::{Alliance, AuthData, BasicCatalog, Character, Corporation};
+use crate::objects::{
+    Alliance, AuthData, BasicCatalog, Character, Corporation, JumpBridge, SovereigntyMap,
+    SovereigntyStructure, SystemSovereignty,
+};
 use chrono::{DateTime, Utc};
 use rusqlite::vtab::array;
-use rusqlite::{Connection, ToSql, params};
+use rusqlite::{Connection, OptionalExtension, ToSql, params};
 use std::rc::Rc;
 
+/// Version of the player database schema, the number of changes applied by
+/// `PlayerDatabase::migrate_database`
+pub(crate) const DATABASE_VERSION: u32 = 2;
+
 pub(crate) struct PlayerDatabase {}
 
 impl PlayerDatabase {
@@ -257,11 +264,54 @@ impl PlayerDatabase {
         s
     }
 
-    pub(crate) fn migrate_database() -> Result<bool, Error> {
+    /// Applies the schema changes made after the database was created, the `db` metadata
+    /// keeps the last one applied. Returns the version of the database after the changes.
+    pub(crate) fn migrate_database(conn: &Connection) -> Result<u32, Error> {
         #[cfg(feature = "puffin")]
         puffin::profile_function!();
-        // TODO: migration database schema goes here
-        Ok(true)
+
+        let mut statement = conn.prepare("SELECT value FROM metadata WHERE id = ?1")?;
+        let version: Option<String> = statement.query_row(["db"], |row| row.get(0)).optional()?;
+        // every step can be applied again, so a missing or unreadable version starts from
+        // the first
+        let version = version
+            .and_then(|value| value.parse::<u32>().ok())
+            .unwrap_or(0);
+        if version >= DATABASE_VERSION {
+            return Ok(version);
+        }
+
+        let transaction = conn.unchecked_transaction()?;
+        if version < 1 {
+            // version 1: jump bridges and other connections defined by the player
+            let mut query = String::from("CREATE TABLE IF NOT EXISTS bridge (");
+            query += " systemA INTEGER NOT NULL, systemB INTEGER NOT NULL,";
+            query += " label VARCHAR(255) NOT NULL, expiration DATETIME,";
+            query += " PRIMARY KEY (systemA, systemB))";
+            transaction.execute(&query, [])?;
+        }
+        if version < 2 {
+            // version 2: sovereignty downloaded from the public ESI endpoints
+            let mut query = String::from("CREATE TABLE IF NOT EXISTS sovSystem (");
+            query += " systemId INTEGER PRIMARY KEY, allianceId INTEGER,";
+            query += " corporationId INTEGER, factionId INTEGER)";
+            transaction.execute(&query, [])?;
+            let mut query = String::from("CREATE TABLE IF NOT EXISTS sovStructure (");
+            query += " structureId INTEGER PRIMARY KEY, systemId INTEGER NOT NULL,";
+            query += " typeId INTEGER NOT NULL, allianceId INTEGER NOT NULL, adm REAL,";
+            query += " vulnerableStart DATETIME, vulnerableEnd DATETIME)";
+            transaction.execute(&query, [])?;
+            transaction.execute(
+                "INSERT OR IGNORE INTO metadata (id,value) VALUES (?,?)",
+                ["sovereignty", ""],
+            )?;
+        }
+        transaction.execute(
+            "INSERT OR REPLACE INTO metadata (id,value) VALUES (?1,?2);",
+            params!["db", DATABASE_VERSION.to_string()],
+        )?;
+        transaction.commit()?;
+        Ok(DATABASE_VERSION)
     }
 
     pub(crate) fn delete_characters(conn: &Connection, ids: Vec<i32>) -> Result<usize, Error> {
@@ -271,6 +321,194 @@ impl PlayerDatabase {
         PlayerDatabase::delete_general(conn, "char", ids)
     }
 
+    // Jump bridges
+    pub(crate) fn select_bridges(conn: &Connection) -> Result<Vec<JumpBridge>, Error> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let mut result = Vec::new();
+        let query =
+            "SELECT systemA, systemB, label, expiration FROM bridge ORDER BY systemA, systemB";
+        let mut statement = conn.prepare(query)?;
+        let mut rows = statement.query([])?;
+        while let Some(row) = rows.next()? {
+            let mut bridge = JumpBridge::new(row.get(0)?, row.get(1)?);
+            bridge.label = row.get(2)?;
+            if let Some(date_as_string) = row.get::<usize, Option<String>>(3)?
+                && let Ok(utc_dt) = DateTime::parse_from_rfc3339(&date_as_string)
+            {
+                bridge.expiration = Some(utc_dt.to_utc());
+            }
+            result.push(bridge);
+        }
+        Ok(result)
+    }
+
+    pub(crate) fn insert_bridge(conn: &Connection, bridge: &JumpBridge) -> Result<usize, Error> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let mut query = String::from("INSERT OR REPLACE INTO bridge ");
+        query += "(systemA, systemB, label, expiration) VALUES (?1, ?2, ?3, ?4)";
+        let mut statement = conn.prepare(&query)?;
+        let rows = statement.execute(params![
+            bridge.system_a,
+            bridge.system_b,
+            bridge.label,
+            bridge.expiration.map(|date| date.to_rfc3339())
+        ])?;
+        Ok(rows)
+    }
+
+    pub(crate) fn delete_bridges(
+        conn: &Connection,
+        bridges: Vec<(i32, i32)>,
+    ) -> Result<usize, Error> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let mut statement =
+            conn.prepare("DELETE FROM bridge WHERE systemA = ?1 AND systemB = ?2")?;
+        let mut rows = 0;
+        for (system_a, system_b) in bridges {
+            rows += statement.execute(params![system_a.min(system_b), system_a.max(system_b)])?;
+        }
+        Ok(rows)
+    }
+
+    pub(crate) fn delete_all_bridges(conn: &Connection) -> Result<usize, Error> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        conn.execute("DELETE FROM bridge", [])
+    }
+
+    pub(crate) fn delete_expired_bridges(
+        conn: &Connection,
+        now: DateTime<Utc>,
+    ) -> Result<usize, Error> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let bridges = PlayerDatabase::select_bridges(conn)?
+            .into_iter()
+            .filter(|bridge| bridge.is_expired(now))
+            .map(|bridge| (bridge.system_a, bridge.system_b))
+            .collect::<Vec<_>>();
+        PlayerDatabase::delete_bridges(conn, bridges)
+    }
+
+    // Sovereignty
+    pub(crate) fn select_sovereignty(conn: &Connection) -> Result<SovereigntyMap, Error> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let mut result = SovereigntyMap::new();
+        let query = "SELECT systemId, allianceId, corporationId, factionId FROM sovSystem";
+        let mut statement = conn.prepare(query)?;
+        let mut rows = statement.query([])?;
+        while let Some(row) = rows.next()? {
+            let mut system = SystemSovereignty::new(row.get(0)?);
+            system.alliance_id = row.get(1)?;
+            system.corporation_id = row.get(2)?;
+            system.faction_id = row.get(3)?;
+            result.systems.insert(system.system_id, system);
+        }
+
+        let mut query = String::from("SELECT structureId, systemId, typeId, allianceId, adm,");
+        query += " vulnerableStart, vulnerableEnd FROM sovStructure ORDER BY structureId";
+        let mut statement = conn.prepare(&query)?;
+        let mut rows = statement.query([])?;
+        while let Some(row) = rows.next()? {
+            let structure = SovereigntyStructure {
+                structure_id: row.get(0)?,
+                solar_system_id: row.get(1)?,
+                structure_type_id: row.get(2)?,
+                alliance_id: row.get(3)?,
+                vulnerability_occupancy_level: row.get(4)?,
+                vulnerable_start_time: PlayerDatabase::parse_datetime(row.get(5)?),
+                vulnerable_end_time: PlayerDatabase::parse_datetime(row.get(6)?),
+            };
+            result
+                .systems
+                .entry(structure.solar_system_id)
+                .or_insert_with(|| SystemSovereignty::new(structure.solar_system_id))
+                .structures
+                .push(structure);
+        }
+
+        let mut statement = conn.prepare("SELECT value FROM metadata WHERE id = ?1")?;
+        let mut rows = statement.query(["sovereignty"])?;
+        if let Some(row) = rows.next()? {
+            result.updated = PlayerDatabase::parse_datetime(row.get(0)?);
+        }
+        Ok(result)
+    }
+
+    /// Replaces the stored sovereignty with the given one in a single transaction
+    pub(crate) fn replace_sovereignty(
+        conn: &Connection,
+        sovereignty: &SovereigntyMap,
+    ) -> Result<usize, Error> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let transaction = conn.unchecked_transaction()?;
+        transaction.execute("DELETE FROM sovStructure", [])?;
+        transaction.execute("DELETE FROM sovSystem", [])?;
+        let mut rows = 0;
+        {
+            let mut query = String::from("INSERT INTO sovSystem ");
+            query += "(systemId, allianceId, corporationId, factionId) VALUES (?1, ?2, ?3, ?4)";
+            let mut system_statement = transaction.prepare(&query)?;
+            let mut query = String::from("INSERT OR REPLACE INTO sovStructure (structureId,");
+            query += " systemId, typeId, allianceId, adm, vulnerableStart, vulnerableEnd)";
+            query += " VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)";
+            let mut structure_statement = transaction.prepare(&query)?;
+            for system in sovereignty.systems.values() {
+                rows += system_statement.execute(params![
+                    system.system_id,
+                    system.alliance_id,
+                    system.corporation_id,
+                    system.faction_id
+                ])?;
+                for structure in &system.structures {
+                    rows += structure_statement.execute(params![
+                        structure.structure_id,
+                        structure.solar_system_id,
+                        structure.structure_type_id,
+                        structure.alliance_id,
+                        structure.vulnerability_occupancy_level,
+                        structure
+                            .vulnerable_start_time
+                            .map(|date| date.to_rfc3339()),
+                        structure.vulnerable_end_time.map(|date| date.to_rfc3339())
+                    ])?;
+                }
+            }
+        }
+        transaction.execute(
+            "UPDATE metadata SET value = ?1 WHERE id = ?2",
+            params![
+                sovereignty
+                    .updated
+                    .map(|date| date.to_rfc3339())
+                    .unwrap_or_default(),
+                "sovereignty"
+            ],
+        )?;
+        transaction.commit()?;
+        Ok(rows)
+    }
+
+    fn parse_datetime(text: Option<String>) -> Option<DateTime<Utc>> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        text.and_then(|date_as_string| DateTime::parse_from_rfc3339(&date_as_string).ok())
+            .map(|utc_dt| utc_dt.to_utc())
+    }
+
     // Corporation
     pub(crate) fn select_corporation(
         conn: &Connection,
@@ -427,3 +665,93 @@ impl PlayerDatabase {
         Ok(rows)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tables(conn: &Connection) -> Vec<String> {
+        let mut statement = conn
+            .prepare("SELECT name FROM sqlite_master WHERE type = 'table' ORDER BY name")
+            .unwrap();
+        statement
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<Result<Vec<String>, _>>()
+            .unwrap()
+    }
+
+    fn version(conn: &Connection) -> String {
+        conn.query_row("SELECT value FROM metadata WHERE id = 'db'", [], |row| {
+            row.get(0)
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn migrates_new_databases() {
+        let conn = Connection::open_in_memory().unwrap();
+        PlayerDatabase::create_database(&conn).unwrap();
+        assert_eq!(
+            PlayerDatabase::migrate_database(&conn).unwrap(),
+            DATABASE_VERSION
+        );
+        assert_eq!(version(&conn), DATABASE_VERSION.to_string());
+        let found = tables(&conn);
+        for table in ["bridge", "sovSystem", "sovStructure"] {
+            assert!(found.iter().any(|name| name == table), "{table}");
+        }
+        let sovereignty = PlayerDatabase::select_sovereignty(&conn).unwrap();
+        assert!(sovereignty.systems.is_empty());
+        assert_eq!(sovereignty.updated, None);
+    }
+
+    #[test]
+    fn applies_only_the_missing_steps() {
+        let conn = Connection::open_in_memory().unwrap();
+        PlayerDatabase::create_database(&conn).unwrap();
+        conn.execute("UPDATE metadata SET value = '1' WHERE id = 'db'", [])
+            .unwrap();
+        PlayerDatabase::migrate_database(&conn).unwrap();
+        let found = tables(&conn);
+        assert!(!found.iter().any(|name| name == "bridge"));
+        assert!(found.iter().any(|name| name == "sovSystem"));
+
+        // an up to date database is not changed
+        conn.execute("DROP TABLE sovSystem", []).unwrap();
+        assert_eq!(
+            PlayerDatabase::migrate_database(&conn).unwrap(),
+            DATABASE_VERSION
+        );
+        assert!(!tables(&conn).iter().any(|name| name == "sovSystem"));
+    }
+
+    #[test]
+    fn missing_versions_start_from_the_first_step() {
+        let conn = Connection::open_in_memory().unwrap();
+        PlayerDatabase::create_database(&conn).unwrap();
+        conn.execute("DELETE FROM metadata WHERE id = 'db'", [])
+            .unwrap();
+        assert_eq!(
+            PlayerDatabase::migrate_database(&conn).unwrap(),
+            DATABASE_VERSION
+        );
+        assert_eq!(version(&conn), DATABASE_VERSION.to_string());
+        assert!(tables(&conn).iter().any(|name| name == "bridge"));
+    }
+
+    #[test]
+    fn failed_migrations_are_reported() {
+        let conn = Connection::open_in_memory().unwrap();
+        assert!(PlayerDatabase::migrate_database(&conn).is_err());
+
+        // the steps are rolled back together with the version
+        PlayerDatabase::create_database(&conn).unwrap();
+        let mut query = String::from("CREATE TRIGGER readOnly BEFORE INSERT ON metadata");
+        query += " BEGIN SELECT RAISE(ABORT, 'read only'); END";
+        conn.execute(&query, []).unwrap();
+        assert!(PlayerDatabase::migrate_database(&conn).is_err());
+        assert_eq!(version(&conn), "0");
+        assert!(!tables(&conn).iter().any(|name| name == "bridge"));
+    }
+}
@@ -1,5 +1,7 @@
 use chrono::prelude::*;
 use rusqlite::Error;
+use serde::Deserialize;
+use std::collections::HashMap;
 
 pub trait EsiObject {
     fn retrieve() -> Result<bool, Error>;
@@ -159,9 +161,322 @@ impl BasicCatalog for Alliance {
     }
 }
 
+/// Connection between two Solar Systems that is not a CCP stargate, like an Ansiblex
+/// jump bridge or a known wormhole. The systems are stored with the lowest identifier
+/// first, so the same connection always has the same key.
+#[derive(Clone, PartialEq, Debug)]
+pub struct JumpBridge {
+    pub system_a: i32,
+    pub system_b: i32,
+    /// Free text shown to the user, like the structure name
+    pub label: String,
+    /// Moment when the connection stops existing, None for permanent connections
+    pub expiration: Option<DateTime<Utc>>,
+}
+
+impl JumpBridge {
+    pub fn new(system_a: i32, system_b: i32) -> Self {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        JumpBridge {
+            system_a: system_a.min(system_b),
+            system_b: system_a.max(system_b),
+            label: String::new(),
+            expiration: None,
+        }
+    }
+
+    /// Returns true if the connection expired before the given moment
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        self.expiration.is_some_and(|expiration| expiration <= now)
+    }
+
+    /// Reads jump bridges from the tab-separated text copied from the structure browser
+    /// or shared by alliances, one bridge per line with a column like
+    /// `1DQ1-A » 8WA-Z6 - Label`. A column with a date, as `2025.10.20 18:30` or in
+    /// RFC 3339, is used as the expiration, otherwise the default one is used.
+    ///
+    /// The Solar System names are turned into identifiers with `resolve`. The lines that
+    /// can not be read are returned with the bridges, so they can be shown to the user.
+    pub fn from_paste<F>(
+        text: &str,
+        resolve: F,
+        default_expiration: Option<DateTime<Utc>>,
+    ) -> (Vec<JumpBridge>, Vec<String>)
+    where
+        F: Fn(&str) -> Option<i32>,
+    {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let mut bridges = Vec::new();
+        let mut rejected = Vec::new();
+        for line in text.lines().filter(|line| !line.trim().is_empty()) {
+            let columns: Vec<&str> = line.split('\t').map(str::trim).collect();
+            let Some((origin, rest)) = columns.iter().find_map(|column| column.split_once('»'))
+            else {
+                rejected.push(line.to_string());
+                continue;
+            };
+            let (destination, label) = rest.split_once(" - ").unwrap_or((rest, ""));
+            let (Some(system_a), Some(system_b)) =
+                (resolve(origin.trim()), resolve(destination.trim()))
+            else {
+                rejected.push(line.to_string());
+                continue;
+            };
+            if system_a == system_b {
+                rejected.push(line.to_string());
+                continue;
+            }
+            let mut bridge = JumpBridge::new(system_a, system_b);
+            bridge.label = label.trim().to_string();
+            bridge.expiration = columns
+                .iter()
+                .find_map(|column| Self::parse_date(column))
+                .or(default_expiration);
+            // both directions of a bridge are the same connection, the last line is kept
+            match bridges.iter_mut().find(|known: &&mut JumpBridge| {
+                known.system_a == bridge.system_a && known.system_b == bridge.system_b
+            }) {
+                Some(known) => *known = bridge,
+                None => bridges.push(bridge),
+            }
+        }
+        (bridges, rejected)
+    }
+
+    fn parse_date(text: &str) -> Option<DateTime<Utc>> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        if let Ok(date) = DateTime::parse_from_rfc3339(text) {
+            return Some(date.to_utc());
+        }
+        [
+            "%Y.%m.%d %H:%M",
+            "%Y-%m-%d %H:%M",
+            "%Y.%m.%d %H:%M:%S",
+            "%Y-%m-%d %H:%M:%S",
+        ]
+        .into_iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(text, format).ok())
+        .map(|date| date.and_utc())
+    }
+}
+
+/// Sovereignty structure anchored in a Solar System, as returned by the public
+/// `/sovereignty/structures/` endpoint
+#[derive(Clone, PartialEq, Debug, Deserialize)]
+pub struct SovereigntyStructure {
+    pub structure_id: i64,
+    pub structure_type_id: i32,
+    pub alliance_id: i32,
+    pub solar_system_id: i32,
+    /// Activity Defense Multiplier of the structure, it makes the vulnerability window
+    /// harder to hit
+    pub vulnerability_occupancy_level: Option<f32>,
+    pub vulnerable_start_time: Option<DateTime<Utc>>,
+    pub vulnerable_end_time: Option<DateTime<Utc>>,
+}
+
+impl SovereigntyStructure {
+    /// Returns true if the structure can be attacked at the given moment
+    pub fn is_vulnerable(&self, now: DateTime<Utc>) -> bool {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        match (self.vulnerable_start_time, self.vulnerable_end_time) {
+            (Some(start), Some(end)) => start <= now && now < end,
+            _ => false,
+        }
+    }
+}
+
+/// Owner of a Solar System, as returned by the public `/sovereignty/map/` endpoint.
+/// Player sovereignty has an alliance and a corporation, NPC null-sec only a faction.
+#[derive(Clone, PartialEq, Debug, Deserialize)]
+pub struct SystemSovereignty {
+    pub system_id: i32,
+    pub alliance_id: Option<i32>,
+    pub corporation_id: Option<i32>,
+    pub faction_id: Option<i32>,
+    /// Sovereignty structures anchored in the Solar System
+    #[serde(skip)]
+    pub structures: Vec<SovereigntyStructure>,
+}
+
+impl SystemSovereignty {
+    pub fn new(system_id: i32) -> Self {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        SystemSovereignty {
+            system_id,
+            alliance_id: None,
+            corporation_id: None,
+            faction_id: None,
+            structures: Vec::new(),
+        }
+    }
+
+    /// Returns the alliance holding the Solar System, or the faction when it is NPC
+    /// sovereignty
+    pub fn owner(&self) -> Option<i32> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        self.alliance_id.or(self.faction_id)
+    }
+}
+
+/// Sovereignty of every Solar System that has an owner, by Solar System identifier
+#[derive(Clone, PartialEq, Debug)]
+pub struct SovereigntyMap {
+    pub systems: HashMap<i32, SystemSovereignty>,
+    /// Moment when the data was downloaded from ESI, None if it never was
+    pub updated: Option<DateTime<Utc>>,
+}
+
+impl SovereigntyMap {
+    pub fn new() -> Self {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        SovereigntyMap {
+            systems: HashMap::new(),
+            updated: None,
+        }
+    }
+
+    /// Builds the map from the responses of both public endpoints. Structures in Solar
+    /// Systems without an owner are kept, with an empty owner.
+    pub fn from_esi(
+        systems: Vec<SystemSovereignty>,
+        structures: Vec<SovereigntyStructure>,
+        updated: DateTime<Utc>,
+    ) -> Self {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        let mut map = SovereigntyMap::new();
+        for system in systems {
+            // the endpoint lists every Solar System, most of them without owner
+            if system.owner().is_some() {
+                map.systems.insert(system.system_id, system);
+            }
+        }
+        for structure in structures {
+            map.systems
+                .entry(structure.solar_system_id)
+                .or_insert_with(|| SystemSovereignty::new(structure.solar_system_id))
+                .structures
+                .push(structure);
+        }
+        map.updated = Some(updated);
+        map
+    }
+
+    /// Returns the owner of every Solar System, see [`SystemSovereignty::owner`]
+    pub fn owners(&self) -> HashMap<i32, i32> {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        self.systems
+            .values()
+            .filter_map(|system| system.owner().map(|owner| (system.system_id, owner)))
+            .collect()
+    }
+}
+
+impl Default for SovereigntyMap {
+    fn default() -> Self {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+
+        Self::new()
+    }
+}
+
 pub trait BasicCatalog {
     type Output;
 
     fn id(&self) -> Self::Output;
     fn name(&self) -> &str;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resolve(name: &str) -> Option<i32> {
+        match name {
+            "1DQ1-A" => Some(30004759),
+            "8WA-Z6" => Some(30004708),
+            "T5ZI-S" => Some(30004739),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn reads_pasted_bridges() {
+        let text = "1DQ1-A » 8WA-Z6 - Imperial Palace\t2025.10.20 18:30\n\n\
+                    Structure\tT5ZI-S » 1DQ1-A\tAnsiblex\n";
+        let (bridges, rejected) = JumpBridge::from_paste(text, resolve, None);
+        assert!(rejected.is_empty());
+        assert_eq!(bridges.len(), 2);
+        assert_eq!(
+            (bridges[0].system_a, bridges[0].system_b),
+            (30004708, 30004759)
+        );
+        assert_eq!(bridges[0].label, "Imperial Palace");
+        assert_eq!(
+            bridges[0].expiration,
+            Some(Utc.with_ymd_and_hms(2025, 10, 20, 18, 30, 0).unwrap())
+        );
+        assert_eq!(
+            (bridges[1].system_a, bridges[1].system_b),
+            (30004739, 30004759)
+        );
+        assert_eq!(bridges[1].label, "");
+    }
+
+    #[test]
+    fn uses_the_default_expiration_and_rfc_3339_dates() {
+        let default = Utc.with_ymd_and_hms(2030, 1, 1, 0, 0, 0).unwrap();
+        let text = "1DQ1-A » 8WA-Z6\nT5ZI-S » 8WA-Z6\t2025-10-20T18:30:00+02:00\n";
+        let (bridges, _) = JumpBridge::from_paste(text, resolve, Some(default));
+        assert_eq!(bridges[0].expiration, Some(default));
+        assert_eq!(
+            bridges[1].expiration,
+            Some(Utc.with_ymd_and_hms(2025, 10, 20, 16, 30, 0).unwrap())
+        );
+        assert!(bridges[1].is_expired(default));
+        assert!(!bridges[0].is_expired(default - chrono::Duration::seconds(1)));
+        assert!(!JumpBridge::new(1, 2).is_expired(default));
+    }
+
+    #[test]
+    fn both_directions_are_one_bridge() {
+        let text = "1DQ1-A » 8WA-Z6 - Out\n8WA-Z6 » 1DQ1-A - Back\n";
+        let (bridges, rejected) = JumpBridge::from_paste(text, resolve, None);
+        assert!(rejected.is_empty());
+        assert_eq!(bridges.len(), 1);
+        assert_eq!(bridges[0].label, "Back");
+    }
+
+    #[test]
+    fn returns_the_lines_that_can_not_be_read() {
+        let text = "no bridge here\nJita » 8WA-Z6\n1DQ1-A » 1DQ1-A\n";
+        let (bridges, rejected) = JumpBridge::from_paste(text, resolve, None);
+        assert!(bridges.is_empty());
+        assert_eq!(
+            rejected,
+            vec!["no bridge here", "Jita » 8WA-Z6", "1DQ1-A » 1DQ1-A"]
+        );
+    }
+}